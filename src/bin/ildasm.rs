@@ -0,0 +1,69 @@
+//! Prints the ildasm style disassembly of the methods in an assembly.
+//!
+//! ```text
+//! recil-ildasm <assembly> [filter]
+//! ```
+//!
+//! When a filter is given only methods whose qualified name, `Namespace.Type::Method`, contains
+//! it are printed.  Methods that fail to disassemble are listed as an error comment.
+
+use anyhow::{anyhow, Context, Result};
+use recil::{
+  ecma335::{
+    tables::{MethodDefRowId, RowIndex},
+    Md,
+  },
+  il::Disassembler,
+};
+use std::io::{self, Write};
+
+fn main() -> Result<()> {
+  let mut args = std::env::args().skip(1);
+  let path = match args.next() {
+    Some(path) => path,
+    None => {
+      eprintln!("Usage: recil-ildasm <assembly> [filter]");
+      std::process::exit(2);
+    }
+  };
+  let filter = args.next();
+
+  let buf = std::fs::read(&path).with_context(|| format!("Reading `{}`", path))?;
+  let md = Md::parse_from_pe(&buf)?.ok_or_else(|| anyhow!("`{}` has no CLI metadata", path))?;
+  let disassembler = Disassembler::new(&md)?;
+
+  let stdout = io::stdout();
+  let mut out = io::BufWriter::new(stdout.lock());
+  let mut index = MethodDefRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    let method = match disassembler.method(id) {
+      Ok(method) => method,
+      Err(err) => {
+        writeln!(
+          out,
+          "// Error disassembling method {}: {:#}\n",
+          id.token(),
+          err
+        )?;
+        index = id.next();
+        continue;
+      }
+    };
+
+    let selected = match &filter {
+      Some(filter) => method.qualified_name().contains(filter.as_str()),
+      None => true,
+    };
+
+    if selected {
+      writeln!(out, "{}\n", method)?;
+    }
+
+    index = id.next();
+  }
+
+  out.flush()?;
+
+  Ok(())
+}
@@ -1,21 +1,48 @@
 //! ECMA-335 Metadata Format.
 
+pub mod attributes;
 pub mod blobs;
+pub mod body;
+pub mod compressed;
+pub mod constants;
+pub mod enc;
 pub mod guids;
+pub mod marshal;
+pub mod pdb;
+pub mod security;
+pub mod signatures;
 pub mod strings;
 pub mod tables;
-
-use self::{blobs::Blobs, guids::Guids, strings::Strings, tables::Tables};
+pub mod token;
+pub mod user_strings;
+
+use self::{
+  blobs::Blobs,
+  guids::Guids,
+  pdb::PdbStream,
+  strings::Strings,
+  tables::{ResolutionScope, Tables, TypeDefOrRef, TypeDefRowId, TypeRefRowId},
+  user_strings::UserStrings,
+};
+use crate::pe::PeImage;
+use alloc::{format, string::String};
 use anyhow::{anyhow, bail, Error, Result};
 use core::ffi::CStr;
 use scroll::{ctx::TryFromCtx, Pread, LE};
 
+/// How deeply types may be nested, bounding the walks through the enclosing types and resolution
+/// scopes of cyclic metadata.
+pub(crate) const MAX_NESTING: usize = 64;
+
 /// Partially decoded ECMA-335 metadata.
 pub struct Md<'a> {
   guids: Guids<'a>,
   blobs: Blobs<'a>,
   tables: Tables<'a>,
   strings: Strings<'a>,
+  user_strings: UserStrings<'a>,
+  pdb: Option<PdbStream>,
+  image: Option<PeImage<'a>>,
 }
 
 impl<'a> Md<'a> {
@@ -39,10 +66,20 @@ impl<'a> Md<'a> {
       .map(Blobs::parse_from_header)
       .unwrap_or(Ok(Default::default()))?;
 
+    let pdb = header
+      .streams
+      .pdb
+      .map(PdbStream::parse_from_header)
+      .transpose()?;
+
     let tables = header
       .streams
       .tables
-      .map(Tables::parse_from_header)
+      .map(|tables| match (&pdb, header.streams.minimal_delta) {
+        (Some(pdb), _) => Tables::parse_pdb_from_header(tables, pdb),
+        (None, true) => Tables::parse_minimal_delta_from_header(tables),
+        (None, false) => Tables::parse_from_header(tables),
+      })
       .unwrap_or(Ok(Default::default()))?;
 
     let strings = header
@@ -51,33 +88,206 @@ impl<'a> Md<'a> {
       .map(Strings::parse_from_header)
       .unwrap_or(Ok(Default::default()))?;
 
+    let user_strings = header
+      .streams
+      .user_strings
+      .map(UserStrings::parse_from_header)
+      .unwrap_or(Ok(Default::default()))?;
+
     Ok(Self {
       guids,
       blobs,
       tables,
       strings,
+      user_strings,
+      pdb,
+      image: None,
     })
   }
 
+  /// Attaches the PE image the metadata was loaded from.
+  pub(crate) fn with_image(self, image: PeImage<'a>) -> Self {
+    Self {
+      image: Some(image),
+      ..self
+    }
+  }
+
   /// Gets the guids metadata stream.
-  pub fn guids(&self) -> &Guids {
+  pub fn guids(&self) -> &Guids<'a> {
     &self.guids
   }
 
   /// Gets the blobs metadata stream.
-  pub fn blobs(&self) -> &Blobs {
+  pub fn blobs(&self) -> &Blobs<'a> {
     &self.blobs
   }
 
   /// Gets the tables metadata stream.
-  pub fn tables(&self) -> &Tables {
+  pub fn tables(&self) -> &Tables<'a> {
     &self.tables
   }
 
   /// Gets the strings metadata stream.
-  pub fn strings(&self) -> &Strings {
+  pub fn strings(&self) -> &Strings<'a> {
     &self.strings
   }
+
+  /// Gets the user strings metadata stream.
+  pub fn user_strings(&self) -> &UserStrings<'a> {
+    &self.user_strings
+  }
+
+  /// Gets the PE image the metadata was loaded from, `None` if it was parsed from bare metadata.
+  pub fn image(&self) -> Option<&PeImage<'a>> {
+    self.image.as_ref()
+  }
+
+  /// Creates metadata without any streams for unit tests.
+  #[cfg(test)]
+  pub(crate) fn empty() -> Md<'static> {
+    #[rustfmt::skip]
+    const ROOT: &[u8] = &[
+      // Magic, major and minor version, reserved
+      0x42, 0x53, 0x4a, 0x42, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+      // Version
+      0x04, 0x00, 0x00, 0x00, b'v', b'4', 0x00, 0x00,
+      // Flags, streams
+      0x00, 0x00, 0x00, 0x00,
+    ];
+
+    Md::from_cli_data(ROOT).unwrap()
+  }
+
+  /// Builds a metadata root for unit tests with a `#~` stream holding the given `(table id, rows,
+  /// row bytes)` tables, in ascending id order, and `#Strings` and `#Blob` heaps.
+  #[cfg(test)]
+  pub(crate) fn root_with_tables(
+    tables: &[(usize, u32, &[u8])],
+    strings: &[u8],
+    blobs: &[u8],
+  ) -> Vec<u8> {
+    // Reserved, major and minor version, heap sizes, reserved
+    let mut stream = alloc::vec![0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01];
+    let valid = tables.iter().fold(0u64, |valid, &(id, ..)| valid | 1 << id);
+    stream.extend_from_slice(&valid.to_le_bytes());
+    stream.extend_from_slice(&0u64.to_le_bytes());
+    for &(_, rows, _) in tables {
+      stream.extend_from_slice(&rows.to_le_bytes());
+    }
+    for &(.., data) in tables {
+      stream.extend_from_slice(data);
+    }
+
+    #[rustfmt::skip]
+    let mut root = alloc::vec![
+      // Magic, major and minor version, reserved
+      0x42, 0x53, 0x4a, 0x42, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+      // Version
+      0x04, 0x00, 0x00, 0x00, b'v', b'4', 0x00, 0x00,
+      // Flags, streams
+      0x00, 0x00, 0x03, 0x00,
+    ];
+
+    // The stream data follows the stream headers.
+    let tables_offset = root.len() + 12 + 20 + 16;
+    let strings_offset = tables_offset + stream.len();
+    let blobs_offset = strings_offset + strings.len();
+    root.extend_from_slice(&(tables_offset as u32).to_le_bytes());
+    root.extend_from_slice(&(stream.len() as u32).to_le_bytes());
+    root.extend_from_slice(b"#~\0\0");
+    root.extend_from_slice(&(strings_offset as u32).to_le_bytes());
+    root.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+    root.extend_from_slice(b"#Strings\0\0\0\0");
+    root.extend_from_slice(&(blobs_offset as u32).to_le_bytes());
+    root.extend_from_slice(&(blobs.len() as u32).to_le_bytes());
+    root.extend_from_slice(b"#Blob\0\0\0");
+    root.extend_from_slice(&stream);
+    root.extend_from_slice(strings);
+    root.extend_from_slice(blobs);
+
+    root
+  }
+
+  /// Gets the full name of a type definition or reference.
+  ///
+  /// Nested types are separated from their enclosing type by a `+`, e.g.
+  /// `System.Environment+SpecialFolder`.
+  pub fn type_name(&self, ty: TypeDefOrRef) -> Result<String> {
+    match ty {
+      TypeDefOrRef::TypeDef(id) => self.type_def_name(id),
+      TypeDefOrRef::TypeRef(id) => self.type_ref_name(id),
+      TypeDefOrRef::TypeSpec(_) => bail!("Type specifications don't have a name"),
+    }
+  }
+
+  /// Gets the full name of a type definition.
+  pub fn type_def_name(&self, id: TypeDefRowId) -> Result<String> {
+    self.nested_type_def_name(id, 0)
+  }
+
+  /// Gets the full name of a type definition nested `depth` types deep.
+  fn nested_type_def_name(&self, id: TypeDefRowId, depth: usize) -> Result<String> {
+    if depth > MAX_NESTING {
+      bail!(
+        "TypeDef {} is nested deeper than {} types",
+        id.row(),
+        MAX_NESTING
+      );
+    }
+
+    let row = self.tables().type_defs().read(id)?;
+    let name = self.strings().get(row.name)?;
+    let nested = self
+      .tables()
+      .nested_classes()
+      .find(&id.row(), |row| row.nested_class.row())?;
+
+    match nested {
+      Some(nested) => Ok(format!(
+        "{}+{}",
+        self.nested_type_def_name(nested.enclosing_class, depth + 1)?,
+        name
+      )),
+      None => Ok(full_name(self.strings().get(row.namespace)?, name)),
+    }
+  }
+
+  /// Gets the full name of a type reference.
+  pub fn type_ref_name(&self, id: TypeRefRowId) -> Result<String> {
+    self.nested_type_ref_name(id, 0)
+  }
+
+  /// Gets the full name of a type reference nested `depth` types deep.
+  fn nested_type_ref_name(&self, id: TypeRefRowId, depth: usize) -> Result<String> {
+    if depth > MAX_NESTING {
+      bail!(
+        "TypeRef {} is nested deeper than {} types",
+        id.row(),
+        MAX_NESTING
+      );
+    }
+
+    let row = self.tables().type_refs().read(id)?;
+    let name = self.strings().get(row.name)?;
+
+    match row.resolution_scope {
+      Some(ResolutionScope::TypeRef(enclosing)) => Ok(format!(
+        "{}+{}",
+        self.nested_type_ref_name(enclosing, depth + 1)?,
+        name
+      )),
+      _ => Ok(full_name(self.strings().get(row.namespace)?, name)),
+    }
+  }
+}
+
+/// Joins a namespace and a type name.
+fn full_name(namespace: &str, name: &str) -> String {
+  match namespace.is_empty() {
+    true => name.into(),
+    false => format!("{}.{}", namespace, name),
+  }
 }
 
 /// The magic number for the metadata header.
@@ -135,6 +345,10 @@ pub struct StreamHeaders<'a> {
   pub strings: Option<StreamHeader<'a>>,
   /// The `#US` stream header.
   pub user_strings: Option<StreamHeader<'a>>,
+  /// The `#Pdb` stream header of a portable PDB.
+  pub pdb: Option<StreamHeader<'a>>,
+  /// Whether there's a `#JTD` stream, marking the metadata as a minimal ENC delta.
+  pub minimal_delta: bool,
 }
 
 impl<'a> TryFromCtx<'a, &'a [u8]> for StreamHeaders<'a> {
@@ -158,6 +372,8 @@ impl<'a> TryFromCtx<'a, &'a [u8]> for StreamHeaders<'a> {
     let mut tables: Option<StreamHeader<'a>> = None;
     let mut strings: Option<StreamHeader<'a>> = None;
     let mut user_strings: Option<StreamHeader<'a>> = None;
+    let mut pdb: Option<StreamHeader<'a>> = None;
+    let mut minimal_delta = false;
 
     for _ in 0..count {
       let header = from.gread_with::<StreamHeader>(offset, md_buf)?;
@@ -177,6 +393,10 @@ impl<'a> TryFromCtx<'a, &'a [u8]> for StreamHeaders<'a> {
         b"#US" => {
           user_strings.get_or_insert(header);
         }
+        b"#Pdb" => {
+          pdb.get_or_insert(header);
+        }
+        b"#JTD" => minimal_delta = true,
         _ => {}
       };
     }
@@ -188,6 +408,8 @@ impl<'a> TryFromCtx<'a, &'a [u8]> for StreamHeaders<'a> {
         tables,
         strings,
         user_strings,
+        pdb,
+        minimal_delta,
       },
       *offset,
     ))
@@ -278,3 +500,67 @@ impl<'a> TryFromCtx<'a, FourByteBoundaryPadded> for &'a CStr {
     Ok((cstr, *offset))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    tables::{NestedClassRow, Row, TypeDefRow, TypeDefRowId, TypeRefRow, TypeRefRowId},
+    Md,
+  };
+
+  #[test]
+  fn cyclic_type_names() {
+    #[rustfmt::skip]
+    let type_defs = [
+      // flags, name, namespace, extends, field_list, method_list
+      0u8, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 5, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+    ];
+    // B is nested in C, and A and C in each other.
+    let nested_classes = [2u8, 0, 3, 0, 1, 0, 3, 0, 3, 0, 1, 0];
+    // TypeRef 1 is resolved in itself.
+    let type_refs = [0x07u8, 0x00, 0x01, 0x00, 0x00, 0x00];
+    let root = Md::root_with_tables(
+      &[
+        (TypeRefRow::ID, 1, &type_refs),
+        (TypeDefRow::ID, 3, &type_defs),
+        (NestedClassRow::ID, 3, &nested_classes),
+      ],
+      b"\0A\0B\0C\0",
+      &[],
+    );
+    let md = Md::from_cli_data(&root).unwrap();
+    let header = md.tables().header();
+
+    let id = TypeDefRowId::new(2, header).unwrap();
+    assert!(md.type_def_name(id).is_err());
+    let id = TypeRefRowId::new(1, header).unwrap();
+    assert!(md.type_ref_name(id).is_err());
+  }
+
+  #[test]
+  fn nested_type_names() {
+    #[rustfmt::skip]
+    let type_defs = [
+      // flags, name, namespace, extends, field_list, method_list
+      0u8, 0, 0, 0, 1, 0, 7, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 5, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+    ];
+    // C is nested in B, itself nested in A.
+    let nested_classes = [2u8, 0, 1, 0, 3, 0, 2, 0];
+    let root = Md::root_with_tables(
+      &[
+        (TypeDefRow::ID, 3, &type_defs),
+        (NestedClassRow::ID, 2, &nested_classes),
+      ],
+      b"\0A\0B\0C\0Ns\0",
+      &[],
+    );
+    let md = Md::from_cli_data(&root).unwrap();
+
+    let id = TypeDefRowId::new(3, md.tables().header()).unwrap();
+    assert_eq!(md.type_def_name(id).unwrap(), "Ns.A+B+C");
+  }
+}
@@ -0,0 +1,526 @@
+//! Custom attribute values (II.23.3).
+//!
+//! The blob referenced by a `CustomAttribute` row holds the arguments passed to the attribute's
+//! constructor followed by any named field and property assignments.  The blob isn't
+//! self-describing, the types of the fixed arguments come from the constructor's signature and
+//! enums are stored as their bare underlying integer, so decoding an enum defined in another
+//! assembly needs an [EnumResolver].
+
+use super::{
+  compressed::CompressedU32,
+  signatures::{read_element_type, MethodSig, TypeSig, MAX_DEPTH},
+  tables::{
+    flags::{ElementType, FieldAttributes},
+    CustomAttributeRow, CustomAttributeType, RowIndex, TypeDefOrRef,
+  },
+  Md,
+};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use anyhow::{bail, Context, Result};
+use scroll::{ctx::TryFromCtx, Endian, Pread, LE};
+
+/// The prolog of every custom attribute blob.
+const PROLOG: u16 = 0x0001;
+
+/// Provides the underlying type of enums that aren't defined in the current module.
+pub trait EnumResolver {
+  /// Gets the underlying integer type of the enum with the given full name, e.g.
+  /// `System.AttributeTargets` or `System.Diagnostics.DebuggableAttribute+DebuggingModes`, or
+  /// `None` if the enum is unknown.
+  fn underlying_type(&self, name: &str) -> Option<TypeSig>;
+}
+
+/// Doesn't resolve any enums.
+impl EnumResolver for () {
+  fn underlying_type(&self, _: &str) -> Option<TypeSig> {
+    None
+  }
+}
+
+impl<F: Fn(&str) -> Option<TypeSig>> EnumResolver for F {
+  fn underlying_type(&self, name: &str) -> Option<TypeSig> {
+    self(name)
+  }
+}
+
+/// A decoded custom attribute blob.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomAttributeValue {
+  /// The arguments passed to the constructor, in parameter order.
+  pub fixed_args: Vec<AttributeArg>,
+  /// The fields and properties assigned after construction.
+  pub named_args: Vec<NamedArg>,
+}
+
+impl CustomAttributeValue {
+  /// Gets the value assigned to the named field or property.
+  pub fn named(&self, name: &str) -> Option<&AttributeArg> {
+    self
+      .named_args
+      .iter()
+      .find(|arg| arg.name == name)
+      .map(|arg| &arg.value)
+  }
+}
+
+/// A field or property assignment in a custom attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedArg {
+  /// `true` for a property, `false` for a field.
+  pub property: bool,
+  /// The name of the field or property.
+  pub name: String,
+  /// The declared type of the field or property.
+  pub ty: AttributeType,
+  /// The assigned value.
+  pub value: AttributeArg,
+}
+
+/// The type of a custom attribute argument.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+  /// `bool`
+  Boolean,
+  /// `char`
+  Char,
+  /// `int8`
+  I1,
+  /// `unsigned int8`
+  U1,
+  /// `int16`
+  I2,
+  /// `unsigned int16`
+  U2,
+  /// `int32`
+  I4,
+  /// `unsigned int32`
+  U4,
+  /// `int64`
+  I8,
+  /// `unsigned int64`
+  U8,
+  /// `float32`
+  R4,
+  /// `float64`
+  R8,
+  /// `string`
+  String,
+  /// `System.Type`, stored as the type's serialized name.
+  Type,
+  /// `object`, stored as a boxed value preceded by its type.
+  Object,
+  /// An enum with the given full name, possibly assembly qualified.
+  Enum(String),
+  /// A single dimensional, zero based array of the given type.
+  SzArray(Box<AttributeType>),
+}
+
+/// A custom attribute argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeArg {
+  Boolean(bool),
+  /// A UTF-16 code unit.
+  Char(u16),
+  I1(i8),
+  U1(u8),
+  I2(i16),
+  U2(u16),
+  I4(i32),
+  U4(u32),
+  I8(i64),
+  U8(u64),
+  R4(f32),
+  R8(f64),
+  /// A string, `None` if null.
+  String(Option<String>),
+  /// The serialized, possibly assembly qualified name of a type, `None` if null.
+  Type(Option<String>),
+  /// An enum value with the given type name, holding the underlying integer.
+  Enum(String, Box<AttributeArg>),
+  /// A value passed to an `object` argument.
+  Boxed(Box<AttributeArg>),
+  /// An array with the given element type, `None` if null.
+  Array(AttributeType, Option<Vec<AttributeArg>>),
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the value of a custom attribute, failing on enums defined outside of this module.
+  pub fn custom_attribute(&self, row: &CustomAttributeRow) -> Result<CustomAttributeValue> {
+    self.custom_attribute_with(row, &())
+  }
+
+  /// Decodes the value of a custom attribute, using the given [EnumResolver] for enums defined
+  /// outside of this module.
+  pub fn custom_attribute_with<R: EnumResolver + ?Sized>(
+    &self,
+    row: &CustomAttributeRow,
+    resolver: &R,
+  ) -> Result<CustomAttributeValue> {
+    let ctor = match row.attribute_type {
+      CustomAttributeType::MethodDef(id) => self.tables().method_defs().read(id)?.signature,
+      CustomAttributeType::MemberRef(id) => self.tables().member_refs().read(id)?.signature,
+    };
+
+    let ctor = self.method_sig(ctor)?;
+    let mut decoder = Decoder::new(self, resolver, self.blobs().get(row.value)?);
+
+    decoder
+      .value(&ctor)
+      .with_context(|| format!("Custom attribute value at {:#x}", row.value.offset()))
+  }
+
+  /// Gets the underlying type of an enum defined in this module, or `None` if there's no such enum.
+  pub fn local_enum_type(&self, name: &str) -> Result<Option<TypeSig>> {
+    let simple_name = name.rsplit(['.', '+']).next().unwrap_or(name);
+    let type_defs = self.tables().type_defs();
+    let mut index = RowIndex::first(self.tables().header());
+
+    while let Some(id) = index {
+      let row = type_defs.read(id)?;
+      index = id.next();
+
+      if self.strings().get(row.name)? != simple_name || self.type_def_name(id)? != name {
+        continue;
+      }
+
+      match row.extends {
+        Some(extends) if self.type_name(extends)? == "System.Enum" => {}
+        _ => return Ok(None),
+      }
+
+      for field in self.tables().type_def_fields(id)? {
+        let field = self.tables().fields().read(field)?;
+
+        if !field.flags.contains(FieldAttributes::STATIC) {
+          return Ok(Some(self.field_sig(field.signature)?.ty));
+        }
+      }
+
+      bail!("Enum `{}` has no value field", name);
+    }
+
+    Ok(None)
+  }
+}
+
+/// Reads custom attribute arguments from a blob.
+pub(super) struct Decoder<'m, 'a, 'r, R: ?Sized> {
+  md: &'m Md<'a>,
+  resolver: &'r R,
+  buf: &'a [u8],
+  offset: usize,
+}
+
+impl<'m, 'a, 'r, R: EnumResolver + ?Sized> Decoder<'m, 'a, 'r, R> {
+  pub(super) fn new(md: &'m Md<'a>, resolver: &'r R, buf: &'a [u8]) -> Self {
+    Self {
+      md,
+      resolver,
+      buf,
+      offset: 0,
+    }
+  }
+
+  /// Gets the offset of the next unread byte.
+  pub(super) fn offset(&self) -> usize {
+    self.offset
+  }
+
+  fn value(&mut self, ctor: &MethodSig) -> Result<CustomAttributeValue> {
+    // A missing blob is equivalent to a constructor call without arguments.
+    if self.buf.is_empty() && ctor.params.is_empty() {
+      return Ok(Default::default());
+    }
+
+    let prolog = self.read::<u16>()?;
+    if prolog != PROLOG {
+      bail!("Bad custom attribute prolog {:#x}", prolog);
+    }
+
+    let fixed_args = ctor
+      .params
+      .iter()
+      .map(|param| {
+        let ty = self.param_type(param)?;
+        self.arg(&ty, 0)
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    let count = self.read::<u16>()?;
+    let named_args = (0..count)
+      .map(|_| self.named_arg())
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(CustomAttributeValue {
+      fixed_args,
+      named_args,
+    })
+  }
+
+  /// Maps the type of a constructor parameter to an attribute argument type.
+  fn param_type(&self, ty: &TypeSig) -> Result<AttributeType> {
+    Ok(match ty.strip_modifiers() {
+      TypeSig::Boolean => AttributeType::Boolean,
+      TypeSig::Char => AttributeType::Char,
+      TypeSig::I1 => AttributeType::I1,
+      TypeSig::U1 => AttributeType::U1,
+      TypeSig::I2 => AttributeType::I2,
+      TypeSig::U2 => AttributeType::U2,
+      TypeSig::I4 => AttributeType::I4,
+      TypeSig::U4 => AttributeType::U4,
+      TypeSig::I8 => AttributeType::I8,
+      TypeSig::U8 => AttributeType::U8,
+      TypeSig::R4 => AttributeType::R4,
+      TypeSig::R8 => AttributeType::R8,
+      TypeSig::String => AttributeType::String,
+      TypeSig::Object => AttributeType::Object,
+      TypeSig::SzArray(ty) => AttributeType::SzArray(Box::new(self.param_type(ty)?)),
+      // The only value types allowed as attribute parameters are enums.
+      TypeSig::ValueType(ty) => AttributeType::Enum(self.md.type_name(*ty)?),
+      TypeSig::Class(ty @ (TypeDefOrRef::TypeDef(_) | TypeDefOrRef::TypeRef(_)))
+        if self.md.type_name(*ty)? == "System.Type" =>
+      {
+        AttributeType::Type
+      }
+      ty => bail!("Unsupported custom attribute parameter type {:?}", ty),
+    })
+  }
+
+  /// Reads a `FieldOrPropType` nested `depth` types deep.
+  fn field_or_prop_type(&mut self, depth: usize) -> Result<AttributeType> {
+    if depth > MAX_DEPTH {
+      bail!(
+        "Malformed custom attribute, type nested deeper than {}",
+        MAX_DEPTH
+      );
+    }
+
+    let element = read_element_type(self.buf, &mut self.offset)?;
+
+    Ok(match element {
+      ElementType::ELEMENT_TYPE_BOOLEAN => AttributeType::Boolean,
+      ElementType::ELEMENT_TYPE_CHAR => AttributeType::Char,
+      ElementType::ELEMENT_TYPE_I1 => AttributeType::I1,
+      ElementType::ELEMENT_TYPE_U1 => AttributeType::U1,
+      ElementType::ELEMENT_TYPE_I2 => AttributeType::I2,
+      ElementType::ELEMENT_TYPE_U2 => AttributeType::U2,
+      ElementType::ELEMENT_TYPE_I4 => AttributeType::I4,
+      ElementType::ELEMENT_TYPE_U4 => AttributeType::U4,
+      ElementType::ELEMENT_TYPE_I8 => AttributeType::I8,
+      ElementType::ELEMENT_TYPE_U8 => AttributeType::U8,
+      ElementType::ELEMENT_TYPE_R4 => AttributeType::R4,
+      ElementType::ELEMENT_TYPE_R8 => AttributeType::R8,
+      ElementType::ELEMENT_TYPE_STRING => AttributeType::String,
+      ElementType::ELEMENT_TYPE_SYSTEM_TYPE => AttributeType::Type,
+      ElementType::ELEMENT_TYPE_BOXED => AttributeType::Object,
+      ElementType::ELEMENT_TYPE_ENUM => match self.ser_string()? {
+        Some(name) => AttributeType::Enum(name),
+        None => bail!("Malformed custom attribute, null enum type name"),
+      },
+      ElementType::ELEMENT_TYPE_SZARRAY => {
+        AttributeType::SzArray(Box::new(self.field_or_prop_type(depth + 1)?))
+      }
+      element => bail!(
+        "Malformed custom attribute, unexpected element type {:#x}",
+        element.bits()
+      ),
+    })
+  }
+
+  /// Reads a `NamedArg`.
+  pub(super) fn named_arg(&mut self) -> Result<NamedArg> {
+    let property = match read_element_type(self.buf, &mut self.offset)? {
+      ElementType::ELEMENT_TYPE_FIELD => false,
+      ElementType::ELEMENT_TYPE_PROPERTY => true,
+      element => bail!("Malformed named argument, unexpected {:#x}", element.bits()),
+    };
+
+    let ty = self.field_or_prop_type(0)?;
+    let name = match self.ser_string()? {
+      Some(name) => name,
+      None => bail!("Malformed named argument, null name"),
+    };
+
+    let value = self
+      .arg(&ty, 0)
+      .with_context(|| format!("Named argument `{}`", name))?;
+
+    Ok(NamedArg {
+      property,
+      name,
+      ty,
+      value,
+    })
+  }
+
+  /// Reads a `FixedArg` or the value of a `NamedArg`, nested `depth` values deep.
+  fn arg(&mut self, ty: &AttributeType, depth: usize) -> Result<AttributeArg> {
+    if depth > MAX_DEPTH {
+      bail!(
+        "Malformed custom attribute, value nested deeper than {}",
+        MAX_DEPTH
+      );
+    }
+
+    Ok(match ty {
+      AttributeType::Boolean => AttributeArg::Boolean(self.read::<u8>()? != 0),
+      AttributeType::Char => AttributeArg::Char(self.read()?),
+      AttributeType::I1 => AttributeArg::I1(self.read()?),
+      AttributeType::U1 => AttributeArg::U1(self.read()?),
+      AttributeType::I2 => AttributeArg::I2(self.read()?),
+      AttributeType::U2 => AttributeArg::U2(self.read()?),
+      AttributeType::I4 => AttributeArg::I4(self.read()?),
+      AttributeType::U4 => AttributeArg::U4(self.read()?),
+      AttributeType::I8 => AttributeArg::I8(self.read()?),
+      AttributeType::U8 => AttributeArg::U8(self.read()?),
+      AttributeType::R4 => AttributeArg::R4(self.read()?),
+      AttributeType::R8 => AttributeArg::R8(self.read()?),
+      AttributeType::String => AttributeArg::String(self.ser_string()?),
+      AttributeType::Type => AttributeArg::Type(self.ser_string()?),
+      AttributeType::Object => {
+        let ty = self.field_or_prop_type(depth + 1)?;
+        AttributeArg::Boxed(Box::new(self.arg(&ty, depth + 1)?))
+      }
+      AttributeType::Enum(name) => {
+        let ty = self.enum_type(name)?;
+        AttributeArg::Enum(name.clone(), Box::new(self.arg(&ty, depth + 1)?))
+      }
+      AttributeType::SzArray(ty) => {
+        let count = self.read::<u32>()?;
+        let values = match count {
+          u32::MAX => None,
+          // Every element takes at least a byte.
+          _ if count as usize > self.buf.len() - self.offset => {
+            bail!("Malformed custom attribute, array of {} elements", count)
+          }
+          _ => Some(
+            (0..count)
+              .map(|_| self.arg(ty, depth + 1))
+              .collect::<Result<_>>()?,
+          ),
+        };
+
+        AttributeArg::Array((**ty).clone(), values)
+      }
+    })
+  }
+
+  /// Reads a little endian primitive.
+  pub(super) fn read<T>(&mut self) -> Result<T>
+  where
+    T: TryFromCtx<'a, Endian, Error = scroll::Error>,
+  {
+    Ok(self.buf.gread_with(&mut self.offset, LE)?)
+  }
+
+  /// Reads a compressed unsigned integer.
+  pub(super) fn compressed_u32(&mut self) -> Result<u32> {
+    Ok(self.buf.gread::<CompressedU32>(&mut self.offset)?.0)
+  }
+
+  /// Reads a `SerString`, a compressed length followed by UTF-8, `None` if the length is `0xff`.
+  pub(super) fn ser_string(&mut self) -> Result<Option<String>> {
+    if self.buf.pread::<u8>(self.offset)? == 0xff {
+      self.offset += 1;
+      return Ok(None);
+    }
+
+    let len = self.compressed_u32()? as usize;
+    let bytes = match len {
+      0 => &[][..],
+      _ => self.buf.gread_with::<&[u8]>(&mut self.offset, len)?,
+    };
+
+    match core::str::from_utf8(bytes) {
+      Ok(value) => Ok(Some(value.into())),
+      Err(err) => bail!("Malformed custom attribute string, {}", err),
+    }
+  }
+
+  /// Gets the underlying type of the enum with the given, possibly assembly qualified, name.
+  fn enum_type(&self, name: &str) -> Result<AttributeType> {
+    let name = strip_assembly(name);
+    let ty = match self.md.local_enum_type(name)? {
+      Some(ty) => Some(ty),
+      None => self.resolver.underlying_type(name),
+    };
+
+    Ok(match ty {
+      Some(TypeSig::Boolean) => AttributeType::Boolean,
+      Some(TypeSig::Char) => AttributeType::Char,
+      Some(TypeSig::I1) => AttributeType::I1,
+      Some(TypeSig::U1) => AttributeType::U1,
+      Some(TypeSig::I2) => AttributeType::I2,
+      Some(TypeSig::U2) => AttributeType::U2,
+      Some(TypeSig::I4) => AttributeType::I4,
+      Some(TypeSig::U4) => AttributeType::U4,
+      Some(TypeSig::I8) => AttributeType::I8,
+      Some(TypeSig::U8) => AttributeType::U8,
+      Some(ty) => bail!("Invalid underlying type {:?} of enum `{}`", ty, name),
+      None => bail!("Unknown underlying type of enum `{}`", name),
+    })
+  }
+}
+
+/// Removes the assembly from an assembly qualified type name, e.g.
+/// `System.AttributeTargets, mscorlib, Version=4.0.0.0` becomes `System.AttributeTargets`.
+fn strip_assembly(name: &str) -> &str {
+  let mut depth = 0;
+
+  for (i, c) in name.char_indices() {
+    match c {
+      '[' => depth += 1,
+      ']' => depth -= 1,
+      ',' if depth == 0 => return name[..i].trim_end(),
+      _ => {}
+    }
+  }
+
+  name
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{strip_assembly, AttributeType, Decoder};
+  use crate::ecma335::Md;
+  use alloc::vec;
+
+  #[test]
+  fn strip_assembly_qualified_name() {
+    assert_eq!(
+      strip_assembly("System.AttributeTargets, mscorlib, Version=4.0.0.0"),
+      "System.AttributeTargets"
+    );
+    assert_eq!(
+      strip_assembly("Outer+Inner`1[[System.Int32, mscorlib]], Lib"),
+      "Outer+Inner`1[[System.Int32, mscorlib]]"
+    );
+    assert_eq!(strip_assembly("Plain"), "Plain");
+  }
+
+  #[test]
+  fn nested_args() {
+    let md = Md::empty();
+
+    // object boxing an object boxing ... an int32
+    let mut blob = vec![0x51u8; 200_000];
+    blob.extend_from_slice(&[0x08, 0x00, 0x00, 0x00, 0x00]);
+    let mut decoder = Decoder::new(&md, &(), &blob);
+    assert!(decoder.arg(&AttributeType::Object, 0).is_err());
+
+    let mut blob = vec![0x51u8; 8];
+    blob.extend_from_slice(&[0x08, 0x00, 0x00, 0x00, 0x00]);
+    let mut decoder = Decoder::new(&md, &(), &blob);
+    assert!(decoder.arg(&AttributeType::Object, 0).is_ok());
+
+    // An array of arrays of ... int32
+    let mut blob = vec![0x1du8; 200_000];
+    blob.push(0x08);
+    let mut decoder = Decoder::new(&md, &(), &blob);
+    assert!(decoder.field_or_prop_type(0).is_err());
+
+    // 0x88 isn't an element type.
+    let mut decoder = Decoder::new(&md, &(), &[0x88]);
+    assert!(decoder.field_or_prop_type(0).is_err());
+  }
+}
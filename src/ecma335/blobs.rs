@@ -1,16 +1,20 @@
 //! ECMA-335 Metadata `#Blob` stream data.
 
 use super::{
+  compressed::CompressedU32,
   tables::{HeapSizes, TablesHeader},
   StreamHeader,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{bail, Context, Result};
 use scroll::{
   ctx::{SizeWith, TryFromCtx},
   Pread, LE,
 };
 
 /// Contains the blobs in the `#Blob` stream.
+///
+/// Blobs are indexable by their offset into the stream data.  Each blob is prefixed with its length
+/// stored as a compressed unsigned integer.  The first blob is always the empty blob at offset 0.
 #[derive(Default)]
 pub struct Blobs<'a>(&'a [u8]);
 
@@ -26,15 +30,101 @@ impl<'a> Blobs<'a> {
   }
 
   /// Gets the blob at the given [BlobIndex].
-  pub fn get(&self, index: BlobIndex) -> Result<&'a str> {
-    todo!()
+  pub fn get(&self, index: BlobIndex) -> Result<&'a [u8]> {
+    // The empty blob is always available, even when the stream is missing.
+    if index.0 == 0 && self.0.is_empty() {
+      return Ok(&[]);
+    }
+
+    if index.0 >= self.0.len() {
+      bail!(
+        "Blob index {:#x} out of range, heap size is {:#x}",
+        index.0,
+        self.0.len()
+      );
+    }
+
+    let mut offset = index.0;
+
+    read_blob(self.0, &mut offset).with_context(|| format!("Blob at {:#x}", index.0))
+  }
+
+  /// Gets the size of the heap in bytes.
+  pub fn size(&self) -> usize {
+    self.0.len()
+  }
+
+  /// Creates an iterator over every blob in the heap along with its [BlobIndex].
+  pub fn iter(&self) -> BlobIterator<'a> {
+    BlobIterator {
+      buf: self.0,
+      offset: 0,
+    }
+  }
+}
+
+impl<'a> IntoIterator for &Blobs<'a> {
+  type Item = Result<(BlobIndex, &'a [u8])>;
+  type IntoIter = BlobIterator<'a>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+/// Iterates over the blobs in the `#Blob` stream.
+pub struct BlobIterator<'a> {
+  buf: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Iterator for BlobIterator<'a> {
+  type Item = Result<(BlobIndex, &'a [u8])>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.offset >= self.buf.len() {
+      return None;
+    }
+
+    let index = BlobIndex(self.offset);
+    match read_blob(self.buf, &mut self.offset) {
+      Ok(blob) => Some(Ok((index, blob))),
+      Err(err) => {
+        // The remaining heap can't be walked without a valid length.
+        self.offset = self.buf.len();
+        Some(Err(err.context(format!("Blob at {:#x}", index.0))))
+      }
+    }
+  }
+}
+
+/// Reads a length prefixed blob at the given offset.
+pub(super) fn read_blob<'a>(buf: &'a [u8], offset: &mut usize) -> Result<&'a [u8]> {
+  let len = buf.gread::<CompressedU32>(offset)?.0 as usize;
+
+  match len {
+    // Scroll refuses to read an empty slice at the end of the buffer.
+    0 => Ok(&[]),
+    _ if buf.len() - *offset >= len => Ok(buf.gread_with(offset, len)?),
+    _ => bail!("Blob length {:#x} exceeds heap size", len),
   }
 }
 
 // An index into the [Blobs] stream.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BlobIndex(usize);
 
+impl BlobIndex {
+  pub(crate) fn new(offset: usize) -> Self {
+    Self(offset)
+  }
+
+  /// Gets the byte offset of the blob in the `#Blob` stream.
+  pub fn offset(&self) -> usize {
+    self.0
+  }
+}
+
 impl<'a> TryFromCtx<'a, TablesHeader> for BlobIndex {
   type Error = anyhow::Error;
 
@@ -0,0 +1,329 @@
+//! CIL method bodies (II.25.4).
+//!
+//! A method's RVA points at a tiny or fat header followed by the IL code and, for fat headers, any
+//! number of extra data sections holding the exception handling clauses.
+
+pub mod exceptions;
+#[doc(inline)]
+pub use exceptions::*;
+
+use super::{
+  tables::{flags::MethodImplAttributes, MethodDefRow, StandAloneSigRowId, TablesHeader},
+  Md,
+};
+use alloc::{format, vec::Vec};
+use anyhow::{bail, Context, Error, Result};
+use scroll::{ctx::TryFromCtx, Pread, LE};
+
+/// The mask of the header format in the first byte of a method body.
+const FORMAT_MASK: u8 = 0x03;
+/// The max stack of a method with a tiny header.
+const TINY_MAX_STACK: u16 = 8;
+
+bitflags::bitflags! {
+  /// The flags of a method body header (II.25.4.4).
+  #[derive(Default)]
+  pub struct MethodHeaderFlags : u16 {
+    /// The method has a tiny header, the code size is stored in the upper 6 bits.
+    const TINY_FORMAT = 0x2;
+    /// The method has a fat header.
+    const FAT_FORMAT = 0x3;
+    /// More data sections follow the code.
+    const MORE_SECTS = 0x8;
+    /// Locals are zero initialized.
+    const INIT_LOCALS = 0x10;
+  }
+}
+
+bitflags::bitflags! {
+  /// The kind of an extra data section (II.25.4.5).
+  pub struct SectionKind : u8 {
+    /// Exception handling clauses.
+    const EH_TABLE = 0x1;
+    /// Reserved.
+    const OPT_IL_TABLE = 0x2;
+    /// The section uses the fat format with a 24 bit size.
+    const FAT_FORMAT = 0x40;
+    /// Another section follows this one.
+    const MORE_SECTS = 0x80;
+  }
+}
+
+/// A decoded method body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodBody<'a> {
+  /// The header flags, [MethodHeaderFlags::TINY_FORMAT] for tiny headers.
+  pub flags: MethodHeaderFlags,
+  /// The maximum number of items on the evaluation stack.
+  pub max_stack: u16,
+  /// The signature of the local variables, `None` if there aren't any.
+  pub local_var_sig: Option<StandAloneSigRowId>,
+  /// The IL code.
+  pub code: &'a [u8],
+  /// The extra data sections following the code.
+  pub sections: Vec<DataSection<'a>>,
+}
+
+impl<'a> MethodBody<'a> {
+  /// Determines if the method has a tiny header.
+  pub fn is_tiny(&self) -> bool {
+    self.flags & MethodHeaderFlags::FAT_FORMAT == MethodHeaderFlags::TINY_FORMAT
+  }
+
+  /// Determines if locals are zero initialized, `.locals init`.
+  pub fn init_locals(&self) -> bool {
+    self.flags.contains(MethodHeaderFlags::INIT_LOCALS)
+  }
+}
+
+/// An extra data section of a method body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSection<'a> {
+  /// The kind and format of the section.
+  pub kind: SectionKind,
+  /// The section data, without the section header.
+  pub data: &'a [u8],
+}
+
+impl<'a> DataSection<'a> {
+  /// Determines if the section uses the fat format.
+  pub fn is_fat(&self) -> bool {
+    self.kind.contains(SectionKind::FAT_FORMAT)
+  }
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for MethodBody<'a> {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let first = from.pread::<u8>(0)?;
+
+    match first & FORMAT_MASK {
+      0x2 => {
+        *offset += 1;
+        let size = (first >> 2) as usize;
+        let code = from.gread_with::<&[u8]>(offset, size)?;
+
+        return Ok((
+          Self {
+            flags: MethodHeaderFlags::TINY_FORMAT,
+            max_stack: TINY_MAX_STACK,
+            local_var_sig: None,
+            code,
+            sections: Vec::new(),
+          },
+          *offset,
+        ));
+      }
+      0x3 => {}
+      format => bail!("Unknown method header format {:#x}", format),
+    }
+
+    let flags_and_size = from.gread_with::<u16>(offset, LE)?;
+    let flags = MethodHeaderFlags::from_bits_truncate(flags_and_size & 0x0fff);
+    let header_size = (flags_and_size >> 12) as usize * 4;
+    let max_stack = from.gread_with::<u16>(offset, LE)?;
+    let code_size = from.gread_with::<u32>(offset, LE)? as usize;
+    let local_var_sig = read_local_var_sig_token(from.gread_with::<u32>(offset, LE)?, header)?;
+
+    // The header size is in 4 byte units and should always be 3.
+    if header_size < *offset {
+      bail!("Malformed fat method header, size {}", header_size);
+    }
+
+    *offset = header_size;
+    let code = from.gread_with::<&[u8]>(offset, code_size)?;
+
+    let mut sections = Vec::new();
+    let mut more = flags.contains(MethodHeaderFlags::MORE_SECTS);
+
+    while more {
+      // Sections are 4 byte aligned.
+      *offset = (*offset + 3) & !3;
+
+      // The kind is the low byte, followed by a 1 byte small or 3 byte fat data size.
+      let section = from.gread_with::<u32>(offset, LE)?;
+      let kind = SectionKind::from_bits_truncate(section as u8);
+      let size = match kind.contains(SectionKind::FAT_FORMAT) {
+        true => (section >> 8) as usize,
+        false => ((section >> 8) & 0xff) as usize,
+      };
+
+      // The size includes the 4 byte section header.
+      let data = match size.checked_sub(4) {
+        Some(0) => &[][..],
+        Some(len) => from.gread_with::<&[u8]>(offset, len)?,
+        None => bail!("Malformed method data section, size {}", size),
+      };
+
+      more = kind.contains(SectionKind::MORE_SECTS);
+      sections.push(DataSection { kind, data });
+    }
+
+    Ok((
+      Self {
+        flags,
+        max_stack,
+        local_var_sig,
+        code,
+        sections,
+      },
+      *offset,
+    ))
+  }
+}
+
+/// Reads the `LocalVarSigTok` of a fat header, a `StandAloneSig` token or 0.
+fn read_local_var_sig_token(
+  token: u32,
+  header: &TablesHeader,
+) -> Result<Option<StandAloneSigRowId>> {
+  if token == 0 {
+    return Ok(None);
+  }
+
+  StandAloneSigRowId::from_token(token.into(), header)
+    .map(Some)
+    .with_context(|| format!("Malformed local variable signature token {:#010x}", token))
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the method body at the given RVA.
+  pub fn method_body_at(&self, rva: u32) -> Result<MethodBody<'a>> {
+    let image = match self.image() {
+      Some(image) => image,
+      None => bail!("Method bodies need the metadata to be loaded from a PE image"),
+    };
+
+    image
+      .read_rva(rva)?
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Method body at RVA {:#x}", rva))
+  }
+
+  /// Decodes the body of a method, `None` for abstract, `extern`, native and runtime provided
+  /// methods.
+  pub fn method_body(&self, row: &MethodDefRow) -> Result<Option<MethodBody<'a>>> {
+    // The RVA of a native method points at machine code rather than an IL body.
+    let code_type = row.impl_flags & MethodImplAttributes::CODE_TYPE_MASK;
+    if code_type != MethodImplAttributes::IL {
+      return Ok(None);
+    }
+
+    match row.rva {
+      0 => Ok(None),
+      rva => self.method_body_at(rva).map(Some),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{MethodBody, MethodHeaderFlags, SectionKind};
+  use crate::ecma335::{
+    tables::{
+      MethodDefRow, MethodDefRowId, Row, StandAloneSigRow, StandAloneSigRowId, TablesHeader,
+    },
+    Md,
+  };
+  use scroll::Pread;
+
+  #[test]
+  fn native_method_body() {
+    #[rustfmt::skip]
+    let method_defs = [
+      // rva, impl_flags, flags, name, signature, param_list
+      0x50u8, 0x20, 0, 0, 0x01, 0x00, 0, 0, 0, 0, 0, 0, 1, 0,
+      0x50u8, 0x20, 0, 0, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 1, 0,
+    ];
+    let root = Md::root_with_tables(&[(MethodDefRow::ID, 2, &method_defs)], b"\0", &[]);
+    let md = Md::from_cli_data(&root).unwrap();
+    let header = md.tables().header();
+
+    // The RVA of a native method isn't read.
+    let row = md
+      .tables()
+      .method_defs()
+      .read(MethodDefRowId::new(1, header).unwrap())
+      .unwrap();
+    assert!(md.method_body(&row).unwrap().is_none());
+
+    // IL bodies are read from the PE image, which bare metadata doesn't have.
+    let row = md
+      .tables()
+      .method_defs()
+      .read(MethodDefRowId::new(2, header).unwrap())
+      .unwrap();
+    assert!(md.method_body(&row).is_err());
+  }
+
+  #[test]
+  fn tiny_header() {
+    let header = TablesHeader::default();
+
+    // ldnull; ret
+    let body = [0x0au8, 0x14, 0x2a, 0xff]
+      .pread_with::<MethodBody>(0, &header)
+      .unwrap();
+
+    assert!(body.is_tiny());
+    assert!(!body.init_locals());
+    assert_eq!(body.max_stack, 8);
+    assert_eq!(body.local_var_sig, None);
+    assert_eq!(body.code, [0x14, 0x2a]);
+  }
+
+  #[test]
+  fn fat_header() {
+    let header = TablesHeader::with_rows(&[(StandAloneSigRow::ID, 2)]);
+
+    #[rustfmt::skip]
+    let buf = [
+      // MoreSects | InitLocals, size 3
+      0x1b, 0x30,
+      // max stack
+      0x02, 0x00,
+      // code size
+      0x03, 0x00, 0x00, 0x00,
+      // StandAloneSig 2
+      0x02, 0x00, 0x00, 0x11,
+      // nop; nop; ret
+      0x00, 0x00, 0x2a,
+      // padding
+      0x00,
+      // small EH section, 16 bytes
+      0x01, 0x10, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let body = buf.pread_with::<MethodBody>(0, &header).unwrap();
+
+    assert!(!body.is_tiny());
+    assert!(body.init_locals());
+    assert!(body.flags.contains(MethodHeaderFlags::MORE_SECTS));
+    assert_eq!(body.max_stack, 2);
+    assert_eq!(
+      body.local_var_sig,
+      Some(StandAloneSigRowId::new(2, &header).unwrap())
+    );
+    assert_eq!(body.code, [0x00, 0x00, 0x2a]);
+    assert_eq!(body.sections.len(), 1);
+    assert_eq!(body.sections[0].kind, SectionKind::EH_TABLE);
+    assert_eq!(body.sections[0].data, &buf[20..]);
+  }
+
+  #[test]
+  fn malformed_header() {
+    let header = TablesHeader::default();
+
+    // Unknown format.
+    assert!([0x00u8].pread_with::<MethodBody>(0, &header).is_err());
+    // Code runs past the end.
+    assert!([0x0eu8, 0x00].pread_with::<MethodBody>(0, &header).is_err());
+    // The local variable token isn't a `StandAloneSig`.
+    #[rustfmt::skip]
+    let buf = [0x13u8, 0x30, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02];
+    assert!(buf.pread_with::<MethodBody>(0, &header).is_err());
+  }
+}
@@ -0,0 +1,410 @@
+//! Exception handling clauses (II.25.4.6).
+//!
+//! The clauses of a method are stored in the `EH_TABLE` data sections following its code, in
+//! either a small format with 16 bit offsets and 8 bit lengths or a fat format with 32 bit fields.
+//! Clauses protecting the same range of code are grouped into a [TryBlock] and try blocks nested
+//! within another try block or its handlers form a tree.
+
+use super::{DataSection, MethodBody, SectionKind};
+use crate::ecma335::tables::{TablesHeader, TypeDefOrRef};
+use alloc::{format, vec, vec::Vec};
+use anyhow::{bail, Context, Result};
+use core::{cmp::Reverse, iter, ops::Range};
+use scroll::{Pread, LE};
+
+/// The size of a small clause.
+const SMALL_CLAUSE_SIZE: usize = 12;
+/// The size of a fat clause.
+const FAT_CLAUSE_SIZE: usize = 24;
+
+/// A typed exception handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerKind {
+  /// A typed catch handler, `catch [mscorlib]System.Exception`.
+  Catch(TypeDefOrRef),
+  /// A filtered catch handler, the filter block starts at the given offset and runs up to the
+  /// handler.
+  Filter(u32),
+  /// A `finally` handler, run when the try block exits.
+  Finally,
+  /// A `fault` handler, run when the try block exits with an exception.
+  Fault,
+}
+
+/// An exception handling clause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionClause {
+  /// The kind of handler.
+  pub kind: HandlerKind,
+  /// The offset in bytes of the try block from the start of the code.
+  pub try_offset: u32,
+  /// The length in bytes of the try block.
+  pub try_length: u32,
+  /// The offset in bytes of the handler from the start of the code.
+  pub handler_offset: u32,
+  /// The length in bytes of the handler.
+  pub handler_length: u32,
+}
+
+impl ExceptionClause {
+  /// Gets the range of code protected by the clause.
+  pub fn try_range(&self) -> Range<u32> {
+    self.try_offset..self.try_offset.saturating_add(self.try_length)
+  }
+
+  /// Gets the range of code of the handler.
+  pub fn handler_range(&self) -> Range<u32> {
+    self.handler_offset..self.handler_offset.saturating_add(self.handler_length)
+  }
+
+  /// Gets the range of code of the filter block, `None` if the clause isn't a filter.
+  pub fn filter_range(&self) -> Option<Range<u32>> {
+    match self.kind {
+      HandlerKind::Filter(filter_offset) => Some(filter_offset..self.handler_offset),
+      _ => None,
+    }
+  }
+
+  /// Reads a small clause.
+  fn read_small(from: &[u8], header: &TablesHeader) -> Result<Self> {
+    let offset = &mut 0;
+    let flags = from.gread_with::<u16>(offset, LE)?;
+    let try_offset = from.gread_with::<u16>(offset, LE)?.into();
+    let try_length = from.gread::<u8>(offset)?.into();
+    let handler_offset = from.gread_with::<u16>(offset, LE)?.into();
+    let handler_length = from.gread::<u8>(offset)?.into();
+    let token = from.gread_with::<u32>(offset, LE)?;
+
+    Ok(Self {
+      kind: read_handler_kind(flags.into(), token, header)?,
+      try_offset,
+      try_length,
+      handler_offset,
+      handler_length,
+    })
+  }
+
+  /// Reads a fat clause.
+  fn read_fat(from: &[u8], header: &TablesHeader) -> Result<Self> {
+    let offset = &mut 0;
+    let flags = from.gread_with::<u32>(offset, LE)?;
+    let try_offset = from.gread_with(offset, LE)?;
+    let try_length = from.gread_with(offset, LE)?;
+    let handler_offset = from.gread_with(offset, LE)?;
+    let handler_length = from.gread_with(offset, LE)?;
+    let token = from.gread_with::<u32>(offset, LE)?;
+
+    Ok(Self {
+      kind: read_handler_kind(flags, token, header)?,
+      try_offset,
+      try_length,
+      handler_offset,
+      handler_length,
+    })
+  }
+}
+
+/// Reads the handler kind from the clause flags and the `ClassToken` or `FilterOffset` field.
+fn read_handler_kind(flags: u32, token: u32, header: &TablesHeader) -> Result<HandlerKind> {
+  match flags {
+    0x0 => Ok(HandlerKind::Catch(read_class_token(token, header)?)),
+    0x1 => Ok(HandlerKind::Filter(token)),
+    0x2 => Ok(HandlerKind::Finally),
+    0x4 => Ok(HandlerKind::Fault),
+    flags => bail!("Unknown exception clause flags {:#x}", flags),
+  }
+}
+
+/// Reads the `TypeDef`, `TypeRef` or `TypeSpec` token of a catch clause.
+fn read_class_token(token: u32, header: &TablesHeader) -> Result<TypeDefOrRef> {
+  TypeDefOrRef::from_token(token.into(), header)
+    .with_context(|| format!("Malformed catch class token {:#010x}", token))
+}
+
+impl<'a> DataSection<'a> {
+  /// Decodes the exception handling clauses of an `EH_TABLE` section.
+  pub fn exception_clauses(&self, header: &TablesHeader) -> Result<Vec<ExceptionClause>> {
+    if !self.kind.contains(SectionKind::EH_TABLE) {
+      bail!(
+        "Expected an exception handling section, found {:?}",
+        self.kind
+      );
+    }
+
+    let size = match self.is_fat() {
+      true => FAT_CLAUSE_SIZE,
+      false => SMALL_CLAUSE_SIZE,
+    };
+
+    if !self.data.len().is_multiple_of(size) {
+      bail!(
+        "Malformed exception handling section, {} bytes isn't a multiple of {}",
+        self.data.len(),
+        size
+      );
+    }
+
+    self
+      .data
+      .chunks_exact(size)
+      .map(|clause| match self.is_fat() {
+        true => ExceptionClause::read_fat(clause, header),
+        false => ExceptionClause::read_small(clause, header),
+      })
+      .collect()
+  }
+}
+
+impl<'a> MethodBody<'a> {
+  /// Decodes the exception handling clauses of all `EH_TABLE` sections, in order.
+  pub fn exception_clauses(&self, header: &TablesHeader) -> Result<Vec<ExceptionClause>> {
+    let mut clauses = Vec::new();
+
+    for section in &self.sections {
+      if section.kind.contains(SectionKind::EH_TABLE) {
+        clauses.extend(section.exception_clauses(header)?);
+      }
+    }
+
+    Ok(clauses)
+  }
+
+  /// Decodes the exception handling clauses and nests them into a tree of [TryBlock]s.
+  pub fn try_blocks(&self, header: &TablesHeader) -> Result<Vec<TryBlock>> {
+    Ok(TryBlock::build(&self.exception_clauses(header)?))
+  }
+}
+
+/// A protected range of code with its handlers.
+///
+/// Nested try blocks are the try blocks starting inside this block's protected range or inside
+/// one of its handlers or filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryBlock {
+  /// The protected range of code.
+  pub range: Range<u32>,
+  /// The clauses protecting the range, in order of precedence.
+  pub clauses: Vec<ExceptionClause>,
+  /// The try blocks nested within this block.
+  pub children: Vec<TryBlock>,
+}
+
+impl TryBlock {
+  /// Groups clauses with the same protected range and nests the resulting try blocks, returning
+  /// the outermost try blocks in order of their offset.
+  pub fn build(clauses: &[ExceptionClause]) -> Vec<TryBlock> {
+    let mut blocks = Vec::<TryBlock>::new();
+
+    for clause in clauses {
+      match blocks
+        .iter_mut()
+        .find(|block| block.range == clause.try_range())
+      {
+        Some(block) => block.clauses.push(*clause),
+        None => blocks.push(TryBlock {
+          range: clause.try_range(),
+          clauses: vec![*clause],
+          children: Vec::new(),
+        }),
+      }
+    }
+
+    // A nested block lies entirely within one of the ranges of its parent, so its extent is
+    // smaller and inserting the largest blocks first always finds the parent already in the tree.
+    blocks.sort_by_key(|block| {
+      let extent = block.extent();
+      (Reverse(extent.end - extent.start), extent.start)
+    });
+
+    let mut roots = Vec::new();
+    for block in blocks {
+      block.insert_into(&mut roots);
+    }
+
+    roots
+  }
+
+  /// Gets the ranges of code covered by the block, the protected range, the handlers and the
+  /// filters.
+  pub fn ranges(&self) -> impl Iterator<Item = Range<u32>> + '_ {
+    iter::once(self.range.clone()).chain(
+      self
+        .clauses
+        .iter()
+        .flat_map(|clause| iter::once(clause.handler_range()).chain(clause.filter_range())),
+    )
+  }
+
+  /// Determines if the given offset is in the block's protected range, handlers or filters.
+  pub fn contains(&self, offset: u32) -> bool {
+    self.ranges().any(|range| range.contains(&offset))
+  }
+
+  /// Gets the smallest range spanning all of the block's ranges.
+  fn extent(&self) -> Range<u32> {
+    self.ranges().fold(self.range.clone(), |extent, range| {
+      extent.start.min(range.start)..extent.end.max(range.end)
+    })
+  }
+
+  /// Inserts the block under the innermost block in `blocks` that contains it.
+  fn insert_into(self, blocks: &mut Vec<TryBlock>) {
+    match blocks
+      .iter_mut()
+      .find(|block| block.contains(self.range.start))
+    {
+      Some(parent) => self.insert_into(&mut parent.children),
+      None => {
+        let index = blocks.partition_point(|block| block.range.start < self.range.start);
+        blocks.insert(index, self);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ExceptionClause, HandlerKind, TryBlock};
+  use crate::ecma335::{
+    body::{DataSection, SectionKind},
+    tables::{Row, TablesHeader, TypeDefOrRef, TypeRefRow, TypeRefRowId},
+  };
+  use alloc::vec;
+
+  fn clause(kind: HandlerKind, try_range: (u32, u32), handler: (u32, u32)) -> ExceptionClause {
+    ExceptionClause {
+      kind,
+      try_offset: try_range.0,
+      try_length: try_range.1 - try_range.0,
+      handler_offset: handler.0,
+      handler_length: handler.1 - handler.0,
+    }
+  }
+
+  #[test]
+  fn small_clauses() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    #[rustfmt::skip]
+    let data = [
+      // catch TypeRef 3, try 0x01..0x11, handler 0x11..0x18
+      0x00, 0x00, 0x01, 0x00, 0x10, 0x11, 0x00, 0x07, 0x03, 0x00, 0x00, 0x01,
+      // filter at 0x18, try 0x01..0x11, handler 0x20..0x28
+      0x01, 0x00, 0x01, 0x00, 0x10, 0x20, 0x00, 0x08, 0x18, 0x00, 0x00, 0x00,
+    ];
+    let section = DataSection {
+      kind: SectionKind::EH_TABLE,
+      data: &data,
+    };
+
+    let clauses = section.exception_clauses(&header).unwrap();
+    let catch = TypeDefOrRef::TypeRef(TypeRefRowId::new(3, &header).unwrap());
+
+    assert_eq!(
+      clauses,
+      [
+        clause(HandlerKind::Catch(catch), (0x01, 0x11), (0x11, 0x18)),
+        clause(HandlerKind::Filter(0x18), (0x01, 0x11), (0x20, 0x28)),
+      ]
+    );
+    assert_eq!(clauses[1].filter_range(), Some(0x18..0x20));
+  }
+
+  #[test]
+  fn fat_clauses() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    #[rustfmt::skip]
+    let data = [
+      // finally, try 0x00..0x100, handler 0x100..0x110
+      0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+      0x00, 0x01, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let section = DataSection {
+      kind: SectionKind::EH_TABLE | SectionKind::FAT_FORMAT,
+      data: &data,
+    };
+
+    assert_eq!(
+      section.exception_clauses(&header).unwrap(),
+      [clause(HandlerKind::Finally, (0x00, 0x100), (0x100, 0x110))]
+    );
+
+    // Truncated clause.
+    let section = DataSection {
+      data: &data[..20],
+      ..section
+    };
+    assert!(section.exception_clauses(&header).is_err());
+  }
+
+  #[test]
+  fn malformed_clauses() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    // Unknown flags.
+    #[rustfmt::skip]
+    let data = [
+      0x08, 0x00, 0x01, 0x00, 0x10, 0x11, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let section = DataSection {
+      kind: SectionKind::EH_TABLE,
+      data: &data,
+    };
+    assert!(section.exception_clauses(&header).is_err());
+
+    // The catch token isn't a type.
+    #[rustfmt::skip]
+    let data = [
+      0x00, 0x00, 0x01, 0x00, 0x10, 0x11, 0x00, 0x07, 0x01, 0x00, 0x00, 0x06,
+    ];
+    let section = DataSection {
+      kind: SectionKind::EH_TABLE,
+      data: &data,
+    };
+    assert!(section.exception_clauses(&header).is_err());
+  }
+
+  #[test]
+  fn nested_try_blocks() {
+    // try {
+    //   try { } finally { }
+    // } catch { try { } fault { } }
+    // try { } finally { }
+    let catch =
+      HandlerKind::Catch(unsafe { TypeDefOrRef::TypeRef(TypeRefRowId::new_unchecked(1)) });
+    let inner = clause(HandlerKind::Finally, (0x02, 0x08), (0x08, 0x0c));
+    let in_handler = clause(HandlerKind::Fault, (0x12, 0x14), (0x14, 0x18));
+    let outer = clause(catch, (0x00, 0x10), (0x10, 0x20));
+    let sibling = clause(HandlerKind::Finally, (0x20, 0x24), (0x24, 0x28));
+
+    let blocks = TryBlock::build(&[inner, in_handler, outer, sibling]);
+
+    assert_eq!(
+      blocks,
+      [
+        TryBlock {
+          range: 0x00..0x10,
+          clauses: vec![outer],
+          children: vec![
+            TryBlock {
+              range: 0x02..0x08,
+              clauses: vec![inner],
+              children: vec![],
+            },
+            TryBlock {
+              range: 0x12..0x14,
+              clauses: vec![in_handler],
+              children: vec![],
+            },
+          ],
+        },
+        TryBlock {
+          range: 0x20..0x24,
+          clauses: vec![sibling],
+          children: vec![],
+        },
+      ]
+    );
+  }
+}
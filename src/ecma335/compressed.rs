@@ -0,0 +1,182 @@
+//! ECMA-335 compressed integer encoding (II.23.2).
+//!
+//! Blob lengths, signature items and user string lengths are stored using a variable length
+//! encoding where the high bits of the first byte determine the width of the value.
+
+use anyhow::{bail, Error, Result};
+use scroll::{
+  ctx::{TryFromCtx, TryIntoCtx},
+  Pread, Pwrite,
+};
+
+/// An unsigned integer stored in the 1, 2 or 4 byte compressed format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompressedU32(pub u32);
+
+impl<'a> TryFromCtx<'a> for CompressedU32 {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], _: ()) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let b0 = from.gread::<u8>(offset)? as u32;
+
+    let value = match b0 {
+      // 0bbbbbbb
+      _ if b0 & 0x80 == 0 => b0,
+      // 10bbbbbb bbbbbbbb
+      _ if b0 & 0xc0 == 0x80 => {
+        let b1 = from.gread::<u8>(offset)? as u32;
+
+        ((b0 & 0x3f) << 8) | b1
+      }
+      // 110bbbbb bbbbbbbb bbbbbbbb bbbbbbbb
+      _ if b0 & 0xe0 == 0xc0 => {
+        let b1 = from.gread::<u8>(offset)? as u32;
+        let b2 = from.gread::<u8>(offset)? as u32;
+        let b3 = from.gread::<u8>(offset)? as u32;
+
+        ((b0 & 0x1f) << 24) | (b1 << 16) | (b2 << 8) | b3
+      }
+      _ => bail!("Malformed compressed integer, lead byte {:#x}", b0),
+    };
+
+    Ok((Self(value), *offset))
+  }
+}
+
+impl TryIntoCtx for CompressedU32 {
+  type Error = Error;
+
+  fn try_into_ctx(self, into: &mut [u8], _: ()) -> Result<usize> {
+    let offset = &mut 0;
+    let value = self.0;
+
+    match value {
+      0..=0x7f => into.gwrite(value as u8, offset)?,
+      0x80..=0x3fff => into.gwrite_with((value as u16) | 0x8000, offset, scroll::BE)?,
+      0x4000..=0x1fff_ffff => into.gwrite_with(value | 0xc000_0000, offset, scroll::BE)?,
+      _ => bail!("{:#x} is too large for a compressed integer", value),
+    };
+
+    Ok(*offset)
+  }
+}
+
+/// A signed integer stored in the 1, 2 or 4 byte compressed format.
+///
+/// The two's complement value is truncated to 7, 14 or 29 bits and rotated left by one so the sign
+/// ends up in the least significant bit before being compressed like a [CompressedU32].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompressedI32(pub i32);
+
+impl<'a> TryFromCtx<'a> for CompressedI32 {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], _: ()) -> Result<(Self, usize)> {
+    let (CompressedU32(value), size) = CompressedU32::try_from_ctx(from, ())?;
+    let bits = match size {
+      1 => 7,
+      2 => 14,
+      _ => 29,
+    };
+
+    let magnitude = (value >> 1) as i32;
+    let value = match value & 1 {
+      0 => magnitude,
+      _ => magnitude - (1 << (bits - 1)),
+    };
+
+    Ok((Self(value), size))
+  }
+}
+
+impl TryIntoCtx for CompressedI32 {
+  type Error = Error;
+
+  fn try_into_ctx(self, into: &mut [u8], _: ()) -> Result<usize> {
+    let value = self.0;
+    let bits = match value {
+      -0x40..=0x3f => 7,
+      -0x2000..=0x1fff => 14,
+      -0x1000_0000..=0x0fff_ffff => 29,
+      _ => bail!("{} is too large for a compressed integer", value),
+    };
+
+    let magnitude = (value as u32) & ((1 << (bits - 1)) - 1);
+    let sign = (value < 0) as u32;
+    let encoded = (magnitude << 1) | sign;
+
+    let offset = &mut 0;
+    match bits {
+      7 => into.gwrite(encoded as u8, offset)?,
+      14 => into.gwrite_with((encoded as u16) | 0x8000, offset, scroll::BE)?,
+      _ => into.gwrite_with(encoded | 0xc000_0000, offset, scroll::BE)?,
+    };
+
+    Ok(*offset)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{CompressedI32, CompressedU32};
+  use scroll::{Pread, Pwrite};
+
+  #[test]
+  fn read_compressed_u32() {
+    let cases: &[(&[u8], u32)] = &[
+      (&[0x03], 0x03),
+      (&[0x7f], 0x7f),
+      (&[0x80, 0x80], 0x80),
+      (&[0xae, 0x57], 0x2e57),
+      (&[0xbf, 0xff], 0x3fff),
+      (&[0xc0, 0x00, 0x40, 0x00], 0x4000),
+      (&[0xdf, 0xff, 0xff, 0xff], 0x1fff_ffff),
+    ];
+
+    for (buf, expected) in cases {
+      let offset = &mut 0;
+      let actual = buf.gread::<CompressedU32>(offset).unwrap();
+
+      assert_eq!(actual.0, *expected);
+      assert_eq!(*offset, buf.len());
+
+      let mut written = [0u8; 4];
+      let size = written.pwrite(CompressedU32(*expected), 0).unwrap();
+      assert_eq!(&written[..size], *buf);
+    }
+
+    assert!([0xe0u8, 0, 0, 0].pread::<CompressedU32>(0).is_err());
+    assert!([0x80u8].pread::<CompressedU32>(0).is_err());
+  }
+
+  #[test]
+  fn read_compressed_i32() {
+    let cases: &[(&[u8], i32)] = &[
+      (&[0x06], 3),
+      (&[0x7b], -3),
+      (&[0x80, 0x80], 64),
+      (&[0x01], -64),
+      (&[0xc0, 0x00, 0x40, 0x00], 8192),
+      (&[0x80, 0x01], -8192),
+      (&[0xdf, 0xff, 0xff, 0xfe], 268435455),
+      (&[0xc0, 0x00, 0x00, 0x01], -268435456),
+    ];
+
+    for (buf, expected) in cases {
+      let offset = &mut 0;
+      let actual = buf.gread::<CompressedI32>(offset).unwrap();
+
+      assert_eq!(actual.0, *expected);
+      assert_eq!(*offset, buf.len());
+
+      let mut written = [0u8; 4];
+      let size = written.pwrite(CompressedI32(*expected), 0).unwrap();
+      assert_eq!(&written[..size], *buf);
+    }
+
+    let mut written = [0u8; 4];
+    assert!(written.pwrite(CompressedI32(0x1000_0000), 0).is_err());
+    assert!(written.pwrite(CompressedU32(0x2000_0000), 0).is_err());
+  }
+}
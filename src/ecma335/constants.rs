@@ -0,0 +1,208 @@
+//! Constant values (II.22.9).
+//!
+//! The `Constant` table holds the compile time values of literal fields, default parameter values
+//! and property defaults.  The value is stored little endian in the `#Blob` heap and its type is
+//! given by the row's `kind`.
+
+use super::{
+  tables::{flags::ElementType, ConstantRow, HasConstant},
+  Md,
+};
+use alloc::{format, vec::Vec};
+use anyhow::{bail, Context, Error, Result};
+use core::fmt;
+use scroll::{ctx::TryFromCtx, Pread, LE};
+
+/// A decoded constant value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+  Boolean(bool),
+  /// A UTF-16 code unit.
+  Char(u16),
+  I1(i8),
+  U1(u8),
+  I2(i16),
+  U2(u16),
+  I4(i32),
+  U4(u32),
+  I8(i64),
+  U8(u64),
+  R4(f32),
+  R8(f64),
+  /// A string as UTF-16 code units, which aren't guaranteed to be valid UTF-16.
+  String(Vec<u16>),
+  /// A null object reference.
+  Null,
+}
+
+impl<'a> TryFromCtx<'a, ElementType> for ConstantValue {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], kind: ElementType) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+
+    let value = match kind {
+      ElementType::ELEMENT_TYPE_BOOLEAN => Self::Boolean(from.gread::<u8>(offset)? != 0),
+      ElementType::ELEMENT_TYPE_CHAR => Self::Char(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_I1 => Self::I1(from.gread(offset)?),
+      ElementType::ELEMENT_TYPE_U1 => Self::U1(from.gread(offset)?),
+      ElementType::ELEMENT_TYPE_I2 => Self::I2(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_U2 => Self::U2(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_I4 => Self::I4(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_U4 => Self::U4(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_I8 => Self::I8(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_U8 => Self::U8(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_R4 => Self::R4(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_R8 => Self::R8(from.gread_with(offset, LE)?),
+      ElementType::ELEMENT_TYPE_STRING => {
+        if !from.len().is_multiple_of(2) {
+          bail!("Malformed string constant, odd length {}", from.len());
+        }
+
+        let units = (0..from.len() / 2)
+          .map(|_| from.gread_with(offset, LE))
+          .collect::<Result<Vec<u16>, _>>()?;
+
+        Self::String(units)
+      }
+      // The blob of a null reference is a 4 byte zero.
+      ElementType::ELEMENT_TYPE_CLASS => match from.gread_with::<u32>(offset, LE)? {
+        0 => Self::Null,
+        value => bail!("Malformed null constant {:#x}", value),
+      },
+      kind => bail!("Invalid constant type {:#x}", kind.bits()),
+    };
+
+    Ok((value, *offset))
+  }
+}
+
+impl fmt::Display for ConstantValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Boolean(value) => write!(f, "{}", value),
+      Self::Char(value) => match char::from_u32(*value as u32) {
+        Some(value) => write!(f, "{:?}", value),
+        None => write!(f, "'\\u{{{:x}}}'", value),
+      },
+      Self::I1(value) => write!(f, "{}", value),
+      Self::U1(value) => write!(f, "{}", value),
+      Self::I2(value) => write!(f, "{}", value),
+      Self::U2(value) => write!(f, "{}", value),
+      Self::I4(value) => write!(f, "{}", value),
+      Self::U4(value) => write!(f, "{}", value),
+      Self::I8(value) => write!(f, "{}", value),
+      Self::U8(value) => write!(f, "{}", value),
+      Self::R4(value) => write!(f, "{:?}", value),
+      Self::R8(value) => write!(f, "{:?}", value),
+      Self::String(units) => {
+        let value = char::decode_utf16(units.iter().copied())
+          .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+          .collect::<alloc::string::String>();
+
+        write!(f, "{:?}", value)
+      }
+      Self::Null => write!(f, "null"),
+    }
+  }
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the value of a `Constant` row.
+  pub fn constant_value(&self, row: &ConstantRow) -> Result<ConstantValue> {
+    let blob = self.blobs().get(row.value)?;
+
+    // Empty strings have an empty blob, which `pread` refuses to read from.
+    ConstantValue::try_from_ctx(blob, row.kind)
+      .map(|(value, _)| value)
+      .with_context(|| format!("Constant at {:#x}", row.value.offset()))
+  }
+
+  /// Gets the constant value of a literal field, a parameter's default value or a property's
+  /// default value, `None` if the parent has no constant.
+  pub fn constant(&self, parent: HasConstant) -> Result<Option<ConstantValue>> {
+    let row = self
+      .tables()
+      .constants()
+      .find(&parent.encode(), |row| row.parent.encode())?;
+
+    row.map(|row| self.constant_value(&row)).transpose()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ConstantValue;
+  use crate::ecma335::tables::flags::ElementType;
+  use alloc::{string::ToString, vec};
+  use scroll::{ctx::TryFromCtx, Pread};
+
+  #[test]
+  fn constant_values() {
+    let cases: &[(ElementType, &[u8], ConstantValue)] = &[
+      (
+        ElementType::ELEMENT_TYPE_BOOLEAN,
+        &[1],
+        ConstantValue::Boolean(true),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_I2,
+        &[0xfe, 0xff],
+        ConstantValue::I2(-2),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_U8,
+        &[0xff; 8],
+        ConstantValue::U8(u64::MAX),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_R8,
+        &[0, 0, 0, 0, 0, 0, 0xf0, 0x3f],
+        ConstantValue::R8(1.0),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_STRING,
+        &[0x68, 0, 0x69, 0],
+        ConstantValue::String(vec![0x68, 0x69]),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_STRING,
+        &[],
+        ConstantValue::String(vec![]),
+      ),
+      (
+        ElementType::ELEMENT_TYPE_CLASS,
+        &[0; 4],
+        ConstantValue::Null,
+      ),
+    ];
+
+    for (kind, buf, expected) in cases {
+      let (actual, size) = ConstantValue::try_from_ctx(buf, *kind).unwrap();
+
+      assert_eq!(actual, *expected);
+      assert_eq!(size, buf.len());
+    }
+
+    assert!([0u8; 3]
+      .pread_with::<ConstantValue>(0, ElementType::ELEMENT_TYPE_STRING)
+      .is_err());
+    assert!([1u8, 0, 0, 0]
+      .pread_with::<ConstantValue>(0, ElementType::ELEMENT_TYPE_CLASS)
+      .is_err());
+    assert!([0u8; 4]
+      .pread_with::<ConstantValue>(0, ElementType::ELEMENT_TYPE_OBJECT)
+      .is_err());
+  }
+
+  #[test]
+  fn display_constant_values() {
+    assert_eq!(
+      ConstantValue::String(vec![0x68, 0x22]).to_string(),
+      "\"h\\\"\""
+    );
+    assert_eq!(ConstantValue::Char(0x61).to_string(), "'a'");
+    assert_eq!(ConstantValue::Char(0xd800).to_string(), "'\\u{d800}'");
+    assert_eq!(ConstantValue::Null.to_string(), "null");
+  }
+}
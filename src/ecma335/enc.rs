@@ -0,0 +1,384 @@
+//! Edit and continue (ENC) metadata deltas.
+//!
+//! Every edit applied during a hot reload session emits a delta, a minimal metadata blob whose `#-`
+//! stream only holds the rows the edit added or updated.  The `ENCMap` table of the delta lists the
+//! token of each of those rows in the aggregate metadata, and its heaps only hold the data the edit
+//! appended, so heap offsets in a delta continue past the heaps of the earlier generations.
+//!
+//! Members added to an existing type aren't part of its member lists, the `ENCLog` table records
+//! them as an `Add*` entry for the parent followed by an entry for the member.
+
+use super::{
+  blobs::BlobIndex,
+  guids::{Guid, GuidIndex},
+  strings::StringIndex,
+  tables::{flags::EncFuncCode, *},
+  token::Token,
+  user_strings::{UserString, UserStringIndex},
+  Md,
+};
+use alloc::{collections::BTreeMap, format, vec, vec::Vec};
+use anyhow::{bail, Context, Result};
+
+/// A baseline module with a sequence of ENC deltas applied.
+///
+/// Rows are read from the latest generation that added or updated them.  The row ids and heap
+/// indexes of rows from any generation refer to the aggregate metadata, so they're resolved through
+/// this view rather than the [Md] of the generation.
+///
+/// The RVA of a method updated by a delta points into the IL delta emitted alongside it, which
+/// isn't part of the metadata.
+pub struct EncMd<'a> {
+  generations: Vec<Generation<'a>>,
+  /// The aggregate row counts, for creating and iterating row ids.
+  header: TablesHeader,
+  /// Where the latest version of each row is, indexed by table id and zero-based row.
+  rows: Vec<Vec<RowSource>>,
+  /// The members added to each parent by the `ENCLog`, in the order they were added.
+  added: BTreeMap<Token, Vec<Token>>,
+}
+
+/// The metadata of a generation and where its heaps start in the aggregate heaps.
+struct Generation<'a> {
+  md: Md<'a>,
+  strings: usize,
+  blobs: usize,
+  /// The number of guids in the earlier generations.
+  guids: usize,
+  user_strings: usize,
+}
+
+/// The generation and the 1-based row in it holding a version of a row.
+#[derive(Clone, Copy)]
+struct RowSource {
+  generation: usize,
+  row: u32,
+}
+
+impl<'a> EncMd<'a> {
+  /// Applies the given deltas, in generation order, to a baseline.
+  pub fn new(base: Md<'a>, deltas: impl IntoIterator<Item = &'a [u8]>) -> Result<Self> {
+    let header = *base.tables().header();
+    if header.minimal_delta {
+      bail!("Expected a baseline, found an ENC delta");
+    }
+
+    let rows = header
+      .rows
+      .iter()
+      .map(|&rows| {
+        (1..=rows)
+          .map(|row| RowSource { generation: 0, row })
+          .collect()
+      })
+      .collect();
+
+    let mut md = Self {
+      generations: vec![Generation {
+        md: base,
+        strings: 0,
+        blobs: 0,
+        guids: 0,
+        user_strings: 0,
+      }],
+      header,
+      rows,
+      added: BTreeMap::new(),
+    };
+
+    for delta in deltas {
+      let generation = md.generations.len();
+      md.apply(delta)
+        .with_context(|| format!("Generation {}", generation))?;
+    }
+
+    Ok(md)
+  }
+
+  /// Applies the delta of the next generation, leaving the view unchanged if the delta is
+  /// malformed.
+  pub fn apply(&mut self, delta: &'a [u8]) -> Result<()> {
+    let md = Md::from_cli_data(delta)?;
+    let tables = md.tables();
+    if !tables.header().minimal_delta {
+      bail!("Expected a minimal ENC delta with a `#JTD` stream");
+    }
+
+    let generation = self.generations.len();
+    let mut lens = self.header.rows;
+    let mut counts = [0u32; 64];
+    let mut sources = Vec::with_capacity(tables.enc_maps().len());
+
+    // The rows of each table in the delta are in the order of their tokens in the map.
+    for entry in tables.enc_maps() {
+      let token = entry?.token;
+      let table = token.table() as usize;
+      if table >= lens.len() {
+        bail!("Unknown table {:#x} in ENC map token {}", table, token);
+      }
+
+      counts[table] += 1;
+      match token.row() {
+        0 => bail!("Null ENC map token {}", token),
+        row if row <= lens[table] => {}
+        row if row == lens[table] + 1 => lens[table] = row,
+        _ => bail!(
+          "ENC map token {} skips rows, table has {} rows",
+          token,
+          lens[table]
+        ),
+      }
+
+      let source = RowSource {
+        generation,
+        row: counts[table],
+      };
+      sources.push((table, token.row(), source));
+    }
+
+    for (table, &count) in counts.iter().enumerate() {
+      let rows = tables.header().rows[table];
+      if count != rows && table != EncLogRow::ID && table != EncMapRow::ID {
+        bail!(
+          "ENC map has {} rows of table {:#x}, delta has {}",
+          count,
+          table,
+          rows
+        );
+      }
+    }
+
+    let mut added = Vec::new();
+    let mut log = tables.enc_logs().into_iter();
+    while let Some(entry) = log.next() {
+      let entry = entry?;
+      let table = match entry.func_code {
+        EncFuncCode::AddMethod => MethodDefRow::ID,
+        EncFuncCode::AddField => FieldRow::ID,
+        EncFuncCode::AddParameter => ParamRow::ID,
+        EncFuncCode::AddProperty => PropertyRow::ID,
+        EncFuncCode::AddEvent => EventRow::ID,
+        EncFuncCode::Default | EncFuncCode::Unknown(_) => continue,
+      };
+
+      let member = log
+        .next()
+        .with_context(|| format!("ENC log ends adding a member to {}", entry.token))??
+        .token;
+      if member.table() as usize != table {
+        bail!(
+          "ENC log adds {} to {} with {:?}",
+          member,
+          entry.token,
+          entry.func_code
+        );
+      }
+
+      added.push((entry.token, member));
+    }
+
+    let last = &self.generations[generation - 1];
+    let next = Generation {
+      strings: last.strings + last.md.strings().size(),
+      blobs: last.blobs + last.md.blobs().size(),
+      guids: last.guids + last.md.guids().len(),
+      user_strings: last.user_strings + last.md.user_strings().size(),
+      md,
+    };
+
+    for (table, row, source) in sources {
+      let rows = &mut self.rows[table];
+      match rows.get_mut(row as usize - 1) {
+        Some(latest) => *latest = source,
+        None => rows.push(source),
+      }
+    }
+
+    for (table, len) in lens.into_iter().enumerate() {
+      self.header.rows[table] = len;
+      if len > 0 {
+        self.header.valid |= 1 << table;
+      }
+    }
+
+    for (parent, member) in added {
+      self.added.entry(parent).or_default().push(member);
+    }
+
+    self.generations.push(next);
+
+    Ok(())
+  }
+
+  /// Gets the number of generations, including the baseline.
+  pub fn generations(&self) -> usize {
+    self.generations.len()
+  }
+
+  /// Gets the metadata of a generation, the baseline is generation 0.
+  pub fn generation(&self, generation: usize) -> Option<&Md<'a>> {
+    self
+      .generations
+      .get(generation)
+      .map(|generation| &generation.md)
+  }
+
+  /// Gets the header with the aggregate row counts, for creating row ids.
+  pub fn header(&self) -> &TablesHeader {
+    &self.header
+  }
+
+  /// Finds the generation holding the latest version of a row along with its token in that
+  /// generation.
+  pub fn locate(&self, token: Token) -> Result<(usize, Token)> {
+    let source = self.source(token.table() as usize, token.row())?;
+
+    Ok((source.generation, Token::new(token.table(), source.row)?))
+  }
+
+  /// Reads the latest version of a row.
+  pub fn read<R: Row<'a>>(&self, index: R::Index) -> Result<R> {
+    let row = index.offset().context("Cannot read a null row")? as u32 + 1;
+    let source = self.source(R::ID, row)?;
+    let tables = self.generations[source.generation].md.tables();
+
+    tables.read::<R>(RowIndex::from_row(source.row, tables.header())?)
+  }
+
+  /// Creates an iterator over the latest version of every row of a table.
+  pub fn rows<'t: 'a, R: Row<'a>>(&'t self) -> impl Iterator<Item = Result<R>> + 'a {
+    let mut next = R::Index::first(&self.header);
+
+    core::iter::from_fn(move || {
+      let index = next?;
+      next = index.next();
+
+      Some(self.read::<R>(index))
+    })
+  }
+
+  /// Gets the string at an offset into the aggregate `#Strings` heap.
+  pub fn string(&self, index: StringIndex) -> Result<&'a str> {
+    let generation = self.heap(index.offset(), |generation| generation.strings);
+    let offset = index.offset() - generation.strings;
+
+    generation.md.strings().get(StringIndex::new(offset))
+  }
+
+  /// Gets the blob at an offset into the aggregate `#Blob` heap.
+  pub fn blob(&self, index: BlobIndex) -> Result<&'a [u8]> {
+    let generation = self.heap(index.offset(), |generation| generation.blobs);
+    let offset = index.offset() - generation.blobs;
+
+    generation.md.blobs().get(BlobIndex::new(offset))
+  }
+
+  /// Gets the user string at an offset into the aggregate `#US` heap.
+  pub fn user_string(&self, index: UserStringIndex) -> Result<UserString<'a>> {
+    let generation = self.heap(index.offset(), |generation| generation.user_strings);
+    let offset = index.offset() - generation.user_strings;
+
+    generation
+      .md
+      .user_strings()
+      .get(UserStringIndex::new(offset))
+  }
+
+  /// Gets the guid at a 1-based index into the aggregate `#GUID` heap, or `None` if the index is
+  /// null.
+  pub fn guid(&self, index: GuidIndex) -> Result<Option<Guid>> {
+    if index.is_null() {
+      return Ok(None);
+    }
+
+    // Guid indexes are 1-based, so a generation holds the indexes past the guids before it.
+    let generation = self.heap(index.index() - 1, |generation| generation.guids);
+    let index = index.index() - generation.guids;
+
+    generation.md.guids().get(GuidIndex::new(index))
+  }
+
+  /// Gets the fields of a type, including the fields added to it by deltas.
+  pub fn type_def_fields(&self, id: TypeDefRowId) -> Result<Vec<FieldRowId>> {
+    self.members(id.token(), |tables| {
+      tables.type_def_fields(TypeDefRowId::new(id.row(), tables.header())?)
+    })
+  }
+
+  /// Gets the methods of a type, including the methods added to it by deltas.
+  pub fn type_def_methods(&self, id: TypeDefRowId) -> Result<Vec<MethodDefRowId>> {
+    self.members(id.token(), |tables| {
+      tables.type_def_methods(TypeDefRowId::new(id.row(), tables.header())?)
+    })
+  }
+
+  /// Gets the parameter rows of a method, including the rows added to it by deltas.
+  pub fn method_def_params(&self, id: MethodDefRowId) -> Result<Vec<ParamRowId>> {
+    self.members(id.token(), |tables| {
+      tables.method_def_params(MethodDefRowId::new(id.row(), tables.header())?)
+    })
+  }
+
+  /// Gets the events of an event map, including the events added to it by deltas.
+  pub fn event_map_events(&self, id: EventMapRowId) -> Result<Vec<EventRowId>> {
+    self.members(id.token(), |tables| {
+      tables.event_map_events(EventMapRowId::new(id.row(), tables.header())?)
+    })
+  }
+
+  /// Gets the properties of a property map, including the properties added to it by deltas.
+  pub fn property_map_properties(&self, id: PropertyMapRowId) -> Result<Vec<PropertyRowId>> {
+    self.members(id.token(), |tables| {
+      tables.property_map_properties(PropertyMapRowId::new(id.row(), tables.header())?)
+    })
+  }
+
+  /// Gets the members of a parent, its member list in the baseline followed by the members the
+  /// deltas added.  The member lists of rows updated or added by deltas aren't meaningful.
+  fn members<I: RowIndex + Copy>(
+    &self,
+    parent: Token,
+    baseline: impl FnOnce(&Tables<'a>) -> Result<RowRange<I>>,
+  ) -> Result<Vec<I>> {
+    let tables = self.generations[0].md.tables();
+    let mut members = Vec::new();
+
+    if parent.row() <= tables.header().rows[parent.table() as usize] {
+      for member in baseline(tables)? {
+        let row = member.offset().context("Null member row id")? as u32 + 1;
+        members.push(I::from_row(row, &self.header)?);
+      }
+    }
+
+    for member in self.added.get(&parent).into_iter().flatten() {
+      members.push(I::from_row(member.row(), &self.header)?);
+    }
+
+    Ok(members)
+  }
+
+  /// Finds where the latest version of a row is.
+  fn source(&self, table: usize, row: u32) -> Result<RowSource> {
+    let source = self
+      .rows
+      .get(table)
+      .zip(row.checked_sub(1))
+      .and_then(|(rows, row)| rows.get(row as usize));
+
+    match source {
+      Some(source) => Ok(*source),
+      None => bail!("Row {} of table {:#x} out of range", row, table),
+    }
+  }
+
+  /// Finds the generation whose heap holds an offset into the aggregate heap, the last one whose
+  /// heap starts at or before it.
+  fn heap(&self, offset: usize, start: impl Fn(&Generation<'a>) -> usize) -> &Generation<'a> {
+    self
+      .generations
+      .iter()
+      .rev()
+      .find(|generation| start(generation) <= offset)
+      .unwrap_or(&self.generations[0])
+  }
+}
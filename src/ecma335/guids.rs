@@ -4,13 +4,20 @@ use super::{
   tables::{HeapSizes, TablesHeader},
   StreamHeader,
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
+use core::fmt;
 use scroll::{
   ctx::{SizeWith, TryFromCtx},
   Pread, LE,
 };
 
+/// The size of a single entry in the `#GUID` stream.
+const GUID_SIZE: usize = 16;
+
 /// Contains the GUIDs in the `#GUID` stream.
+///
+/// The stream is an array of 16 byte GUIDs.  GUIDs are indexable by their 1-based position in the
+/// array, an index of 0 is used to represent a null GUID.
 #[derive(Default)]
 pub struct Guids<'a>(&'a [u8]);
 
@@ -25,27 +32,149 @@ impl<'a> Guids<'a> {
     Ok(Self(header.data()?))
   }
 
-  /// Gets the guid at the given [GuidIndex].
-  pub fn get(&self, index: GuidIndex) -> Result<[u8; 16]> {
-    let offset = index.0;
-    let mut guid = [0u8; 16];
+  /// Gets the number of GUIDs in the stream.
+  pub fn len(&self) -> usize {
+    self.0.len() / GUID_SIZE
+  }
+
+  /// Determines if the stream contains no GUIDs.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
 
-    (0..15).for_each(|i| {
-      if offset + i > self.0.len() {
-        return;
-      }
+  /// Gets the guid at the given [GuidIndex], or `None` if the index is null.
+  pub fn get(&self, index: GuidIndex) -> Result<Option<Guid>> {
+    if index.is_null() {
+      return Ok(None);
+    }
 
-      guid[i] = self.0[offset + i];
-    });
+    match self.0.chunks_exact(GUID_SIZE).nth(index.0 - 1) {
+      Some(guid) => Ok(Some(Guid::from_slice(guid))),
+      None => bail!(
+        "Guid index {} out of range, expected at most {}",
+        index.0,
+        self.len()
+      ),
+    }
+  }
 
-    Ok(guid)
+  /// Creates an iterator over every guid in the stream along with its [GuidIndex].
+  pub fn iter(&self) -> impl Iterator<Item = (GuidIndex, Guid)> + 'a {
+    self
+      .0
+      .chunks_exact(GUID_SIZE)
+      .enumerate()
+      .map(|(i, guid)| (GuidIndex(i + 1), Guid::from_slice(guid)))
   }
 }
 
-// An index into the [Guids] stream.
-#[derive(Debug, Clone, Copy)]
+/// A globally unique identifier stored in the Microsoft mixed-endian layout.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Guid([u8; GUID_SIZE]);
+
+impl Guid {
+  /// Creates a [Guid] from its in-memory byte representation.
+  pub const fn from_bytes(bytes: [u8; GUID_SIZE]) -> Self {
+    Self(bytes)
+  }
+
+  /// Gets the in-memory byte representation of the guid.
+  pub const fn as_bytes(&self) -> &[u8; GUID_SIZE] {
+    &self.0
+  }
+
+  fn from_slice(slice: &[u8]) -> Self {
+    let mut bytes = [0; GUID_SIZE];
+    bytes.copy_from_slice(slice);
+
+    Self(bytes)
+  }
+
+  /// Gets the first group of the guid, stored little endian.
+  pub fn data1(&self) -> u32 {
+    u32::from_le_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+  }
+
+  /// Gets the second group of the guid, stored little endian.
+  pub fn data2(&self) -> u16 {
+    u16::from_le_bytes([self.0[4], self.0[5]])
+  }
+
+  /// Gets the third group of the guid, stored little endian.
+  pub fn data3(&self) -> u16 {
+    u16::from_le_bytes([self.0[6], self.0[7]])
+  }
+
+  /// Gets the last 8 bytes of the guid, stored as-is.
+  pub fn data4(&self) -> [u8; 8] {
+    let mut data4 = [0; 8];
+    data4.copy_from_slice(&self.0[8..]);
+
+    data4
+  }
+}
+
+impl fmt::Display for Guid {
+  /// Writes the guid in registry format, e.g. `00020400-0000-0000-C000-000000000046`.
+  ///
+  /// The alternate flag (`{:#}`) wraps the guid in braces.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let d4 = self.data4();
+
+    if f.alternate() {
+      f.write_str("{")?;
+    }
+
+    write!(
+      f,
+      "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+      self.data1(),
+      self.data2(),
+      self.data3(),
+      d4[0],
+      d4[1],
+      d4[2],
+      d4[3],
+      d4[4],
+      d4[5],
+      d4[6],
+      d4[7],
+    )?;
+
+    if f.alternate() {
+      f.write_str("}")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Debug for Guid {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Guid({})", self)
+  }
+}
+
+// A 1-based index into the [Guids] stream, where 0 is null.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GuidIndex(usize);
 
+impl GuidIndex {
+  pub(crate) fn new(index: usize) -> Self {
+    Self(index)
+  }
+
+  /// Gets the 1-based position of the guid in the `#GUID` stream.
+  pub fn index(&self) -> usize {
+    self.0
+  }
+
+  /// Determines if the index refers to no guid.
+  pub fn is_null(&self) -> bool {
+    self.0 == 0
+  }
+}
+
 impl<'a> TryFromCtx<'a, TablesHeader> for GuidIndex {
   type Error = anyhow::Error;
 
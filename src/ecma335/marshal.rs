@@ -0,0 +1,435 @@
+//! Marshalling descriptors (II.23.4).
+//!
+//! The `FieldMarshal` table attaches a `MarshalSpec` blob to fields and parameters describing how
+//! they're marshalled to and from unmanaged code, the blob encoding of `[MarshalAs(...)]`.
+
+use super::{
+  compressed::CompressedU32,
+  tables::{FieldMarshalRow, HasFieldMarshal},
+  Md,
+};
+use alloc::{format, string::String};
+use anyhow::{bail, Context, Error, Result};
+use scroll::{ctx::TryFromCtx, Pread};
+
+/// A native type, the `NATIVE_TYPE_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NativeType {
+  Void = 0x01,
+  /// A 4 byte `BOOL`.
+  Boolean = 0x02,
+  I1 = 0x03,
+  U1 = 0x04,
+  I2 = 0x05,
+  U2 = 0x06,
+  I4 = 0x07,
+  U4 = 0x08,
+  I8 = 0x09,
+  U8 = 0x0a,
+  R4 = 0x0b,
+  R8 = 0x0c,
+  /// Deprecated.
+  SysChar = 0x0d,
+  /// Deprecated.
+  Variant = 0x0e,
+  Currency = 0x0f,
+  /// Deprecated.
+  Ptr = 0x10,
+  /// Deprecated.
+  Decimal = 0x11,
+  /// Deprecated.
+  Date = 0x12,
+  BStr = 0x13,
+  LpStr = 0x14,
+  LpWStr = 0x15,
+  LpTStr = 0x16,
+  /// `UnmanagedType.ByValTStr`, a fixed length inline string.
+  FixedSysString = 0x17,
+  /// Deprecated.
+  ObjectRef = 0x18,
+  IUnknown = 0x19,
+  IDispatch = 0x1a,
+  Struct = 0x1b,
+  /// `UnmanagedType.Interface`.
+  Intf = 0x1c,
+  SafeArray = 0x1d,
+  /// `UnmanagedType.ByValArray`, a fixed length inline array.
+  FixedArray = 0x1e,
+  Int = 0x1f,
+  UInt = 0x20,
+  /// Deprecated.
+  NestedStruct = 0x21,
+  ByValStr = 0x22,
+  AnsiBStr = 0x23,
+  TBStr = 0x24,
+  VariantBool = 0x25,
+  Func = 0x26,
+  AsAny = 0x28,
+  /// `UnmanagedType.LPArray`.
+  Array = 0x2a,
+  LpStruct = 0x2b,
+  CustomMarshaler = 0x2c,
+  Error = 0x2d,
+  IInspectable = 0x2e,
+  HString = 0x2f,
+  LpUtf8Str = 0x30,
+  /// Used as the element type of an array to leave it unspecified.
+  Max = 0x50,
+}
+
+impl TryFrom<u32> for NativeType {
+  type Error = Error;
+
+  fn try_from(value: u32) -> Result<Self> {
+    Ok(match value {
+      0x01 => Self::Void,
+      0x02 => Self::Boolean,
+      0x03 => Self::I1,
+      0x04 => Self::U1,
+      0x05 => Self::I2,
+      0x06 => Self::U2,
+      0x07 => Self::I4,
+      0x08 => Self::U4,
+      0x09 => Self::I8,
+      0x0a => Self::U8,
+      0x0b => Self::R4,
+      0x0c => Self::R8,
+      0x0d => Self::SysChar,
+      0x0e => Self::Variant,
+      0x0f => Self::Currency,
+      0x10 => Self::Ptr,
+      0x11 => Self::Decimal,
+      0x12 => Self::Date,
+      0x13 => Self::BStr,
+      0x14 => Self::LpStr,
+      0x15 => Self::LpWStr,
+      0x16 => Self::LpTStr,
+      0x17 => Self::FixedSysString,
+      0x18 => Self::ObjectRef,
+      0x19 => Self::IUnknown,
+      0x1a => Self::IDispatch,
+      0x1b => Self::Struct,
+      0x1c => Self::Intf,
+      0x1d => Self::SafeArray,
+      0x1e => Self::FixedArray,
+      0x1f => Self::Int,
+      0x20 => Self::UInt,
+      0x21 => Self::NestedStruct,
+      0x22 => Self::ByValStr,
+      0x23 => Self::AnsiBStr,
+      0x24 => Self::TBStr,
+      0x25 => Self::VariantBool,
+      0x26 => Self::Func,
+      0x28 => Self::AsAny,
+      0x2a => Self::Array,
+      0x2b => Self::LpStruct,
+      0x2c => Self::CustomMarshaler,
+      0x2d => Self::Error,
+      0x2e => Self::IInspectable,
+      0x2f => Self::HString,
+      0x30 => Self::LpUtf8Str,
+      0x50 => Self::Max,
+      value => bail!("Unknown native type {:#x}", value),
+    })
+  }
+}
+
+/// A decoded `MarshalSpec` blob.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarshalSpec {
+  /// A native type without any parameters.
+  Intrinsic(NativeType),
+  /// `UnmanagedType.ByValTStr`, an inline string of a fixed number of characters.
+  ByValTStr {
+    /// The number of characters, `SizeConst`.
+    size: Option<u32>,
+  },
+  /// `UnmanagedType.ByValArray`, an inline array of a fixed number of elements.
+  ByValArray {
+    /// The number of elements, `SizeConst`.
+    size: Option<u32>,
+    /// The element type, `ArraySubType`.
+    element: Option<NativeType>,
+  },
+  /// `UnmanagedType.LPArray`, a pointer to the first element of a C-style array.
+  LpArray(LpArray),
+  /// `UnmanagedType.SafeArray`.
+  SafeArray {
+    /// The `VARENUM` of the elements, `SafeArraySubType`.
+    variant_type: Option<u32>,
+    /// The name of the element type when the variant type is `VT_RECORD`, `VT_DISPATCH` or
+    /// `VT_UNKNOWN`, `SafeArrayUserDefinedSubType`.
+    user_defined_subtype: Option<String>,
+  },
+  /// `UnmanagedType.CustomMarshaler`.
+  CustomMarshaler {
+    /// The GUID of the marshaler, unused by the runtime.
+    guid: String,
+    /// The unmanaged type name, unused by the runtime.
+    native_type_name: String,
+    /// The name of the `ICustomMarshaler` implementation, `MarshalType` or `MarshalTypeRef`.
+    marshaler: String,
+    /// The string passed to `GetInstance`, `MarshalCookie`.
+    cookie: String,
+  },
+  /// A COM interface pointer.
+  Interface {
+    /// One of [NativeType::IUnknown], [NativeType::IDispatch], [NativeType::Intf] or
+    /// [NativeType::IInspectable].
+    kind: NativeType,
+    /// The parameter holding the interface's IID, `IidParameterIndex`.
+    iid_param_index: Option<u32>,
+  },
+}
+
+/// An `UnmanagedType.LPArray` descriptor.
+///
+/// The number of elements is `param * multiplier + size_const`, where `param` is the value of the
+/// parameter at [LpArray::size_param_index].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LpArray {
+  /// The element type, `ArraySubType`.
+  pub element: Option<NativeType>,
+  /// The zero-based index of the parameter holding the element count, `SizeParamIndex`.
+  pub size_param_index: Option<u32>,
+  /// The fixed number of elements, `SizeConst`.
+  pub size_const: Option<u32>,
+}
+
+impl LpArray {
+  /// Gets the multiplier of the element count parameter, 1 if there's a `SizeParamIndex` and
+  /// otherwise 0.
+  pub fn multiplier(&self) -> u32 {
+    self.size_param_index.is_some() as u32
+  }
+}
+
+impl<'a> TryFromCtx<'a> for MarshalSpec {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], _: ()) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let kind = NativeType::try_from(from.gread::<CompressedU32>(offset)?.0)?;
+
+    // Everything after the native type is optional and the blob simply ends early.
+    let mut optional = || -> Result<Option<u32>> {
+      match *offset < from.len() {
+        true => Ok(Some(from.gread::<CompressedU32>(offset)?.0)),
+        false => Ok(None),
+      }
+    };
+
+    let spec = match kind {
+      NativeType::FixedSysString => Self::ByValTStr { size: optional()? },
+      NativeType::FixedArray => {
+        let size = optional()?;
+        let element = optional()?.map(NativeType::try_from).transpose()?;
+
+        Self::ByValArray { size, element }
+      }
+      NativeType::Array => {
+        let element = optional()?
+          .map(NativeType::try_from)
+          .transpose()?
+          .filter(|element| *element != NativeType::Max);
+        let param = optional()?;
+        let size_const = optional()?;
+
+        // Compilers emit a dummy parameter number to reach `SizeConst`, the flags tell if the
+        // parameter number is real.  Without flags it always is.
+        let size_param_index = match optional()? {
+          Some(flags) if flags & 1 == 0 => None,
+          _ => param,
+        };
+
+        Self::LpArray(LpArray {
+          element,
+          size_param_index,
+          size_const,
+        })
+      }
+      NativeType::SafeArray => {
+        let variant_type = optional()?;
+        let user_defined_subtype = match *offset < from.len() {
+          true => Some(read_string(from, offset)?),
+          false => None,
+        };
+
+        Self::SafeArray {
+          variant_type,
+          user_defined_subtype,
+        }
+      }
+      NativeType::CustomMarshaler => Self::CustomMarshaler {
+        guid: read_string(from, offset)?,
+        native_type_name: read_string(from, offset)?,
+        marshaler: read_string(from, offset)?,
+        cookie: read_string(from, offset)?,
+      },
+      NativeType::IUnknown
+      | NativeType::IDispatch
+      | NativeType::Intf
+      | NativeType::IInspectable => Self::Interface {
+        kind,
+        iid_param_index: optional()?,
+      },
+      kind => Self::Intrinsic(kind),
+    };
+
+    Ok((spec, *offset))
+  }
+}
+
+/// Reads a UTF-8 string prefixed with its compressed length.
+fn read_string(buf: &[u8], offset: &mut usize) -> Result<String> {
+  let len = buf.gread::<CompressedU32>(offset)?.0 as usize;
+  let bytes = match len {
+    0 => &[][..],
+    _ => buf.gread_with::<&[u8]>(offset, len)?,
+  };
+
+  match core::str::from_utf8(bytes) {
+    Ok(value) => Ok(value.into()),
+    Err(err) => bail!("Malformed marshal string, {}", err),
+  }
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the `MarshalSpec` of a `FieldMarshal` row.
+  pub fn marshal_spec(&self, row: &FieldMarshalRow) -> Result<MarshalSpec> {
+    let blob = self.blobs().get(row.native_type)?;
+
+    blob
+      .pread(0)
+      .with_context(|| format!("Marshal descriptor at {:#x}", row.native_type.offset()))
+  }
+
+  /// Gets how a field or parameter is marshalled, `None` if it uses the default marshalling.
+  pub fn field_marshal(&self, parent: HasFieldMarshal) -> Result<Option<MarshalSpec>> {
+    let row = self
+      .tables()
+      .field_marshals()
+      .find(&parent.encode(), |row| row.parent.encode())?;
+
+    row.map(|row| self.marshal_spec(&row)).transpose()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{LpArray, MarshalSpec, NativeType};
+  use scroll::Pread;
+
+  #[test]
+  fn intrinsic() {
+    let spec = [0x15u8].pread::<MarshalSpec>(0).unwrap();
+
+    assert_eq!(spec, MarshalSpec::Intrinsic(NativeType::LpWStr));
+    assert!([0x27u8].pread::<MarshalSpec>(0).is_err());
+  }
+
+  #[test]
+  fn lp_array() {
+    let cases: &[(&[u8], LpArray)] = &[
+      // [MarshalAs(UnmanagedType.LPArray)]
+      (&[0x2a, 0x50], LpArray::default()),
+      // [MarshalAs(UnmanagedType.LPArray, ArraySubType = UnmanagedType.I4, SizeParamIndex = 2)]
+      (
+        &[0x2a, 0x07, 0x02],
+        LpArray {
+          element: Some(NativeType::I4),
+          size_param_index: Some(2),
+          size_const: None,
+        },
+      ),
+      // [MarshalAs(UnmanagedType.LPArray, SizeParamIndex = 1, SizeConst = 4)]
+      (
+        &[0x2a, 0x50, 0x01, 0x04, 0x01],
+        LpArray {
+          element: None,
+          size_param_index: Some(1),
+          size_const: Some(4),
+        },
+      ),
+      // [MarshalAs(UnmanagedType.LPArray, SizeConst = 16)]
+      (
+        &[0x2a, 0x50, 0x00, 0x10, 0x00],
+        LpArray {
+          element: None,
+          size_param_index: None,
+          size_const: Some(16),
+        },
+      ),
+    ];
+
+    for (buf, expected) in cases {
+      let spec = buf.pread::<MarshalSpec>(0).unwrap();
+
+      assert_eq!(spec, MarshalSpec::LpArray(expected.clone()));
+    }
+  }
+
+  #[test]
+  fn by_val() {
+    let spec = [0x17u8, 0x20].pread::<MarshalSpec>(0).unwrap();
+    assert_eq!(spec, MarshalSpec::ByValTStr { size: Some(32) });
+
+    let spec = [0x1eu8, 0x08, 0x04].pread::<MarshalSpec>(0).unwrap();
+    assert_eq!(
+      spec,
+      MarshalSpec::ByValArray {
+        size: Some(8),
+        element: Some(NativeType::U1),
+      }
+    );
+  }
+
+  #[test]
+  fn safe_array() {
+    // VT_RECORD with a user defined subtype.
+    let spec = [0x1du8, 0x24, 0x03, b'F', b'o', b'o']
+      .pread::<MarshalSpec>(0)
+      .unwrap();
+
+    assert_eq!(
+      spec,
+      MarshalSpec::SafeArray {
+        variant_type: Some(0x24),
+        user_defined_subtype: Some("Foo".into()),
+      }
+    );
+  }
+
+  #[test]
+  fn custom_marshaler() {
+    let spec = [0x2cu8, 0x00, 0x00, 0x03, b'M', b'a', b'r', 0x01, b'c']
+      .pread::<MarshalSpec>(0)
+      .unwrap();
+
+    assert_eq!(
+      spec,
+      MarshalSpec::CustomMarshaler {
+        guid: "".into(),
+        native_type_name: "".into(),
+        marshaler: "Mar".into(),
+        cookie: "c".into(),
+      }
+    );
+
+    // The cookie is missing.
+    assert!([0x2cu8, 0x00, 0x00, 0x00].pread::<MarshalSpec>(0).is_err());
+  }
+
+  #[test]
+  fn interface() {
+    let spec = [0x1cu8, 0x01].pread::<MarshalSpec>(0).unwrap();
+
+    assert_eq!(
+      spec,
+      MarshalSpec::Interface {
+        kind: NativeType::Intf,
+        iid_param_index: Some(1),
+      }
+    );
+  }
+}
@@ -0,0 +1,356 @@
+//! Portable PDB debug metadata.
+//!
+//! A portable PDB is a metadata blob with a `#Pdb` stream and the debug tables `0x30` through
+//! `0x37`.  The debug tables refer to the type system tables of the module the PDB belongs to,
+//! whose row counts are stored in the `#Pdb` stream so the width of those indexes is known.
+
+pub mod debug_info;
+#[doc(inline)]
+pub use debug_info::*;
+
+mod inflate;
+
+use super::{
+  blobs::BlobIndex,
+  compressed::{CompressedI32, CompressedU32},
+  guids::Guid,
+  tables::{DocumentRowId, MethodDebugInformationRowId, StandAloneSigRowId, TablesHeader},
+  token::Token,
+  Md, StreamHeader,
+};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::{bail, Context, Result};
+use scroll::{Pread, LE};
+
+/// The `Language` of a C# document.
+pub const LANGUAGE_CSHARP: Guid = Guid::from_bytes([
+  0xf8, 0x62, 0x51, 0x3f, 0xc6, 0x07, 0xd3, 0x11, 0x90, 0x53, 0x00, 0xc0, 0x4f, 0xa3, 0x02, 0xa1,
+]);
+/// The `Language` of a Visual Basic document.
+pub const LANGUAGE_VISUAL_BASIC: Guid = Guid::from_bytes([
+  0xd0, 0x12, 0x3a, 0x3a, 0xa6, 0x44, 0xd3, 0x11, 0x97, 0xe7, 0x00, 0xc0, 0x4f, 0x68, 0x41, 0x8a,
+]);
+/// The `Language` of an F# document.
+pub const LANGUAGE_FSHARP: Guid = Guid::from_bytes([
+  0xa2, 0x2a, 0x3c, 0xab, 0x1b, 0x18, 0x8d, 0x4a, 0x9e, 0x5c, 0x3d, 0x4a, 0x4d, 0xc0, 0xe1, 0x7b,
+]);
+/// The `HashAlgorithm` of a document hashed with SHA-1.
+pub const HASH_SHA1: Guid = Guid::from_bytes([
+  0xec, 0x16, 0x18, 0xff, 0x5e, 0xaa, 0x10, 0x4d, 0x87, 0xf7, 0x6f, 0x49, 0x63, 0x83, 0x34, 0x60,
+]);
+/// The `HashAlgorithm` of a document hashed with SHA-256.
+pub const HASH_SHA256: Guid = Guid::from_bytes([
+  0x0f, 0xd0, 0x29, 0x88, 0xb8, 0x11, 0x13, 0x42, 0x87, 0x8b, 0x77, 0x0e, 0x85, 0x97, 0xac, 0x16,
+]);
+
+/// The `#Pdb` stream of a portable PDB.
+#[derive(Debug, Clone, Copy)]
+pub struct PdbStream {
+  /// The PDB id, matching the id in the CodeView debug directory entry of the module.
+  pub id: [u8; 20],
+  /// The entry point of the module, null for libraries.
+  pub entry_point: Token,
+  /// Bit vector of the type system tables the debug tables refer to.
+  pub referenced_tables: u64,
+  /// The row counts of the referenced type system tables, indexed by the id of the table.
+  pub type_system_rows: [u32; 64],
+}
+
+impl PdbStream {
+  /// Parses the `#Pdb` stream from the given [StreamHeader].
+  ///
+  /// # Note
+  /// The stream header must be for the `#Pdb` stream and isn't verified here in release builds.
+  pub fn parse_from_header(header: StreamHeader<'_>) -> Result<Self> {
+    debug_assert!(matches!(header.name.to_bytes(), b"#Pdb"));
+
+    let buf = header.data()?;
+    let offset = &mut 0;
+    let id = buf.gread_with::<&[u8]>(offset, 20)?.try_into()?;
+    let entry_point = Token::from(buf.gread_with::<u32>(offset, LE)?);
+    let referenced_tables = buf.gread_with::<u64>(offset, LE)?;
+    let mut type_system_rows = [0; 64];
+
+    for (i, rows) in type_system_rows.iter_mut().enumerate() {
+      if referenced_tables & (1 << i) != 0 {
+        *rows = buf.gread_with(offset, LE)?;
+      }
+    }
+
+    Ok(Self {
+      id,
+      entry_point,
+      referenced_tables,
+      type_system_rows,
+    })
+  }
+
+  /// Gets the guid part of the PDB id.
+  pub fn guid(&self) -> Guid {
+    let mut guid = [0; 16];
+    guid.copy_from_slice(&self.id[..16]);
+
+    Guid::from_bytes(guid)
+  }
+
+  /// Gets the timestamp part of the PDB id.
+  pub fn stamp(&self) -> u32 {
+    u32::from_le_bytes([self.id[16], self.id[17], self.id[18], self.id[19]])
+  }
+}
+
+/// The sequence points of a method.
+#[derive(Debug, Clone, Default)]
+pub struct SequencePoints {
+  /// The local signature of the method, `None` if it has no locals.
+  pub local_signature: Option<StandAloneSigRowId>,
+  /// The sequence points ordered by IL offset.
+  pub points: Vec<SequencePoint>,
+}
+
+impl SequencePoints {
+  /// Finds the sequence point covering an IL offset, the last one starting at or before it, or
+  /// `None` if there's no such point or it's hidden.
+  pub fn find(&self, il_offset: u32) -> Option<&SequencePoint> {
+    let point = match self
+      .points
+      .partition_point(|point| point.il_offset <= il_offset)
+    {
+      0 => return None,
+      i => &self.points[i - 1],
+    };
+
+    match point.is_hidden() {
+      true => None,
+      false => Some(point),
+    }
+  }
+}
+
+/// Maps an IL offset to a span of source.  Lines and columns are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencePoint {
+  pub document: DocumentRowId,
+  pub il_offset: u32,
+  pub start_line: u32,
+  pub start_column: u16,
+  pub end_line: u32,
+  pub end_column: u16,
+}
+
+impl SequencePoint {
+  /// The line of hidden sequence points, which don't map to any source.
+  pub const HIDDEN_LINE: u32 = 0xfeefee;
+
+  /// Determines if the sequence point doesn't map to any source, e.g. compiler generated code.
+  pub fn is_hidden(&self) -> bool {
+    self.start_line == Self::HIDDEN_LINE
+  }
+}
+
+impl<'a> Md<'a> {
+  /// Gets the `#Pdb` stream, `None` if the metadata isn't a portable PDB.
+  pub fn pdb(&self) -> Option<&PdbStream> {
+    self.pdb.as_ref()
+  }
+
+  /// Decodes a document name blob, the parts of the name joined by a separator.
+  pub fn document_name(&self, name: BlobIndex) -> Result<String> {
+    let blob = self.blobs().get(name)?;
+    let offset = &mut 0;
+    let separator = blob.gread::<u8>(offset).context("Empty document name")?;
+    if !separator.is_ascii() {
+      bail!("Document name separator {:#x} isn't ascii", separator);
+    }
+
+    let mut name = String::new();
+    while *offset < blob.len() {
+      if *offset > 1 && separator != 0 {
+        name.push(separator as char);
+      }
+
+      let CompressedU32(part) = blob.gread(offset)?;
+      let part = self.blobs().get(BlobIndex::new(part as usize))?;
+      name.push_str(core::str::from_utf8(part).context("Document name part isn't utf-8")?);
+    }
+
+    Ok(name)
+  }
+
+  /// Decodes the sequence points of a method.
+  pub fn sequence_points(&self, id: MethodDebugInformationRowId) -> Result<SequencePoints> {
+    let row = self.tables().method_debug_information().read(id)?;
+    let blob = self.blobs().get(row.sequence_points)?;
+
+    decode_sequence_points(blob, row.document, self.tables().header())
+      .with_context(|| format!("Sequence points at {:#x}", row.sequence_points.offset()))
+  }
+}
+
+/// Decodes a sequence points blob, `document` being the initial document of the method if it
+/// doesn't change documents.
+fn decode_sequence_points(
+  blob: &[u8],
+  document: Option<DocumentRowId>,
+  header: &TablesHeader,
+) -> Result<SequencePoints> {
+  if blob.is_empty() {
+    return Ok(SequencePoints::default());
+  }
+
+  let offset = &mut 0;
+  let local_signature = match blob.gread::<CompressedU32>(offset)?.0 {
+    0 => None,
+    row => Some(StandAloneSigRowId::new(row, header)?),
+  };
+
+  let mut document = match document {
+    Some(document) => document,
+    None => DocumentRowId::new(blob.gread::<CompressedU32>(offset)?.0, header)?,
+  };
+
+  let mut points = Vec::new();
+  let mut il_offset = 0u32;
+  // The start of the previous visible point, which the start of the next one is relative to.
+  let mut start: Option<(u32, u16)> = None;
+
+  while *offset < blob.len() {
+    let CompressedU32(delta_il_offset) = blob.gread(offset)?;
+    if delta_il_offset == 0 && !points.is_empty() {
+      document = DocumentRowId::new(blob.gread::<CompressedU32>(offset)?.0, header)?;
+      continue;
+    }
+
+    il_offset = match il_offset.checked_add(delta_il_offset) {
+      Some(il_offset) => il_offset,
+      None => bail!("IL offset overflows after IL_{:04x}", il_offset),
+    };
+
+    let CompressedU32(delta_lines) = blob.gread(offset)?;
+    let delta_columns = match delta_lines {
+      0 => blob.gread::<CompressedU32>(offset)?.0 as i32,
+      _ => blob.gread::<CompressedI32>(offset)?.0,
+    };
+
+    if delta_lines == 0 && delta_columns == 0 {
+      points.push(SequencePoint {
+        document,
+        il_offset,
+        start_line: SequencePoint::HIDDEN_LINE,
+        start_column: 0,
+        end_line: SequencePoint::HIDDEN_LINE,
+        end_column: 0,
+      });
+      continue;
+    }
+
+    let (start_line, start_column) = match start {
+      None => (
+        Some(blob.gread::<CompressedU32>(offset)?.0),
+        i32::try_from(blob.gread::<CompressedU32>(offset)?.0).ok(),
+      ),
+      Some((line, column)) => (
+        line.checked_add_signed(blob.gread::<CompressedI32>(offset)?.0),
+        (column as i32).checked_add(blob.gread::<CompressedI32>(offset)?.0),
+      ),
+    };
+
+    let out_of_range = |what| format!("{} out of range at IL_{:04x}", what, il_offset);
+    let start_line = start_line.with_context(|| out_of_range("Start line"))?;
+    let start_column = start_column
+      .and_then(|column| u16::try_from(column).ok())
+      .with_context(|| out_of_range("Start column"))?;
+    let end_line = start_line
+      .checked_add(delta_lines)
+      .with_context(|| out_of_range("End line"))?;
+    let end_column = (start_column as i32)
+      .checked_add(delta_columns)
+      .and_then(|column| u16::try_from(column).ok())
+      .with_context(|| out_of_range("End column"))?;
+
+    start = Some((start_line, start_column));
+    points.push(SequencePoint {
+      document,
+      il_offset,
+      start_line,
+      start_column,
+      end_line,
+      end_column,
+    });
+  }
+
+  Ok(SequencePoints {
+    local_signature,
+    points,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{decode_sequence_points, SequencePoint};
+  use crate::ecma335::tables::{DocumentRow, DocumentRowId, Row, StandAloneSigRow, TablesHeader};
+
+  #[test]
+  fn sequence_points_blob() {
+    let header = TablesHeader::with_rows(&[(DocumentRow::ID, 2), (StandAloneSigRow::ID, 1)]);
+    let document = DocumentRowId::new(1, &header).unwrap();
+
+    #[rustfmt::skip]
+    let blob = [
+      // LocalSignature, no locals
+      0x00,
+      // IL_0000, 10:3 - 10:8
+      0x00, 0x00, 0x05, 0x0a, 0x03,
+      // IL_0004, 11:2 - 12:4, relative to the previous point
+      0x04, 0x01, 0x04, 0x02, 0x7f,
+      // IL_0006, hidden
+      0x02, 0x00, 0x00,
+      // Document 2
+      0x00, 0x02,
+      // IL_0007, 13:2 - 13:3
+      0x01, 0x00, 0x01, 0x04, 0x00,
+    ];
+
+    let points = decode_sequence_points(&blob, Some(document), &header).unwrap();
+    assert!(points.local_signature.is_none());
+
+    let spans = points.points.iter().map(|point| {
+      (
+        point.document.row(),
+        point.il_offset,
+        (point.start_line, point.start_column),
+        (point.end_line, point.end_column),
+      )
+    });
+    let hidden = (SequencePoint::HIDDEN_LINE, 0);
+    assert!(spans.eq([
+      (1, 0, (10, 3), (10, 8)),
+      (1, 4, (11, 2), (12, 4)),
+      (1, 6, hidden, hidden),
+      (2, 7, (13, 2), (13, 3)),
+    ]));
+
+    assert_eq!(points.find(5).unwrap().start_line, 11);
+    assert!(points.find(6).is_none());
+  }
+
+  #[test]
+  fn reject_overflowing_sequence_points() {
+    let header = TablesHeader::with_rows(&[(DocumentRow::ID, 1)]);
+    let document = DocumentRowId::new(1, &header).unwrap();
+    let decode = |blob: &[u8]| decode_sequence_points(blob, Some(document), &header);
+
+    // IL offsets summing past `u32::MAX`
+    let mut blob = vec![0x00];
+    for _ in 0..9 {
+      blob.extend([0xdf, 0xff, 0xff, 0xff, 0x00, 0x00]);
+    }
+    assert!(decode(&blob[..1 + 8 * 6]).is_ok());
+    assert!(decode(&blob).is_err());
+
+    // A start line before line 0
+    assert!(decode(&[0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x00, 0x01, 0x03, 0x00]).is_err());
+    // An end column past `u16::MAX`
+    assert!(decode(&[0x00, 0x00, 0x00, 0xdf, 0xff, 0xff, 0xff, 0x01, 0x01]).is_err());
+  }
+}
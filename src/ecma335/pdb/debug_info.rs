@@ -0,0 +1,476 @@
+//! Custom debug information (the `CustomDebugInformation` table).
+//!
+//! Each row attaches a blob to a module, method, document, scope or variable, the layout of the
+//! blob being identified by the `Kind` guid.  The well-known kinds written by the Roslyn compilers
+//! are decoded here, anything else is left as the raw blob.
+
+use super::inflate::inflate;
+use crate::ecma335::{
+  compressed::CompressedU32, guids::Guid, tables::CustomDebugInformationRow, Md,
+};
+use alloc::{borrow::Cow, format, vec::Vec};
+use anyhow::{bail, Context, Result};
+use scroll::{Pread, LE};
+
+/// The Source Link JSON of the module.
+pub const SOURCE_LINK: Guid = Guid::from_bytes([
+  0x56, 0x05, 0x11, 0xcc, 0x91, 0xa0, 0x38, 0x4d, 0x9f, 0xec, 0x25, 0xab, 0x9a, 0x35, 0x1a, 0x6a,
+]);
+/// The source of a document embedded in the PDB.
+pub const EMBEDDED_SOURCE: Guid = Guid::from_bytes([
+  0x1b, 0x57, 0x8a, 0x0e, 0x26, 0x69, 0x6e, 0x46, 0xb4, 0xad, 0x8a, 0xb0, 0x46, 0x11, 0xf5, 0xfe,
+]);
+/// The scopes of the locals hoisted to fields of a state machine.
+pub const STATE_MACHINE_HOISTED_LOCAL_SCOPES: Guid = Guid::from_bytes([
+  0x1e, 0xa6, 0xa9, 0x6d, 0xc7, 0xf8, 0x74, 0x48, 0xbe, 0x62, 0x68, 0xbc, 0x56, 0x30, 0xdf, 0x71,
+]);
+/// Which types of a local's type are `dynamic`.
+pub const DYNAMIC_LOCAL_VARIABLES: Guid = Guid::from_bytes([
+  0xc4, 0x63, 0xc5, 0x83, 0xf3, 0xb4, 0xd5, 0x47, 0xb8, 0x24, 0xba, 0x54, 0x41, 0x47, 0x7e, 0xa8,
+]);
+/// The element names of the tuples in a local's type.
+pub const TUPLE_ELEMENT_NAMES: Guid = Guid::from_bytes([
+  0x71, 0xdf, 0x9f, 0xed, 0x79, 0x88, 0x47, 0x47, 0x8e, 0xd3, 0xfe, 0x5e, 0xde, 0x3c, 0xe7, 0x10,
+]);
+/// The default namespace of a Visual Basic project.
+pub const DEFAULT_NAMESPACE: Guid = Guid::from_bytes([
+  0xb6, 0xea, 0xb2, 0x58, 0x9f, 0x20, 0x4e, 0x4e, 0xa2, 0x2c, 0xb2, 0xd0, 0xf9, 0x10, 0xc7, 0x82,
+]);
+/// The syntax the local slots of a method were allocated for, used by edit and continue.
+pub const ENC_LOCAL_SLOT_MAP: Guid = Guid::from_bytes([
+  0xa8, 0x52, 0x5f, 0x75, 0xc5, 0x91, 0xbe, 0x45, 0xb4, 0xb8, 0x20, 0x95, 0x71, 0xe5, 0x52, 0xbd,
+]);
+/// The syntax the lambdas and closures of a method were emitted for, used by edit and continue.
+pub const ENC_LAMBDA_AND_CLOSURE_MAP: Guid = Guid::from_bytes([
+  0x4c, 0x00, 0x43, 0xa6, 0x40, 0x02, 0x6f, 0x49, 0xa7, 0x83, 0x30, 0xd6, 0x4f, 0x49, 0x79, 0xde,
+]);
+/// The options the module was compiled with.
+pub const COMPILATION_OPTIONS: Guid = Guid::from_bytes([
+  0x05, 0xec, 0xfe, 0xb5, 0xd0, 0x8c, 0x83, 0x4a, 0x96, 0xda, 0x46, 0x62, 0x84, 0xbb, 0x4b, 0xd8,
+]);
+/// The metadata references the module was compiled against.
+pub const COMPILATION_METADATA_REFERENCES: Guid = Guid::from_bytes([
+  0x08, 0x47, 0x4d, 0x7e, 0x6e, 0x09, 0x5c, 0x4c, 0xae, 0xda, 0xcb, 0x10, 0xba, 0x6a, 0x74, 0x0d,
+]);
+
+/// Decoded custom debug information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomDebugInfo<'a> {
+  /// The Source Link JSON mapping document paths to URLs.
+  SourceLink(&'a str),
+  /// The content of a document, decompressed if it was stored deflated.
+  EmbeddedSource(Cow<'a, [u8]>),
+  /// The scope of each hoisted local, indexed by the slot of its field in the state machine.
+  StateMachineHoistedLocalScopes(Vec<HoistedLocalScope>),
+  /// A flag per type in the pre-order traversal of a local's type, set if the type is `dynamic`.
+  DynamicLocalVariables(Vec<bool>),
+  /// The element names of the tuples in the pre-order traversal of a local's type, `None` for
+  /// unnamed elements.
+  TupleElementNames(Vec<Option<&'a str>>),
+  /// The default namespace of a Visual Basic project.
+  DefaultNamespace(&'a str),
+  /// The local slots of a method in order.
+  EncLocalSlotMap(Vec<LocalSlot>),
+  /// The lambdas and closures of a method.
+  EncLambdaAndClosureMap(LambdaMap),
+  /// The compiler options as name/value pairs, e.g. `("language", "C#")`.
+  CompilationOptions(Vec<(&'a str, &'a str)>),
+  /// The metadata references passed to the compiler.
+  CompilationMetadataReferences(Vec<MetadataReference<'a>>),
+  /// Custom debug information of an unknown kind.
+  Unknown(Guid, &'a [u8]),
+}
+
+/// The IL range a hoisted local is in scope for, empty if the slot is unused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoistedLocalScope {
+  pub start_offset: u32,
+  pub length: u32,
+}
+
+/// What a local slot was allocated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalSlot {
+  /// A short-lived temporary that's never reused across edits.
+  Temp,
+  /// A local declared in source or synthesized for a construct.
+  Local {
+    /// The compiler's `SynthesizedLocalKind`, 0 for locals declared in source.
+    kind: u8,
+    /// The offset of the declaring syntax from the start of the method body.
+    syntax_offset: i32,
+    /// Distinguishes locals of the same kind declared by the same syntax.
+    ordinal: u32,
+  },
+}
+
+/// The lambdas and closures of a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LambdaMap {
+  /// The ordinal of the method among the overloads of its name, `None` if it has no ordinal.
+  pub method_ordinal: Option<u32>,
+  /// The syntax offset of each closure scope.
+  pub closures: Vec<i32>,
+  pub lambdas: Vec<Lambda>,
+}
+
+/// A lambda of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lambda {
+  /// The offset of the lambda's syntax from the start of the method body.
+  pub syntax_offset: i32,
+  /// Where the lambda is emitted.
+  pub closure: LambdaClosure,
+}
+
+/// Where a lambda is emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LambdaClosure {
+  /// On the containing type, capturing only `this`.
+  This,
+  /// On a singleton, capturing nothing.
+  Static,
+  /// On the display class of the closure with the given index in [LambdaMap::closures].
+  Closure(u32),
+}
+
+bitflags::bitflags! {
+  pub struct MetadataReferenceFlags : u8 {
+    /// The reference is an assembly, not a module.
+    const ASSEMBLY = 0x01;
+    /// The interop types of the reference are embedded in the module.
+    const EMBED_INTEROP_TYPES = 0x02;
+  }
+}
+
+/// A metadata reference passed to the compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataReference<'a> {
+  /// The file name of the reference, without its directory.
+  pub file_name: &'a str,
+  /// The extern aliases of the reference.
+  pub aliases: Vec<&'a str>,
+  pub flags: MetadataReferenceFlags,
+  /// The `TimeDateStamp` of the reference's PE header.
+  pub time_stamp: u32,
+  /// The `SizeOfImage` of the reference's PE header.
+  pub file_size: u32,
+  /// The module version id of the reference.
+  pub mvid: Guid,
+}
+
+impl<'a> CustomDebugInfo<'a> {
+  /// Decodes the blob of custom debug information of the given kind.
+  pub fn parse(kind: Guid, blob: &'a [u8]) -> Result<Self> {
+    Ok(match kind {
+      SOURCE_LINK => Self::SourceLink(utf8(blob)?),
+      EMBEDDED_SOURCE => Self::EmbeddedSource(embedded_source(blob)?),
+      STATE_MACHINE_HOISTED_LOCAL_SCOPES => {
+        if !blob.len().is_multiple_of(8) {
+          bail!("Malformed hoisted local scopes, length {}", blob.len());
+        }
+
+        let scopes = blob
+          .chunks_exact(8)
+          .map(|scope| HoistedLocalScope {
+            start_offset: u32::from_le_bytes([scope[0], scope[1], scope[2], scope[3]]),
+            length: u32::from_le_bytes([scope[4], scope[5], scope[6], scope[7]]),
+          })
+          .collect();
+
+        Self::StateMachineHoistedLocalScopes(scopes)
+      }
+      DYNAMIC_LOCAL_VARIABLES => Self::DynamicLocalVariables(
+        (0..blob.len() * 8)
+          .map(|i| blob[i / 8] & (1 << (i % 8)) != 0)
+          .collect(),
+      ),
+      TUPLE_ELEMENT_NAMES => {
+        let offset = &mut 0;
+        let mut names = Vec::new();
+        while *offset < blob.len() {
+          names.push(Some(cstr(blob, offset)?).filter(|name| !name.is_empty()));
+        }
+
+        Self::TupleElementNames(names)
+      }
+      DEFAULT_NAMESPACE => Self::DefaultNamespace(utf8(blob)?),
+      ENC_LOCAL_SLOT_MAP => Self::EncLocalSlotMap(local_slot_map(blob)?),
+      ENC_LAMBDA_AND_CLOSURE_MAP => Self::EncLambdaAndClosureMap(lambda_map(blob)?),
+      COMPILATION_OPTIONS => {
+        let offset = &mut 0;
+        let mut options = Vec::new();
+        while *offset < blob.len() {
+          options.push((cstr(blob, offset)?, cstr(blob, offset)?));
+        }
+
+        Self::CompilationOptions(options)
+      }
+      COMPILATION_METADATA_REFERENCES => {
+        let offset = &mut 0;
+        let mut references = Vec::new();
+        while *offset < blob.len() {
+          references.push(metadata_reference(blob, offset)?);
+        }
+
+        Self::CompilationMetadataReferences(references)
+      }
+      kind => Self::Unknown(kind, blob),
+    })
+  }
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the value of a `CustomDebugInformation` row.
+  pub fn custom_debug_info(&self, row: &CustomDebugInformationRow) -> Result<CustomDebugInfo<'a>> {
+    let kind = self
+      .guids()
+      .get(row.kind)?
+      .context("Custom debug information without a kind")?;
+    let blob = self.blobs().get(row.value)?;
+
+    CustomDebugInfo::parse(kind, blob).with_context(|| {
+      format!(
+        "Custom debug information {} at {:#x}",
+        kind,
+        row.value.offset()
+      )
+    })
+  }
+}
+
+/// Decodes an embedded source, a 4 byte format followed by the content, which is deflated when
+/// the format is the positive size of the content.
+fn embedded_source(blob: &[u8]) -> Result<Cow<'_, [u8]>> {
+  let format = blob
+    .pread_with::<i32>(0, LE)
+    .context("Truncated embedded source")?;
+  let content = &blob[4..];
+
+  match format {
+    0 => Ok(Cow::Borrowed(content)),
+    1.. => Ok(Cow::Owned(inflate(content, format as usize)?)),
+    _ => bail!("Unknown embedded source format {}", format),
+  }
+}
+
+/// Decodes a local slot map, a byte per slot holding the kind (plus one) in the low 6 bits and
+/// whether an ordinal follows in the high bit, followed by the syntax offset.  Syntax offsets are
+/// relative to a baseline that's -1 unless a `0xff` byte followed by the negated baseline comes
+/// first.
+fn local_slot_map(blob: &[u8]) -> Result<Vec<LocalSlot>> {
+  let offset = &mut 0;
+  let mut baseline = -1;
+  let mut slots = Vec::new();
+
+  while *offset < blob.len() {
+    let slot = blob.gread::<u8>(offset)?;
+    if slot == 0xff {
+      baseline = -(blob.gread::<CompressedU32>(offset)?.0 as i32);
+      continue;
+    }
+
+    if slot == 0 {
+      slots.push(LocalSlot::Temp);
+      continue;
+    }
+
+    let kind = match slot & 0x3f {
+      0 => bail!("Local slot {:#x} without a kind", slot),
+      kind => kind - 1,
+    };
+
+    let syntax_offset = blob.gread::<CompressedU32>(offset)?.0 as i32 + baseline;
+    let ordinal = match slot & 0x80 {
+      0 => 0,
+      _ => blob.gread::<CompressedU32>(offset)?.0,
+    };
+
+    slots.push(LocalSlot::Local {
+      kind,
+      syntax_offset,
+      ordinal,
+    });
+  }
+
+  Ok(slots)
+}
+
+/// Decodes a lambda and closure map, the method ordinal (plus one), the negated syntax offset
+/// baseline, the closures and then the lambdas until the end of the blob.  Closure ordinals are
+/// stored plus two so `this` only and static lambdas are representable.
+fn lambda_map(blob: &[u8]) -> Result<LambdaMap> {
+  let offset = &mut 0;
+  let method_ordinal = blob.gread::<CompressedU32>(offset)?.0.checked_sub(1);
+  let baseline = -(blob.gread::<CompressedU32>(offset)?.0 as i32);
+  let closures = blob.gread::<CompressedU32>(offset)?.0;
+  let closures = (0..closures)
+    .map(|_| Ok(blob.gread::<CompressedU32>(offset)?.0 as i32 + baseline))
+    .collect::<Result<Vec<_>>>()?;
+
+  let mut lambdas = Vec::new();
+  while *offset < blob.len() {
+    let syntax_offset = blob.gread::<CompressedU32>(offset)?.0 as i32 + baseline;
+    let closure = match blob.gread::<CompressedU32>(offset)?.0 {
+      0 => LambdaClosure::This,
+      1 => LambdaClosure::Static,
+      closure if closure - 2 < closures.len() as u32 => LambdaClosure::Closure(closure - 2),
+      closure => bail!("Lambda closure {} out of range", closure - 2),
+    };
+
+    lambdas.push(Lambda {
+      syntax_offset,
+      closure,
+    });
+  }
+
+  Ok(LambdaMap {
+    method_ordinal,
+    closures,
+    lambdas,
+  })
+}
+
+/// Decodes a metadata reference, the null terminated file name and comma separated aliases
+/// followed by the flags, time stamp, file size and mvid.
+fn metadata_reference<'a>(blob: &'a [u8], offset: &mut usize) -> Result<MetadataReference<'a>> {
+  let file_name = cstr(blob, offset)?;
+  let aliases = match cstr(blob, offset)? {
+    "" => Vec::new(),
+    aliases => aliases.split(',').collect(),
+  };
+
+  let flags = MetadataReferenceFlags::from_bits_truncate(blob.gread(offset)?);
+  let time_stamp = blob.gread_with(offset, LE)?;
+  let file_size = blob.gread_with(offset, LE)?;
+  let mvid = blob.gread_with::<&[u8]>(offset, 16)?;
+
+  Ok(MetadataReference {
+    file_name,
+    aliases,
+    flags,
+    time_stamp,
+    file_size,
+    mvid: Guid::from_bytes(mvid.try_into()?),
+  })
+}
+
+/// Reads a null terminated utf-8 string.
+fn cstr<'a>(blob: &'a [u8], offset: &mut usize) -> Result<&'a str> {
+  let rest = &blob[*offset..];
+  let len = rest
+    .iter()
+    .position(|&b| b == 0)
+    .context("Unterminated string")?;
+
+  *offset += len + 1;
+  utf8(&rest[..len])
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+  core::str::from_utf8(bytes).context("String isn't utf-8")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    CustomDebugInfo, LambdaClosure, LocalSlot, COMPILATION_OPTIONS, EMBEDDED_SOURCE,
+    ENC_LAMBDA_AND_CLOSURE_MAP, ENC_LOCAL_SLOT_MAP, TUPLE_ELEMENT_NAMES,
+  };
+
+  #[test]
+  fn embedded_source() {
+    let raw = b"\0\0\0\0class C {}";
+    let CustomDebugInfo::EmbeddedSource(source) =
+      CustomDebugInfo::parse(EMBEDDED_SOURCE, raw).unwrap()
+    else {
+      panic!()
+    };
+    assert_eq!(&*source, b"class C {}");
+
+    // `class C {}` deflated with fixed codes.
+    let deflated = [
+      0x0a, 0x00, 0x00, 0x00, 0x4b, 0xce, 0x49, 0x2c, 0x2e, 0x56, 0x70, 0x56, 0xa8, 0xae, 0x05,
+      0x00,
+    ];
+    let CustomDebugInfo::EmbeddedSource(source) =
+      CustomDebugInfo::parse(EMBEDDED_SOURCE, &deflated).unwrap()
+    else {
+      panic!()
+    };
+    assert_eq!(&*source, b"class C {}");
+
+    assert!(CustomDebugInfo::parse(EMBEDDED_SOURCE, &[0xff, 0xff, 0xff, 0xff]).is_err());
+    assert!(CustomDebugInfo::parse(EMBEDDED_SOURCE, &[0x0b, 0, 0, 0, 0x4b, 0xce]).is_err());
+  }
+
+  #[test]
+  fn tuple_element_names() {
+    assert_eq!(
+      CustomDebugInfo::parse(TUPLE_ELEMENT_NAMES, b"a\0\0b\0").unwrap(),
+      CustomDebugInfo::TupleElementNames(vec![Some("a"), None, Some("b")])
+    );
+    assert!(CustomDebugInfo::parse(TUPLE_ELEMENT_NAMES, b"a").is_err());
+  }
+
+  #[test]
+  fn local_slot_map() {
+    let slots = CustomDebugInfo::parse(
+      ENC_LOCAL_SLOT_MAP,
+      &[0xff, 0x04, 0x01, 0x05, 0x00, 0x87, 0x09, 0x02],
+    )
+    .unwrap();
+    assert_eq!(
+      slots,
+      CustomDebugInfo::EncLocalSlotMap(vec![
+        LocalSlot::Local {
+          kind: 0,
+          syntax_offset: 1,
+          ordinal: 0
+        },
+        LocalSlot::Temp,
+        LocalSlot::Local {
+          kind: 6,
+          syntax_offset: 5,
+          ordinal: 2
+        },
+      ])
+    );
+  }
+
+  #[test]
+  fn lambda_map() {
+    let CustomDebugInfo::EncLambdaAndClosureMap(map) = CustomDebugInfo::parse(
+      ENC_LAMBDA_AND_CLOSURE_MAP,
+      &[0x00, 0x01, 0x01, 0x05, 0x0a, 0x02, 0x0b, 0x00, 0x0c, 0x01],
+    )
+    .unwrap() else {
+      panic!()
+    };
+
+    assert_eq!(map.method_ordinal, None);
+    assert_eq!(map.closures, [4]);
+    let closures = map
+      .lambdas
+      .iter()
+      .map(|lambda| (lambda.syntax_offset, lambda.closure));
+    assert!(closures.eq([
+      (9, LambdaClosure::Closure(0)),
+      (10, LambdaClosure::This),
+      (11, LambdaClosure::Static)
+    ]));
+
+    assert!(
+      CustomDebugInfo::parse(ENC_LAMBDA_AND_CLOSURE_MAP, &[0x01, 0x01, 0x00, 0x05, 0x02]).is_err()
+    );
+  }
+
+  #[test]
+  fn compilation_options() {
+    assert_eq!(
+      CustomDebugInfo::parse(COMPILATION_OPTIONS, b"language\0C#\0optimization\0debug\0").unwrap(),
+      CustomDebugInfo::CompilationOptions(vec![("language", "C#"), ("optimization", "debug")])
+    );
+    assert!(CustomDebugInfo::parse(COMPILATION_OPTIONS, b"language\0").is_err());
+  }
+}
@@ -0,0 +1,365 @@
+//! A raw deflate (RFC 1951) decompressor for embedded sources.
+
+use alloc::{vec, vec::Vec};
+use anyhow::{bail, Context, Result};
+
+/// The longest Huffman code in a deflate stream.
+const MAX_BITS: usize = 15;
+
+/// The base lengths of the length symbols 257 through 285.
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+  163, 195, 227, 258,
+];
+/// The extra bits of the length symbols 257 through 285.
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// The base distances of the distance symbols.
+const DIST_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+  3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// The extra bits of the distance symbols.
+const DIST_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// The order the code lengths of the code length alphabet are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses a raw deflate stream, `size` being the expected size of the output.
+pub(crate) fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>> {
+  let mut bits = Bits::new(data);
+  // The size comes from the blob, so it's only trusted as a limit and not to preallocate, a
+  // deflate stream inflating at most 1032:1.
+  let mut out = Vec::with_capacity(size.min(data.len().saturating_mul(1032)));
+
+  loop {
+    let last = bits.read(1)? == 1;
+    match bits.read(2)? {
+      0 => bits.stored(&mut out)?,
+      1 => {
+        let (lengths, distances) = fixed();
+        bits.codes(&mut out, size, &lengths, &distances)?;
+      }
+      2 => {
+        let (lengths, distances) = bits.dynamic()?;
+        bits.codes(&mut out, size, &lengths, &distances)?;
+      }
+      _ => bail!("Invalid deflate block type"),
+    }
+
+    if out.len() > size {
+      bail!("Deflate stream inflates past {} bytes", size);
+    }
+
+    if last {
+      break;
+    }
+  }
+
+  if out.len() != size {
+    bail!(
+      "Deflate stream inflated to {} bytes, expected {}",
+      out.len(),
+      size
+    );
+  }
+
+  Ok(out)
+}
+
+/// A canonical Huffman code.
+struct Huffman {
+  /// The number of codes of each length.
+  counts: [u16; MAX_BITS + 1],
+  /// The symbols ordered by code.
+  symbols: Vec<u16>,
+}
+
+impl Huffman {
+  /// Builds the code from the code length of each symbol, 0 for unused symbols.  Incomplete codes
+  /// are allowed, over-subscribed ones aren't.
+  fn new(lengths: &[u8]) -> Result<Self> {
+    let mut counts = [0u16; MAX_BITS + 1];
+    for &length in lengths {
+      counts[length as usize] += 1;
+    }
+
+    let mut left = 1i32;
+    for &count in &counts[1..] {
+      left = (left << 1) - count as i32;
+      if left < 0 {
+        bail!("Over-subscribed Huffman code");
+      }
+    }
+
+    let mut offsets = [0u16; MAX_BITS + 2];
+    for length in 1..=MAX_BITS {
+      offsets[length + 1] = offsets[length] + counts[length];
+    }
+
+    let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+    for (symbol, &length) in lengths.iter().enumerate() {
+      if length != 0 {
+        symbols[offsets[length as usize] as usize] = symbol as u16;
+        offsets[length as usize] += 1;
+      }
+    }
+
+    Ok(Self { counts, symbols })
+  }
+}
+
+/// Builds the fixed literal/length and distance codes.
+fn fixed() -> (Huffman, Huffman) {
+  let mut lengths = [0u8; 288];
+  lengths[..144].fill(8);
+  lengths[144..256].fill(9);
+  lengths[256..280].fill(7);
+  lengths[280..].fill(8);
+
+  // Neither code is over-subscribed.
+  let lengths = Huffman::new(&lengths).unwrap();
+  let distances = Huffman::new(&[5; 30]).unwrap();
+
+  (lengths, distances)
+}
+
+/// Reads a deflate stream least significant bit first.
+struct Bits<'a> {
+  data: &'a [u8],
+  offset: usize,
+  buf: u32,
+  count: u32,
+}
+
+impl<'a> Bits<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self {
+      data,
+      offset: 0,
+      buf: 0,
+      count: 0,
+    }
+  }
+
+  /// Reads up to 16 bits.
+  fn read(&mut self, count: u32) -> Result<u32> {
+    while self.count < count {
+      let byte = *self
+        .data
+        .get(self.offset)
+        .context("Truncated deflate stream")?;
+      self.buf |= (byte as u32) << self.count;
+      self.offset += 1;
+      self.count += 8;
+    }
+
+    let value = self.buf & ((1 << count) - 1);
+    self.buf >>= count;
+    self.count -= count;
+
+    Ok(value)
+  }
+
+  /// Decodes a symbol, reading the code a bit at a time.
+  fn decode(&mut self, huffman: &Huffman) -> Result<u16> {
+    let mut code = 0i32;
+    let mut first = 0i32;
+    let mut index = 0i32;
+
+    for &count in &huffman.counts[1..] {
+      code |= self.read(1)? as i32;
+      let count = count as i32;
+      if code - first < count {
+        return Ok(huffman.symbols[(index + code - first) as usize]);
+      }
+
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+
+    bail!("Invalid Huffman code")
+  }
+
+  /// Copies a stored block.
+  fn stored(&mut self, out: &mut Vec<u8>) -> Result<()> {
+    // Skip to the byte boundary, at most seven bits are buffered between reads.
+    self.buf = 0;
+    self.count = 0;
+
+    let header = self
+      .data
+      .get(self.offset..self.offset + 4)
+      .context("Truncated stored block")?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+      bail!(
+        "Stored block length {:#x} doesn't match its complement",
+        len
+      );
+    }
+
+    self.offset += 4;
+    let block = self
+      .data
+      .get(self.offset..self.offset + len as usize)
+      .context("Truncated stored block")?;
+    out.extend_from_slice(block);
+    self.offset += len as usize;
+
+    Ok(())
+  }
+
+  /// Reads the literal/length and distance codes of a dynamic block.
+  fn dynamic(&mut self) -> Result<(Huffman, Huffman)> {
+    let nlen = self.read(5)? as usize + 257;
+    let ndist = self.read(5)? as usize + 1;
+    let ncode = self.read(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+      bail!(
+        "Too many codes in dynamic block, {} lengths, {} distances",
+        nlen,
+        ndist
+      );
+    }
+
+    let mut lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..ncode] {
+      lengths[symbol] = self.read(3)? as u8;
+    }
+
+    let code_lengths = Huffman::new(&lengths)?;
+    let mut lengths = [0u8; 286 + 30];
+    let mut index = 0;
+
+    while index < nlen + ndist {
+      let (length, repeat) = match self.decode(&code_lengths)? {
+        symbol @ 0..=15 => (symbol as u8, 1),
+        16 => match index {
+          0 => bail!("Repeated code length without a previous length"),
+          _ => (lengths[index - 1], 3 + self.read(2)? as usize),
+        },
+        17 => (0, 3 + self.read(3)? as usize),
+        _ => (0, 11 + self.read(7)? as usize),
+      };
+
+      let lengths = lengths
+        .get_mut(index..index + repeat)
+        .filter(|_| index + repeat <= nlen + ndist)
+        .context("Too many code lengths in dynamic block")?;
+      lengths.fill(length);
+      index += repeat;
+    }
+
+    if lengths[256] == 0 {
+      bail!("Dynamic block without an end of block code");
+    }
+
+    Ok((
+      Huffman::new(&lengths[..nlen])?,
+      Huffman::new(&lengths[nlen..nlen + ndist])?,
+    ))
+  }
+
+  /// Decodes the literals and back references of a compressed block, failing once the output
+  /// grows past `size`.
+  fn codes(
+    &mut self,
+    out: &mut Vec<u8>,
+    size: usize,
+    lengths: &Huffman,
+    distances: &Huffman,
+  ) -> Result<()> {
+    loop {
+      if out.len() > size {
+        bail!("Deflate stream inflates past {} bytes", size);
+      }
+
+      let symbol = self.decode(lengths)? as usize;
+      let symbol = match symbol {
+        0..=255 => {
+          out.push(symbol as u8);
+          continue;
+        }
+        256 => return Ok(()),
+        _ => symbol - 257,
+      };
+
+      if symbol >= LENGTH_BASE.len() {
+        bail!("Invalid length symbol {}", symbol + 257);
+      }
+
+      let length = LENGTH_BASE[symbol] as usize + self.read(LENGTH_EXTRA[symbol] as u32)? as usize;
+      let symbol = self.decode(distances)? as usize;
+      if symbol >= DIST_BASE.len() {
+        bail!("Invalid distance symbol {}", symbol);
+      }
+
+      let distance = DIST_BASE[symbol] as usize + self.read(DIST_EXTRA[symbol] as u32)? as usize;
+      if distance > out.len() {
+        bail!("Distance {} is past the start of the output", distance);
+      }
+
+      let start = out.len() - distance;
+      for i in start..start + length {
+        out.push(out[i]);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::inflate;
+
+  #[test]
+  fn inflate_blocks() {
+    // A stored block followed by a block with fixed codes.
+    let deflated = [
+      0x00, 0x06, 0x00, 0xf9, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x2b, 0xcf, 0x2f, 0xca,
+      0x49, 0x01, 0x00,
+    ];
+    assert_eq!(inflate(&deflated, 11).unwrap(), b"hello world");
+
+    // A block with dynamic codes.
+    let deflated = [
+      0xed, 0xca, 0xb1, 0x09, 0x00, 0x30, 0x08, 0x04, 0xc0, 0x59, 0x1f, 0x24, 0x60, 0x25, 0x44,
+      0xb4, 0x51, 0x14, 0x9c, 0x3c, 0x53, 0xa4, 0xf3, 0xea, 0x03, 0x6b, 0x1f, 0x87, 0x51, 0x0a,
+      0x4d, 0xb8, 0x5e, 0xf5, 0x18, 0x92, 0x24, 0x83, 0x9f, 0x56, 0xc6, 0x54, 0x66, 0x0d, 0xb6,
+      0x6d, 0xdb, 0xf6, 0xa7, 0x3d,
+    ];
+    let inflated = (0..900)
+      .map(|i: usize| ((i * 7 + i * i) % 39 + 97) as u8)
+      .collect::<Vec<_>>();
+    assert_eq!(inflate(&deflated, 900).unwrap(), inflated);
+  }
+
+  #[test]
+  fn reject_malformed_streams() {
+    let stored = [0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f];
+    assert_eq!(inflate(&stored, 5).unwrap(), b"hello");
+    // Wrong size
+    assert!(inflate(&stored, 4).is_err());
+    assert!(inflate(&stored, 6).is_err());
+    assert!(inflate(&stored, i32::MAX as usize).is_err());
+    // Truncated
+    assert!(inflate(&stored[..8], 5).is_err());
+    // Mismatched length complement
+    assert!(inflate(
+      &[0x01, 0x05, 0x00, 0xfb, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f],
+      5
+    )
+    .is_err());
+    // Reserved block type
+    assert!(inflate(&[0x07], 0).is_err());
+    // A back reference before any output, fixed length 3 (0b0000001) at distance 1 (0b00000).
+    let err = inflate(&[0x03, 0x02, 0x00], 3).unwrap_err();
+    assert!(err.to_string().contains("Distance"), "{}", err);
+  }
+}
@@ -0,0 +1,198 @@
+//! Declarative security permission sets (II.22.11, II.23.1.3).
+//!
+//! The `DeclSecurity` table attaches code access security permissions to types, methods and the
+//! assembly.  The permission set blob comes in two flavours, the XML serialization of a
+//! `PermissionSet` written by the .NET Framework 1.x compilers, and a compact binary form that's
+//! laid out like a list of custom attributes without constructor arguments.
+
+use super::{
+  attributes::{Decoder, EnumResolver, NamedArg},
+  tables::DeclSecurityRow,
+  Md,
+};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::{bail, Context, Result};
+
+/// The first byte of a binary permission set.
+const BINARY_PROLOG: u8 = b'.';
+
+/// A decoded permission set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PermissionSet {
+  /// The XML serialization of a `PermissionSet`.
+  Xml(String),
+  /// The security attributes making up the permission set.
+  Attributes(Vec<SecurityAttribute>),
+}
+
+/// A security attribute in a binary permission set, e.g.
+/// `[SecurityPermission(SecurityAction.Demand, UnmanagedCode = true)]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityAttribute {
+  /// The assembly qualified name of the attribute type.
+  pub type_name: String,
+  /// The fields and properties assigned on the attribute.
+  pub named_args: Vec<NamedArg>,
+}
+
+impl<'a> Md<'a> {
+  /// Decodes the permission set of a `DeclSecurity` row, failing on enums defined outside of this
+  /// module.
+  pub fn permission_set(&self, row: &DeclSecurityRow) -> Result<PermissionSet> {
+    self.permission_set_with(row, &())
+  }
+
+  /// Decodes the permission set of a `DeclSecurity` row, using the given [EnumResolver] for enums
+  /// defined outside of this module.
+  pub fn permission_set_with<R: EnumResolver + ?Sized>(
+    &self,
+    row: &DeclSecurityRow,
+    resolver: &R,
+  ) -> Result<PermissionSet> {
+    let blob = self.blobs().get(row.permission_set)?;
+
+    decode_permission_set(self, resolver, blob)
+      .with_context(|| format!("Permission set at {:#x}", row.permission_set.offset()))
+  }
+}
+
+fn decode_permission_set<R: EnumResolver + ?Sized>(
+  md: &Md,
+  resolver: &R,
+  blob: &[u8],
+) -> Result<PermissionSet> {
+  match blob.first() {
+    Some(&BINARY_PROLOG) => {}
+    Some(_) => {
+      if !blob.len().is_multiple_of(2) {
+        bail!("Malformed XML permission set, odd length {}", blob.len());
+      }
+
+      let units = blob
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+
+      return match char::decode_utf16(units).collect::<Result<String, _>>() {
+        Ok(xml) => Ok(PermissionSet::Xml(xml)),
+        Err(err) => bail!("Malformed XML permission set, {}", err),
+      };
+    }
+    None => bail!("Empty permission set"),
+  }
+
+  let mut decoder = Decoder::new(md, resolver, blob);
+  decoder.read::<u8>()?;
+
+  let count = decoder.compressed_u32()?;
+  let mut attributes = Vec::new();
+
+  for _ in 0..count {
+    let type_name = match decoder.ser_string()? {
+      Some(type_name) => type_name,
+      None => bail!("Malformed permission set, null attribute type"),
+    };
+
+    let len = decoder.compressed_u32()? as usize;
+    let start = decoder.offset();
+    let named_args = (0..decoder.compressed_u32()?)
+      .map(|_| decoder.named_arg())
+      .collect::<Result<Vec<_>>>()
+      .with_context(|| format!("Security attribute `{}`", type_name))?;
+
+    if decoder.offset() - start != len {
+      bail!(
+        "Malformed permission set, `{}` arguments are {} bytes, expected {}",
+        type_name,
+        decoder.offset() - start,
+        len
+      );
+    }
+
+    attributes.push(SecurityAttribute {
+      type_name,
+      named_args,
+    });
+  }
+
+  Ok(PermissionSet::Attributes(attributes))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{decode_permission_set, PermissionSet};
+  use crate::ecma335::{
+    attributes::{AttributeArg, AttributeType},
+    signatures::TypeSig,
+    Md,
+  };
+  use alloc::{vec, vec::Vec};
+
+  #[test]
+  fn xml_permission_set() {
+    let xml = "<PermissionSet class=\"System.Security.PermissionSet\"/>";
+    let blob = xml
+      .encode_utf16()
+      .flat_map(|unit| unit.to_le_bytes())
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      decode_permission_set(&Md::empty(), &(), &blob).unwrap(),
+      PermissionSet::Xml(xml.into())
+    );
+  }
+
+  #[test]
+  fn binary_permission_set() {
+    let type_name = b"System.Security.Permissions.SecurityPermissionAttribute, mscorlib";
+    let flags = b"System.Security.Permissions.SecurityPermissionFlag, mscorlib";
+
+    // [SecurityPermission(SecurityAction.Demand, UnmanagedCode = true, Flags = 2)]
+    let mut args = vec![0x02, 0x54, 0x02, 13];
+    args.extend_from_slice(b"UnmanagedCode");
+    args.extend_from_slice(&[0x01, 0x54, 0x55, flags.len() as u8]);
+    args.extend_from_slice(flags);
+    args.extend_from_slice(&[5]);
+    args.extend_from_slice(b"Flags");
+    args.extend_from_slice(&2i32.to_le_bytes());
+
+    let mut blob = vec![b'.', 0x01, type_name.len() as u8];
+    blob.extend_from_slice(type_name);
+    blob.push(args.len() as u8);
+    blob.extend_from_slice(&args);
+
+    let resolver = |name: &str| match name {
+      "System.Security.Permissions.SecurityPermissionFlag" => Some(TypeSig::I4),
+      _ => None,
+    };
+
+    let PermissionSet::Attributes(attributes) =
+      decode_permission_set(&Md::empty(), &resolver, &blob).unwrap()
+    else {
+      panic!("Expected a binary permission set");
+    };
+
+    assert_eq!(attributes.len(), 1);
+    assert_eq!(attributes[0].type_name.as_bytes(), type_name);
+
+    let [unmanaged_code, flags] = &attributes[0].named_args[..] else {
+      panic!("Expected 2 named arguments");
+    };
+
+    assert!(unmanaged_code.property);
+    assert_eq!(unmanaged_code.ty, AttributeType::Boolean);
+    assert_eq!(unmanaged_code.value, AttributeArg::Boolean(true));
+    assert!(matches!(
+      &flags.value,
+      AttributeArg::Enum(_, value) if **value == AttributeArg::I4(2)
+    ));
+
+    // The enum can't be resolved without a resolver.
+    assert!(decode_permission_set(&Md::empty(), &(), &blob).is_err());
+
+    // The argument length doesn't match.
+    let len = 3 + type_name.len();
+    blob[len] += 1;
+    blob.push(0);
+    assert!(decode_permission_set(&Md::empty(), &resolver, &blob).is_err());
+  }
+}
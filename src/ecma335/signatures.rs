@@ -0,0 +1,91 @@
+//! ECMA-335 signature blobs (II.23.2).
+//!
+//! Signatures describe the types of fields, methods, properties and locals and are stored in the
+//! `#Blob` stream.  They're a compact, recursive encoding built out of [ElementType] tags,
+//! compressed integers and compressed `TypeDefOrRefOrSpecEncoded` tokens.
+//!
+//! Every decoded signature can be encoded back into a blob with [SignatureBuilder].
+//!
+//! [ElementType]: crate::ecma335::tables::flags::ElementType
+
+pub mod builder;
+pub mod local;
+pub mod method;
+pub mod types;
+#[doc(inline)]
+pub use builder::*;
+#[doc(inline)]
+pub use local::*;
+#[doc(inline)]
+pub use method::*;
+#[doc(inline)]
+pub use types::*;
+
+use super::{blobs::BlobIndex, Md};
+use anyhow::{Context, Result};
+use scroll::Pread;
+
+impl<'a> Md<'a> {
+  /// Decodes the `MethodDefSig` or `MethodRefSig` at the given [BlobIndex].
+  pub fn method_sig(&self, index: BlobIndex) -> Result<MethodSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Method signature at {:#x}", index.offset()))
+  }
+
+  /// Decodes the `FieldSig` at the given [BlobIndex].
+  pub fn field_sig(&self, index: BlobIndex) -> Result<FieldSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Field signature at {:#x}", index.offset()))
+  }
+
+  /// Decodes the `TypeSpec` signature at the given [BlobIndex].
+  pub fn type_spec_sig(&self, index: BlobIndex) -> Result<TypeSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Type specification at {:#x}", index.offset()))
+  }
+
+  /// Decodes the `MethodSpec` instantiation at the given [BlobIndex].
+  pub fn method_spec_sig(&self, index: BlobIndex) -> Result<MethodSpecSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Method instantiation at {:#x}", index.offset()))
+  }
+
+  /// Decodes the `LocalVarSig` at the given [BlobIndex].
+  pub fn local_var_sig(&self, index: BlobIndex) -> Result<LocalVarSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Local variable signature at {:#x}", index.offset()))
+  }
+
+  /// Decodes the signature of a `StandAloneSig` row at the given [BlobIndex].
+  pub fn stand_alone_sig(&self, index: BlobIndex) -> Result<StandAloneSig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Standalone signature at {:#x}", index.offset()))
+  }
+
+  /// Decodes the `PropertySig` at the given [BlobIndex].
+  pub fn property_sig(&self, index: BlobIndex) -> Result<PropertySig> {
+    let blob = self.blobs().get(index)?;
+
+    blob
+      .pread_with(0, self.tables().header())
+      .with_context(|| format!("Property signature at {:#x}", index.offset()))
+  }
+}
@@ -0,0 +1,425 @@
+//! Signature encoding, the inverse of the decoders in this module.
+
+use super::{
+  local::{LocalVarSig, StandAloneSig},
+  method::{MethodSig, PropertySig, SignatureAttributes, SignatureHeader, SignatureKind},
+  types::{ArrayShape, FieldSig, MethodSpecSig, TypeSig},
+};
+use crate::ecma335::{
+  compressed::{CompressedI32, CompressedU32},
+  tables::{flags::ElementType, TypeDefOrRef},
+};
+use alloc::vec::Vec;
+use anyhow::{bail, Result};
+use scroll::Pwrite;
+
+/// Builds a signature blob.
+///
+/// The `write_*` methods append a single element of the signature grammar and can be chained to
+/// build a blob by hand, the signature types also have an `encode` method producing a complete
+/// blob that decodes back to the same value.
+///
+/// ```
+/// use recil::ecma335::signatures::{
+///   SignatureAttributes, SignatureBuilder, SignatureHeader, SignatureKind, TypeSig,
+/// };
+///
+/// // instance string (int32)
+/// let mut builder = SignatureBuilder::new();
+/// builder
+///   .write_header(SignatureHeader {
+///     kind: SignatureKind::Default,
+///     attributes: SignatureAttributes::HAS_THIS,
+///   })
+///   .write_compressed_u32(1)?
+///   .write_type(&TypeSig::String)?
+///   .write_type(&TypeSig::I4)?;
+///
+/// assert_eq!(builder.as_bytes(), [0x20, 0x01, 0x0e, 0x08]);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SignatureBuilder {
+  buf: Vec<u8>,
+}
+
+impl SignatureBuilder {
+  /// Creates an empty builder.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Gets the bytes written so far.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf
+  }
+
+  /// Consumes the builder, returning the blob.
+  pub fn finish(self) -> Vec<u8> {
+    self.buf
+  }
+
+  /// Writes the first byte of a signature.
+  pub fn write_header(&mut self, header: SignatureHeader) -> &mut Self {
+    self.buf.push(header.to_byte());
+    self
+  }
+
+  /// Writes an element type tag.
+  pub fn write_element(&mut self, element: ElementType) -> &mut Self {
+    self.buf.push(element.bits());
+    self
+  }
+
+  /// Writes an unsigned compressed integer using the smallest width that fits.
+  pub fn write_compressed_u32(&mut self, value: u32) -> Result<&mut Self> {
+    let mut bytes = [0u8; 4];
+    let size = bytes.pwrite(CompressedU32(value), 0)?;
+
+    self.buf.extend_from_slice(&bytes[..size]);
+    Ok(self)
+  }
+
+  /// Writes a signed compressed integer using the smallest width that fits.
+  pub fn write_compressed_i32(&mut self, value: i32) -> Result<&mut Self> {
+    let mut bytes = [0u8; 4];
+    let size = bytes.pwrite(CompressedI32(value), 0)?;
+
+    self.buf.extend_from_slice(&bytes[..size]);
+    Ok(self)
+  }
+
+  /// Writes a compressed `TypeDefOrRefOrSpecEncoded` token (II.23.2.8).
+  pub fn write_type_def_or_ref(&mut self, ty: TypeDefOrRef) -> Result<&mut Self> {
+    self.write_compressed_u32(ty.encode())
+  }
+
+  /// Writes a type, including any custom modifiers, `BYREF` and `PINNED` wrappers.
+  pub fn write_type(&mut self, ty: &TypeSig) -> Result<&mut Self> {
+    let element = match ty {
+      TypeSig::Void => ElementType::ELEMENT_TYPE_VOID,
+      TypeSig::Boolean => ElementType::ELEMENT_TYPE_BOOLEAN,
+      TypeSig::Char => ElementType::ELEMENT_TYPE_CHAR,
+      TypeSig::I1 => ElementType::ELEMENT_TYPE_I1,
+      TypeSig::U1 => ElementType::ELEMENT_TYPE_U1,
+      TypeSig::I2 => ElementType::ELEMENT_TYPE_I2,
+      TypeSig::U2 => ElementType::ELEMENT_TYPE_U2,
+      TypeSig::I4 => ElementType::ELEMENT_TYPE_I4,
+      TypeSig::U4 => ElementType::ELEMENT_TYPE_U4,
+      TypeSig::I8 => ElementType::ELEMENT_TYPE_I8,
+      TypeSig::U8 => ElementType::ELEMENT_TYPE_U8,
+      TypeSig::R4 => ElementType::ELEMENT_TYPE_R4,
+      TypeSig::R8 => ElementType::ELEMENT_TYPE_R8,
+      TypeSig::String => ElementType::ELEMENT_TYPE_STRING,
+      TypeSig::I => ElementType::ELEMENT_TYPE_I,
+      TypeSig::U => ElementType::ELEMENT_TYPE_U,
+      TypeSig::Object => ElementType::ELEMENT_TYPE_OBJECT,
+      TypeSig::TypedByRef => ElementType::ELEMENT_TYPE_TYPEDBYREF,
+      TypeSig::Ptr(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_PTR)
+          .write_type(ty)
+      }
+      TypeSig::ByRef(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_BYREF)
+          .write_type(ty)
+      }
+      TypeSig::ValueType(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_VALUETYPE)
+          .write_type_def_or_ref(*ty)
+      }
+      TypeSig::Class(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_CLASS)
+          .write_type_def_or_ref(*ty)
+      }
+      TypeSig::Var(number) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_VAR)
+          .write_compressed_u32(*number)
+      }
+      TypeSig::MVar(number) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_MVAR)
+          .write_compressed_u32(*number)
+      }
+      TypeSig::GenericInst(inst) => {
+        let kind = match inst.value_type {
+          true => ElementType::ELEMENT_TYPE_VALUETYPE,
+          false => ElementType::ELEMENT_TYPE_CLASS,
+        };
+
+        self
+          .write_element(ElementType::ELEMENT_TYPE_GENERICINST)
+          .write_element(kind)
+          .write_type_def_or_ref(inst.ty)?
+          .write_compressed_u32(inst.args.len() as u32)?;
+
+        for arg in &inst.args {
+          self.write_type(arg)?;
+        }
+
+        return Ok(self);
+      }
+      TypeSig::SzArray(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_SZARRAY)
+          .write_type(ty)
+      }
+      TypeSig::Array(array) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_ARRAY)
+          .write_type(&array.ty)?
+          .write_array_shape(&array.shape)
+      }
+      TypeSig::FnPtr(method) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_FNPTR)
+          .write_method(method)
+      }
+      TypeSig::Modified(modifier, ty) => {
+        let element = match modifier.required {
+          true => ElementType::ELEMENT_TYPE_CMOD_REQD,
+          false => ElementType::ELEMENT_TYPE_CMOD_OPT,
+        };
+
+        return self
+          .write_element(element)
+          .write_type_def_or_ref(modifier.ty)?
+          .write_type(ty);
+      }
+      TypeSig::Pinned(ty) => {
+        return self
+          .write_element(ElementType::ELEMENT_TYPE_PINNED)
+          .write_type(ty)
+      }
+    };
+
+    Ok(self.write_element(element))
+  }
+
+  /// Writes the shape of a general array (II.23.2.13).
+  pub fn write_array_shape(&mut self, shape: &ArrayShape) -> Result<&mut Self> {
+    if shape.sizes.len() > shape.rank as usize || shape.lower_bounds.len() > shape.rank as usize {
+      bail!(
+        "Malformed array shape, more bounds than rank {}",
+        shape.rank
+      );
+    }
+
+    self
+      .write_compressed_u32(shape.rank)?
+      .write_compressed_u32(shape.sizes.len() as u32)?;
+
+    for size in &shape.sizes {
+      self.write_compressed_u32(*size)?;
+    }
+
+    self.write_compressed_u32(shape.lower_bounds.len() as u32)?;
+
+    for bound in &shape.lower_bounds {
+      self.write_compressed_i32(*bound)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Writes a `MethodDefSig`, `MethodRefSig` or standalone method signature.
+  pub fn write_method(&mut self, sig: &MethodSig) -> Result<&mut Self> {
+    if !sig.header.kind.is_method() {
+      bail!("Expected a method signature, found {:?}", sig.header.kind);
+    }
+
+    let generic = sig.header.attributes.contains(SignatureAttributes::GENERIC);
+    if !generic && sig.generic_param_count != 0 {
+      bail!(
+        "Method signature has {} generic parameters without the GENERIC flag",
+        sig.generic_param_count
+      );
+    }
+
+    if matches!(sig.sentinel, Some(sentinel) if sentinel >= sig.params.len()) {
+      bail!("Malformed method signature, sentinel after the last parameter");
+    }
+
+    self.write_header(sig.header);
+
+    if generic {
+      self.write_compressed_u32(sig.generic_param_count)?;
+    }
+
+    self
+      .write_compressed_u32(sig.params.len() as u32)?
+      .write_type(&sig.ret)?;
+
+    for (i, param) in sig.params.iter().enumerate() {
+      if sig.sentinel == Some(i) {
+        self.write_element(ElementType::ELEMENT_TYPE_SENTINEL);
+      }
+
+      self.write_type(param)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Writes a `FieldSig`.
+  pub fn write_field(&mut self, sig: &FieldSig) -> Result<&mut Self> {
+    if sig.header.kind != SignatureKind::Field {
+      bail!("Expected a field signature, found {:?}", sig.header.kind);
+    }
+
+    self.write_header(sig.header).write_type(&sig.ty)
+  }
+
+  /// Writes a `PropertySig`.
+  pub fn write_property(&mut self, sig: &PropertySig) -> Result<&mut Self> {
+    if sig.header.kind != SignatureKind::Property {
+      bail!("Expected a property signature, found {:?}", sig.header.kind);
+    }
+
+    self
+      .write_header(sig.header)
+      .write_compressed_u32(sig.params.len() as u32)?
+      .write_type(&sig.ty)?;
+
+    for param in &sig.params {
+      self.write_type(param)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Writes a `LocalVarSig`.
+  pub fn write_local_var(&mut self, sig: &LocalVarSig) -> Result<&mut Self> {
+    if sig.header.kind != SignatureKind::LocalSig {
+      bail!(
+        "Expected a local variable signature, found {:?}",
+        sig.header.kind
+      );
+    }
+
+    self
+      .write_header(sig.header)
+      .write_compressed_u32(sig.locals.len() as u32)?;
+
+    for local in &sig.locals {
+      self.write_type(local)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Writes the signature of a `StandAloneSig` row.
+  pub fn write_stand_alone(&mut self, sig: &StandAloneSig) -> Result<&mut Self> {
+    match sig {
+      StandAloneSig::Locals(sig) => self.write_local_var(sig),
+      StandAloneSig::Method(sig) => self.write_method(sig),
+    }
+  }
+
+  /// Writes a `MethodSpec` instantiation.
+  pub fn write_method_spec(&mut self, sig: &MethodSpecSig) -> Result<&mut Self> {
+    if sig.header.kind != SignatureKind::GenericInst {
+      bail!(
+        "Expected a method instantiation, found {:?}",
+        sig.header.kind
+      );
+    }
+
+    self
+      .write_header(sig.header)
+      .write_compressed_u32(sig.args.len() as u32)?;
+
+    for arg in &sig.args {
+      self.write_type(arg)?;
+    }
+
+    Ok(self)
+  }
+}
+
+macro_rules! encode {
+  ($($ty:ident => $write:ident, $what:literal;)*) => {
+    $(
+      impl $ty {
+        #[doc = concat!("Encodes the ", $what, " into a blob.")]
+        pub fn encode(&self) -> Result<Vec<u8>> {
+          let mut builder = SignatureBuilder::new();
+          builder.$write(self)?;
+
+          Ok(builder.finish())
+        }
+      }
+    )*
+  };
+}
+
+encode! {
+  TypeSig => write_type, "type, e.g. the signature of a `TypeSpec` row,";
+  MethodSig => write_method, "method signature";
+  FieldSig => write_field, "field signature";
+  PropertySig => write_property, "property signature";
+  LocalVarSig => write_local_var, "local variable signature";
+  StandAloneSig => write_stand_alone, "standalone signature";
+  MethodSpecSig => write_method_spec, "method instantiation";
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ecma335::{
+    signatures::{MethodSig, SignatureAttributes, SignatureHeader, SignatureKind, TypeSig},
+    tables::{Row, TablesHeader, TypeDefOrRef, TypeRefRow, TypeRefRowId},
+  };
+  use alloc::{boxed::Box, vec};
+  use scroll::Pread;
+
+  #[test]
+  fn encode_vararg_sig() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 0x100)]);
+
+    // vararg void (class TypeRef 0x100, ..., int32)
+    let sig = MethodSig {
+      header: SignatureHeader {
+        kind: SignatureKind::VarArg,
+        attributes: SignatureAttributes::empty(),
+      },
+      generic_param_count: 0,
+      ret: TypeSig::Void,
+      params: vec![
+        TypeSig::Class(TypeDefOrRef::TypeRef(
+          TypeRefRowId::new(0x100, &header).unwrap(),
+        )),
+        TypeSig::I4,
+      ],
+      sentinel: Some(1),
+    };
+
+    let blob = sig.encode().unwrap();
+
+    assert_eq!(blob, [0x05, 0x02, 0x01, 0x12, 0x84, 0x01, 0x41, 0x08]);
+    assert_eq!(blob.pread_with::<MethodSig>(0, &header).unwrap(), sig);
+  }
+
+  #[test]
+  fn encode_malformed_sig() {
+    let sig = MethodSig {
+      header: SignatureHeader {
+        kind: SignatureKind::Default,
+        attributes: SignatureAttributes::empty(),
+      },
+      generic_param_count: 1,
+      ret: TypeSig::Void,
+      params: vec![],
+      sentinel: None,
+    };
+
+    assert!(sig.encode().is_err());
+    assert!(TypeSig::Var(0x2000_0000).encode().is_err());
+    assert!(TypeSig::SzArray(Box::new(TypeSig::Var(0x2000_0000)))
+      .encode()
+      .is_err());
+  }
+}
@@ -0,0 +1,121 @@
+//! Local variable and standalone signatures (II.23.2.6).
+
+use super::{
+  method::{MethodSig, SignatureHeader, SignatureKind},
+  types::TypeSig,
+};
+use crate::ecma335::{compressed::CompressedU32, tables::TablesHeader};
+use alloc::vec::Vec;
+use anyhow::{bail, Error, Result};
+use scroll::{ctx::TryFromCtx, Pread};
+
+/// A `LocalVarSig` describing the local variables of a method body.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalVarSig {
+  /// The signature kind and flags.
+  pub header: SignatureHeader,
+  /// The local variable types, indexed by local number.
+  ///
+  /// Pinned locals are wrapped in [TypeSig::Pinned] and `ref` locals in [TypeSig::ByRef].
+  pub locals: Vec<TypeSig>,
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for LocalVarSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let sig_header = from.gread::<SignatureHeader>(offset)?;
+    if sig_header.kind != SignatureKind::LocalSig {
+      bail!(
+        "Expected a local variable signature, found {:?}",
+        sig_header.kind
+      );
+    }
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let locals = (0..count)
+      .map(|_| from.gread_with(offset, header))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok((
+      Self {
+        header: sig_header,
+        locals,
+      },
+      *offset,
+    ))
+  }
+}
+
+/// The signature referenced by a `StandAloneSig` row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StandAloneSig {
+  /// The local variables of a method body, referenced by the `LocalVarSigTok` of a fat header.
+  Locals(LocalVarSig),
+  /// The call site signature of a `calli` instruction.
+  Method(MethodSig),
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for StandAloneSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    match from.pread::<SignatureHeader>(0)?.kind {
+      SignatureKind::LocalSig => {
+        let (sig, size) = LocalVarSig::try_from_ctx(from, header)?;
+        Ok((Self::Locals(sig), size))
+      }
+      _ => {
+        let (sig, size) = MethodSig::try_from_ctx(from, header)?;
+        Ok((Self::Method(sig), size))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{LocalVarSig, StandAloneSig};
+  use crate::ecma335::{signatures::TypeSig, tables::TablesHeader};
+  use alloc::{boxed::Box, vec};
+  use scroll::Pread;
+
+  #[test]
+  fn local_var_sig() {
+    let header = TablesHeader::default();
+
+    // .locals (int32& pinned, typedref, string)
+    let sig = [0x07u8, 0x03, 0x45, 0x10, 0x08, 0x16, 0x0e];
+    let sig = sig.pread_with::<StandAloneSig>(0, &header).unwrap();
+
+    let StandAloneSig::Locals(LocalVarSig { locals, .. }) = sig else {
+      panic!("Expected locals, found {:?}", sig);
+    };
+
+    assert_eq!(
+      locals,
+      vec![
+        TypeSig::Pinned(Box::new(TypeSig::ByRef(Box::new(TypeSig::I4)))),
+        TypeSig::TypedByRef,
+        TypeSig::String,
+      ]
+    );
+  }
+
+  #[test]
+  fn calli_sig() {
+    let header = TablesHeader::default();
+
+    // unmanaged stdcall int32 *(native int)
+    let sig = [0x02u8, 0x01, 0x08, 0x18];
+    let sig = sig.pread_with::<StandAloneSig>(0, &header).unwrap();
+
+    let StandAloneSig::Method(sig) = sig else {
+      panic!("Expected a method, found {:?}", sig);
+    };
+
+    assert_eq!(sig.ret, TypeSig::I4);
+    assert_eq!(sig.params, [TypeSig::I]);
+  }
+}
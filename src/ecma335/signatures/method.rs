@@ -0,0 +1,282 @@
+//! Method and property signatures (II.23.2.1 - II.23.2.3, II.23.2.5).
+
+use super::types::TypeSig;
+use crate::ecma335::{
+  compressed::CompressedU32,
+  tables::{flags::ElementType, TablesHeader},
+};
+use alloc::vec::Vec;
+use anyhow::{bail, Error, Result};
+use scroll::{ctx::TryFromCtx, Pread};
+
+/// The mask of the calling convention kind in the first byte of a signature.
+const KIND_MASK: u8 = 0x0f;
+
+bitflags::bitflags! {
+  /// The flags stored in the upper bits of the first byte of a signature.
+  #[derive(Default)]
+  pub struct SignatureAttributes : u8 {
+    /// The method has generic parameters, their count follows the first byte.
+    const GENERIC = 0x10;
+    /// The method is an instance method, `this` is passed implicitly.
+    const HAS_THIS = 0x20;
+    /// The `this` parameter is explicitly listed as the first parameter.
+    const EXPLICIT_THIS = 0x40;
+  }
+}
+
+/// The kind of a signature, stored in the low bits of the first byte of a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureKind {
+  /// The default managed calling convention.
+  Default = 0x0,
+  /// The unmanaged C calling convention.
+  C = 0x1,
+  /// The unmanaged stdcall calling convention.
+  StdCall = 0x2,
+  /// The unmanaged thiscall calling convention.
+  ThisCall = 0x3,
+  /// The unmanaged fastcall calling convention.
+  FastCall = 0x4,
+  /// The managed variable argument calling convention.
+  VarArg = 0x5,
+  /// A `FieldSig`.
+  Field = 0x6,
+  /// A `LocalVarSig`.
+  LocalSig = 0x7,
+  /// A `PropertySig`.
+  Property = 0x8,
+  /// An unmanaged calling convention specified by custom modifiers on the return type.
+  Unmanaged = 0x9,
+  /// A `MethodSpec` instantiation.
+  GenericInst = 0xa,
+}
+
+impl SignatureKind {
+  /// Determines if the kind belongs to a method signature.
+  pub fn is_method(&self) -> bool {
+    !matches!(
+      self,
+      Self::Field | Self::LocalSig | Self::Property | Self::GenericInst
+    )
+  }
+}
+
+impl TryFrom<u8> for SignatureKind {
+  type Error = Error;
+
+  fn try_from(value: u8) -> Result<Self> {
+    Ok(match value & KIND_MASK {
+      0x0 => Self::Default,
+      0x1 => Self::C,
+      0x2 => Self::StdCall,
+      0x3 => Self::ThisCall,
+      0x4 => Self::FastCall,
+      0x5 => Self::VarArg,
+      0x6 => Self::Field,
+      0x7 => Self::LocalSig,
+      0x8 => Self::Property,
+      0x9 => Self::Unmanaged,
+      0xa => Self::GenericInst,
+      kind => bail!("Unknown signature kind {:#x}", kind),
+    })
+  }
+}
+
+/// The first byte of a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SignatureHeader {
+  /// The kind of signature, for methods this is the calling convention.
+  pub kind: SignatureKind,
+  /// The flags of the signature.
+  pub attributes: SignatureAttributes,
+}
+
+impl SignatureHeader {
+  /// Gets the encoded byte.
+  pub fn to_byte(&self) -> u8 {
+    self.kind as u8 | self.attributes.bits()
+  }
+}
+
+impl<'a> TryFromCtx<'a> for SignatureHeader {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], _: ()) -> Result<(Self, usize)> {
+    let byte = from.pread::<u8>(0)?;
+
+    Ok((
+      Self {
+        kind: SignatureKind::try_from(byte)?,
+        attributes: SignatureAttributes::from_bits_truncate(byte),
+      },
+      1,
+    ))
+  }
+}
+
+/// A `MethodDefSig`, `MethodRefSig` or standalone method signature.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSig {
+  /// The calling convention and flags.
+  pub header: SignatureHeader,
+  /// The number of generic parameters, 0 if the method isn't generic.
+  pub generic_param_count: u32,
+  /// The return type.
+  pub ret: TypeSig,
+  /// The parameter types, including the extra arguments of a vararg call site.
+  pub params: Vec<TypeSig>,
+  /// The position in [MethodSig::params] of the first extra argument of a vararg call site, only
+  /// present in `MethodRefSig`.
+  pub sentinel: Option<usize>,
+}
+
+impl MethodSig {
+  /// Determines if the method is an instance method.
+  pub fn has_this(&self) -> bool {
+    self
+      .header
+      .attributes
+      .contains(SignatureAttributes::HAS_THIS)
+  }
+
+  /// Determines if `this` is listed as the first parameter.
+  pub fn explicit_this(&self) -> bool {
+    self
+      .header
+      .attributes
+      .contains(SignatureAttributes::EXPLICIT_THIS)
+  }
+
+  /// Gets the parameters declared by the method, excluding the extra arguments of a vararg call.
+  pub fn fixed_params(&self) -> &[TypeSig] {
+    &self.params[..self.sentinel.unwrap_or(self.params.len())]
+  }
+
+  /// Gets the extra arguments passed to a vararg method.
+  pub fn vararg_params(&self) -> &[TypeSig] {
+    &self.params[self.sentinel.unwrap_or(self.params.len())..]
+  }
+}
+
+impl MethodSig {
+  /// Reads a method signature whose types are nested `depth` types deep.
+  pub(crate) fn read(
+    from: &[u8],
+    offset: &mut usize,
+    header: &TablesHeader,
+    depth: usize,
+  ) -> Result<Self> {
+    let sig_header = from.gread::<SignatureHeader>(offset)?;
+    if !sig_header.kind.is_method() {
+      bail!("Expected a method signature, found {:?}", sig_header.kind);
+    }
+
+    let generic_param_count = match sig_header.attributes.contains(SignatureAttributes::GENERIC) {
+      true => from.gread::<CompressedU32>(offset)?.0,
+      false => 0,
+    };
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let ret = TypeSig::read(from, offset, header, depth)?;
+    // The count comes from the blob, every parameter taking at least a byte.
+    let mut params = Vec::with_capacity((count as usize).min(from.len() - *offset));
+    let mut sentinel = None;
+
+    while params.len() < count as usize {
+      if from.pread::<u8>(*offset)? == ElementType::ELEMENT_TYPE_SENTINEL.bits() {
+        if sentinel.is_some() {
+          bail!("Malformed method signature, multiple sentinels");
+        }
+
+        *offset += 1;
+        sentinel = Some(params.len());
+      }
+
+      params.push(TypeSig::read(from, offset, header, depth)?);
+    }
+
+    Ok(Self {
+      header: sig_header,
+      generic_param_count,
+      ret,
+      params,
+      sentinel,
+    })
+  }
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for MethodSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let sig = Self::read(from, offset, header, 0)?;
+
+    Ok((sig, *offset))
+  }
+}
+
+/// A `PropertySig`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PropertySig {
+  /// The signature kind and flags.
+  pub header: SignatureHeader,
+  /// The property type.
+  pub ty: TypeSig,
+  /// The index parameter types of an indexer.
+  pub params: Vec<TypeSig>,
+}
+
+impl PropertySig {
+  /// Determines if the property is an instance property.
+  pub fn has_this(&self) -> bool {
+    self
+      .header
+      .attributes
+      .contains(SignatureAttributes::HAS_THIS)
+  }
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for PropertySig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let sig_header = from.gread::<SignatureHeader>(offset)?;
+    if sig_header.kind != SignatureKind::Property {
+      bail!("Expected a property signature, found {:?}", sig_header.kind);
+    }
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let ty = from.gread_with(offset, header)?;
+    let params = (0..count)
+      .map(|_| from.gread_with(offset, header))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok((
+      Self {
+        header: sig_header,
+        ty,
+        params,
+      },
+      *offset,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::MethodSig;
+  use crate::ecma335::tables::TablesHeader;
+  use scroll::Pread;
+
+  #[test]
+  fn malformed_method_sig() {
+    let header = TablesHeader::default();
+
+    // A parameter count of 0x1fffffff with no parameters.
+    let sig = [0x00u8, 0xdf, 0xff, 0xff, 0xff, 0x01];
+    assert!(sig.pread_with::<MethodSig>(0, &header).is_err());
+  }
+}
@@ -0,0 +1,462 @@
+//! Type signatures (II.23.2.12) and the signatures built directly on them: `FieldSig`,
+//! `TypeSpec` and `MethodSpec` instantiations.
+
+use super::method::{MethodSig, SignatureHeader, SignatureKind};
+use crate::ecma335::{
+  compressed::{CompressedI32, CompressedU32},
+  tables::{flags::ElementType, TablesHeader, TypeDefOrRef},
+};
+use alloc::{boxed::Box, vec::Vec};
+use anyhow::{anyhow, bail, Error, Result};
+use scroll::{ctx::TryFromCtx, Pread};
+
+/// A decoded type signature.
+///
+/// Custom modifiers, `BYREF` and `PINNED` are modelled as nodes wrapping the type they apply to, so
+/// the tree mirrors the order of the encoded elements exactly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeSig {
+  /// `void`, only valid as a return type or pointer target.
+  Void,
+  /// `bool`
+  Boolean,
+  /// `char`
+  Char,
+  /// `int8`
+  I1,
+  /// `unsigned int8`
+  U1,
+  /// `int16`
+  I2,
+  /// `unsigned int16`
+  U2,
+  /// `int32`
+  I4,
+  /// `unsigned int32`
+  U4,
+  /// `int64`
+  I8,
+  /// `unsigned int64`
+  U8,
+  /// `float32`
+  R4,
+  /// `float64`
+  R8,
+  /// `string`
+  String,
+  /// `native int`
+  I,
+  /// `native unsigned int`
+  U,
+  /// `object`
+  Object,
+  /// `typedref`, only valid as a parameter, return or local type.
+  TypedByRef,
+  /// An unmanaged pointer to the given type.
+  Ptr(Box<TypeSig>),
+  /// A managed pointer to the given type, only valid as a parameter, return or local type.
+  ByRef(Box<TypeSig>),
+  /// A value type.
+  ValueType(TypeDefOrRef),
+  /// A reference type.
+  Class(TypeDefOrRef),
+  /// A generic parameter of the enclosing type, by number.
+  Var(u32),
+  /// A generic parameter of the enclosing method, by number.
+  MVar(u32),
+  /// An instantiation of a generic type.
+  GenericInst(Box<GenericInstSig>),
+  /// A single dimensional, zero based array of the given type.
+  SzArray(Box<TypeSig>),
+  /// A general array with an explicit shape.
+  Array(Box<ArraySig>),
+  /// A pointer to a function with the given signature.
+  FnPtr(Box<MethodSig>),
+  /// A type with a custom modifier applied.
+  Modified(CustomMod, Box<TypeSig>),
+  /// A local variable whose referenced object is pinned, only valid in a `LocalVarSig`.
+  Pinned(Box<TypeSig>),
+}
+
+impl TypeSig {
+  /// Gets the type with any custom modifiers removed.
+  pub fn strip_modifiers(&self) -> &TypeSig {
+    match self {
+      TypeSig::Modified(_, ty) => ty.strip_modifiers(),
+      ty => ty,
+    }
+  }
+
+  /// Gets an iterator over the custom modifiers applied to the outermost type.
+  pub fn custom_mods(&self) -> impl Iterator<Item = &CustomMod> {
+    let mut ty = self;
+
+    core::iter::from_fn(move || match ty {
+      TypeSig::Modified(modifier, inner) => {
+        ty = inner;
+        Some(modifier)
+      }
+      _ => None,
+    })
+  }
+
+  /// Determines if the type, ignoring custom modifiers, is a managed pointer.
+  pub fn is_by_ref(&self) -> bool {
+    matches!(self.strip_modifiers(), TypeSig::ByRef(_))
+  }
+}
+
+/// A custom modifier (II.23.2.7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomMod {
+  /// `true` for `modreq`, `false` for `modopt`.
+  pub required: bool,
+  /// The modifier type.
+  pub ty: TypeDefOrRef,
+}
+
+/// An instantiation of a generic type, e.g. `List<int>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenericInstSig {
+  /// `true` if the generic type is a value type, otherwise a class.
+  pub value_type: bool,
+  /// The generic type definition.
+  pub ty: TypeDefOrRef,
+  /// The type arguments.
+  pub args: Vec<TypeSig>,
+}
+
+/// A general array type, e.g. `int32[0...5, 0...]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArraySig {
+  /// The element type.
+  pub ty: TypeSig,
+  /// The dimensions of the array.
+  pub shape: ArrayShape,
+}
+
+/// The shape of a general array (II.23.2.13).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ArrayShape {
+  /// The number of dimensions.
+  pub rank: u32,
+  /// The sizes of the leading dimensions, may be shorter than the rank.
+  pub sizes: Vec<u32>,
+  /// The lower bounds of the leading dimensions, may be shorter than the rank.
+  pub lower_bounds: Vec<i32>,
+}
+
+impl<'a> TryFromCtx<'a> for ArrayShape {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], _: ()) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let rank = from.gread::<CompressedU32>(offset)?.0;
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let sizes = (0..count)
+      .map(|_| Ok(from.gread::<CompressedU32>(offset)?.0))
+      .collect::<Result<Vec<_>>>()?;
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let lower_bounds = (0..count)
+      .map(|_| Ok(from.gread::<CompressedI32>(offset)?.0))
+      .collect::<Result<Vec<_>>>()?;
+
+    if sizes.len() > rank as usize || lower_bounds.len() > rank as usize {
+      bail!("Malformed array shape, more bounds than rank {}", rank);
+    }
+
+    Ok((
+      Self {
+        rank,
+        sizes,
+        lower_bounds,
+      },
+      *offset,
+    ))
+  }
+}
+
+/// How deeply types may nest in a signature, bounding the recursion of the decoder.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+impl TypeSig {
+  /// Reads a type signature nested `depth` types deep, failing past [MAX_DEPTH].
+  pub(crate) fn read(
+    from: &[u8],
+    offset: &mut usize,
+    header: &TablesHeader,
+    depth: usize,
+  ) -> Result<Self> {
+    if depth > MAX_DEPTH {
+      bail!(
+        "Malformed type signature, nested deeper than {} types",
+        MAX_DEPTH
+      );
+    }
+
+    let element = read_element_type(from, offset)?;
+    let nested = |offset: &mut usize| Self::read(from, offset, header, depth + 1);
+
+    let ty = match element {
+      ElementType::ELEMENT_TYPE_VOID => TypeSig::Void,
+      ElementType::ELEMENT_TYPE_BOOLEAN => TypeSig::Boolean,
+      ElementType::ELEMENT_TYPE_CHAR => TypeSig::Char,
+      ElementType::ELEMENT_TYPE_I1 => TypeSig::I1,
+      ElementType::ELEMENT_TYPE_U1 => TypeSig::U1,
+      ElementType::ELEMENT_TYPE_I2 => TypeSig::I2,
+      ElementType::ELEMENT_TYPE_U2 => TypeSig::U2,
+      ElementType::ELEMENT_TYPE_I4 => TypeSig::I4,
+      ElementType::ELEMENT_TYPE_U4 => TypeSig::U4,
+      ElementType::ELEMENT_TYPE_I8 => TypeSig::I8,
+      ElementType::ELEMENT_TYPE_U8 => TypeSig::U8,
+      ElementType::ELEMENT_TYPE_R4 => TypeSig::R4,
+      ElementType::ELEMENT_TYPE_R8 => TypeSig::R8,
+      ElementType::ELEMENT_TYPE_STRING => TypeSig::String,
+      ElementType::ELEMENT_TYPE_I => TypeSig::I,
+      ElementType::ELEMENT_TYPE_U => TypeSig::U,
+      ElementType::ELEMENT_TYPE_OBJECT => TypeSig::Object,
+      ElementType::ELEMENT_TYPE_TYPEDBYREF => TypeSig::TypedByRef,
+      ElementType::ELEMENT_TYPE_PTR => TypeSig::Ptr(Box::new(nested(offset)?)),
+      ElementType::ELEMENT_TYPE_BYREF => TypeSig::ByRef(Box::new(nested(offset)?)),
+      ElementType::ELEMENT_TYPE_VALUETYPE => {
+        TypeSig::ValueType(read_type_def_or_ref(from, offset, header)?)
+      }
+      ElementType::ELEMENT_TYPE_CLASS => {
+        TypeSig::Class(read_type_def_or_ref(from, offset, header)?)
+      }
+      ElementType::ELEMENT_TYPE_VAR => TypeSig::Var(from.gread::<CompressedU32>(offset)?.0),
+      ElementType::ELEMENT_TYPE_MVAR => TypeSig::MVar(from.gread::<CompressedU32>(offset)?.0),
+      ElementType::ELEMENT_TYPE_GENERICINST => {
+        let value_type = match read_element_type(from, offset)? {
+          ElementType::ELEMENT_TYPE_CLASS => false,
+          ElementType::ELEMENT_TYPE_VALUETYPE => true,
+          element => bail!("Malformed GENERICINST, unexpected {:?}", element),
+        };
+
+        let ty = read_type_def_or_ref(from, offset, header)?;
+        let count = from.gread::<CompressedU32>(offset)?.0;
+        let args = (0..count)
+          .map(|_| nested(offset))
+          .collect::<Result<Vec<_>>>()?;
+
+        TypeSig::GenericInst(Box::new(GenericInstSig {
+          value_type,
+          ty,
+          args,
+        }))
+      }
+      ElementType::ELEMENT_TYPE_SZARRAY => TypeSig::SzArray(Box::new(nested(offset)?)),
+      ElementType::ELEMENT_TYPE_ARRAY => {
+        let ty = nested(offset)?;
+        let shape = from.gread(offset)?;
+
+        TypeSig::Array(Box::new(ArraySig { ty, shape }))
+      }
+      ElementType::ELEMENT_TYPE_FNPTR => {
+        TypeSig::FnPtr(Box::new(MethodSig::read(from, offset, header, depth + 1)?))
+      }
+      ElementType::ELEMENT_TYPE_PINNED => TypeSig::Pinned(Box::new(nested(offset)?)),
+      ElementType::ELEMENT_TYPE_CMOD_REQD | ElementType::ELEMENT_TYPE_CMOD_OPT => {
+        let modifier = CustomMod {
+          required: element == ElementType::ELEMENT_TYPE_CMOD_REQD,
+          ty: read_type_def_or_ref(from, offset, header)?,
+        };
+
+        TypeSig::Modified(modifier, Box::new(nested(offset)?))
+      }
+      element => bail!("Unsupported element type {:#x}", element.bits()),
+    };
+
+    Ok(ty)
+  }
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for TypeSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let ty = Self::read(from, offset, header, 0)?;
+
+    Ok((ty, *offset))
+  }
+}
+
+/// A `FieldSig` (II.23.2.4).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldSig {
+  /// The signature kind and flags.
+  pub header: SignatureHeader,
+  /// The field type, including any custom modifiers.
+  pub ty: TypeSig,
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for FieldSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let sig_header = from.gread::<SignatureHeader>(offset)?;
+    if sig_header.kind != SignatureKind::Field {
+      bail!("Expected a field signature, found {:?}", sig_header.kind);
+    }
+
+    let ty = from.gread_with(offset, header)?;
+
+    Ok((
+      Self {
+        header: sig_header,
+        ty,
+      },
+      *offset,
+    ))
+  }
+}
+
+/// The instantiation of a generic method in a `MethodSpec` row (II.23.2.15).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSpecSig {
+  /// The signature kind and flags.
+  pub header: SignatureHeader,
+  /// The generic arguments.
+  pub args: Vec<TypeSig>,
+}
+
+impl<'a, 'h> TryFromCtx<'a, &'h TablesHeader> for MethodSpecSig {
+  type Error = Error;
+
+  fn try_from_ctx(from: &'a [u8], header: &'h TablesHeader) -> Result<(Self, usize)> {
+    let offset = &mut 0;
+    let sig_header = from.gread::<SignatureHeader>(offset)?;
+    if sig_header.kind != SignatureKind::GenericInst {
+      bail!(
+        "Expected a method instantiation, found {:?}",
+        sig_header.kind
+      );
+    }
+
+    let count = from.gread::<CompressedU32>(offset)?.0;
+    let args = (0..count)
+      .map(|_| from.gread_with(offset, header))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok((
+      Self {
+        header: sig_header,
+        args,
+      },
+      *offset,
+    ))
+  }
+}
+
+/// Reads an [ElementType], failing on undefined values.
+pub(crate) fn read_element_type(buf: &[u8], offset: &mut usize) -> Result<ElementType> {
+  let value = buf.gread::<u8>(offset)?;
+  ElementType::from_bits(value).ok_or_else(|| anyhow!("Unsupported element type {:#x}", value))
+}
+
+/// Reads a compressed `TypeDefOrRefOrSpecEncoded` token (II.23.2.8).
+pub(crate) fn read_type_def_or_ref(
+  buf: &[u8],
+  offset: &mut usize,
+  header: &TablesHeader,
+) -> Result<TypeDefOrRef> {
+  TypeDefOrRef::new(buf.gread::<CompressedU32>(offset)?.0, header)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ArrayShape, ArraySig, CustomMod, TypeSig, MAX_DEPTH};
+  use crate::ecma335::{
+    signatures::{MethodSig, SignatureAttributes, SignatureHeader, SignatureKind},
+    tables::{Row, TablesHeader, TypeDefOrRef, TypeRefRow, TypeRefRowId},
+  };
+  use alloc::{boxed::Box, vec};
+  use scroll::Pread;
+
+  #[test]
+  fn array_sig() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    // int32[0...4, -1...]
+    let sig = [0x14u8, 0x08, 0x02, 0x01, 0x05, 0x02, 0x00, 0x7f];
+    let sig = sig.pread_with::<TypeSig>(0, &header).unwrap();
+
+    assert_eq!(
+      sig,
+      TypeSig::Array(Box::new(ArraySig {
+        ty: TypeSig::I4,
+        shape: ArrayShape {
+          rank: 2,
+          sizes: vec![5],
+          lower_bounds: vec![0, -1],
+        },
+      }))
+    );
+  }
+
+  #[test]
+  fn fn_ptr_sig() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    // method void *(int32 modopt(TypeRef 1)&)
+    let sig = [0x1bu8, 0x20, 0x01, 0x01, 0x10, 0x20, 0x05, 0x08];
+    let sig = sig.pread_with::<TypeSig>(0, &header).unwrap();
+    let modifier = CustomMod {
+      required: false,
+      ty: TypeDefOrRef::TypeRef(TypeRefRowId::new(1, &header).unwrap()),
+    };
+
+    assert_eq!(
+      sig,
+      TypeSig::FnPtr(Box::new(MethodSig {
+        header: SignatureHeader {
+          kind: SignatureKind::Default,
+          attributes: SignatureAttributes::HAS_THIS,
+        },
+        generic_param_count: 0,
+        ret: TypeSig::Void,
+        params: vec![TypeSig::ByRef(Box::new(TypeSig::Modified(
+          modifier,
+          Box::new(TypeSig::I4)
+        )))],
+        sentinel: None,
+      }))
+    );
+  }
+
+  #[test]
+  fn malformed_sig() {
+    let header = TablesHeader::with_rows(&[(TypeRefRow::ID, 4)]);
+
+    // A `TypeDefOrRefOrSpecEncoded` tag of 3 is invalid.
+    assert!([0x12u8, 0x07].pread_with::<TypeSig>(0, &header).is_err());
+    // TypeRef 5 is out of range.
+    assert!([0x12u8, 0x15].pread_with::<TypeSig>(0, &header).is_err());
+    // Truncated.
+    assert!([0x1du8].pread_with::<TypeSig>(0, &header).is_err());
+    // 0x88 isn't an element type.
+    assert!([0x88u8].pread_with::<TypeSig>(0, &header).is_err());
+    assert!([0x15u8, 0x88, 0x05, 0x00]
+      .pread_with::<TypeSig>(0, &header)
+      .is_err());
+  }
+
+  #[test]
+  fn nested_sig() {
+    let header = TablesHeader::default();
+
+    // int32* with MAX_DEPTH pointers
+    let mut sig = vec![0x0fu8; MAX_DEPTH];
+    sig.push(0x08);
+    assert!(sig.pread_with::<TypeSig>(0, &header).is_ok());
+
+    // Too deep to decode without overflowing the stack.
+    let mut sig = vec![0x0fu8; 200_000];
+    sig.push(0x08);
+    assert!(sig.pread_with::<TypeSig>(0, &header).is_err());
+  }
+}
@@ -37,12 +37,28 @@ impl<'a> Strings<'a> {
 
     Ok(core::str::from_utf8(cstr.to_bytes())?)
   }
+
+  /// Gets the size of the heap in bytes.
+  pub fn size(&self) -> usize {
+    self.0.len()
+  }
 }
 
 // An index into the [Strings] stream.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct StringIndex(usize);
 
+impl StringIndex {
+  pub(crate) fn new(offset: usize) -> Self {
+    Self(offset)
+  }
+
+  /// Gets the byte offset of the string in the `#Strings` stream.
+  pub fn offset(&self) -> usize {
+    self.0
+  }
+}
+
 impl<'a> TryFromCtx<'a, TablesHeader> for StringIndex {
   type Error = anyhow::Error;
 
@@ -5,16 +5,19 @@
 
 pub mod flags;
 pub mod index;
+pub mod lists;
 #[doc(hidden)]
 pub mod rows;
 #[doc(inline)]
 pub use index::*;
 #[doc(inline)]
+pub use lists::*;
+#[doc(inline)]
 pub use rows::*;
 
-use super::StreamHeader;
-use anyhow::{Context, Error, Result};
-use core::marker::PhantomData;
+use super::{pdb::PdbStream, StreamHeader};
+use anyhow::{bail, Context, Error, Result};
+use core::{cmp::Ordering, marker::PhantomData};
 use scroll::{ctx::TryFromCtx, Pread, LE};
 
 /// The `#~` stream data.
@@ -30,32 +33,47 @@ pub struct Tables<'a> {
   class_layouts: Table<'a, ClassLayoutRow>,
   constants: Table<'a, ConstantRow>,
   custom_attributes: Table<'a, CustomAttributeRow>,
+  custom_debug_information: Table<'a, CustomDebugInformationRow>,
   decl_securities: Table<'a, DeclSecurityRow>,
+  documents: Table<'a, DocumentRow>,
+  enc_logs: Table<'a, EncLogRow>,
+  enc_maps: Table<'a, EncMapRow>,
   events: Table<'a, EventRow>,
   event_maps: Table<'a, EventMapRow>,
+  event_ptrs: Table<'a, EventPtrRow>,
   exported_types: Table<'a, ExportedTypeRow>,
   fields: Table<'a, FieldRow>,
   field_layouts: Table<'a, FieldLayoutRow>,
   field_marshals: Table<'a, FieldMarshalRow>,
+  field_ptrs: Table<'a, FieldPtrRow>,
   field_rvas: Table<'a, FieldRvaRow>,
   files: Table<'a, FileRow>,
   generic_params: Table<'a, GenericParamRow>,
   generic_param_constraints: Table<'a, GenericParamConstraintRow>,
   impl_maps: Table<'a, ImplMapRow>,
+  import_scopes: Table<'a, ImportScopeRow>,
   interface_impls: Table<'a, InterfaceImplRow>,
+  local_constants: Table<'a, LocalConstantRow>,
+  local_scopes: Table<'a, LocalScopeRow>,
+  local_variables: Table<'a, LocalVariableRow>,
   manifest_resources: Table<'a, ManifestResourceRow>,
   member_refs: Table<'a, MemberRefRow>,
+  method_debug_information: Table<'a, MethodDebugInformationRow>,
   method_defs: Table<'a, MethodDefRow>,
   method_impls: Table<'a, MethodImplRow>,
+  method_ptrs: Table<'a, MethodPtrRow>,
   method_semantics: Table<'a, MethodSemanticsRow>,
   method_specs: Table<'a, MethodSpecRow>,
   modules: Table<'a, ModuleRow>,
   module_refs: Table<'a, ModuleRefRow>,
   nested_classes: Table<'a, NestedClassRow>,
   params: Table<'a, ParamRow>,
+  param_ptrs: Table<'a, ParamPtrRow>,
   properties: Table<'a, PropertyRow>,
   property_maps: Table<'a, PropertyMapRow>,
+  property_ptrs: Table<'a, PropertyPtrRow>,
   stand_alone_sigs: Table<'a, StandAloneSigRow>,
+  state_machine_methods: Table<'a, StateMachineMethodRow>,
   type_defs: Table<'a, TypeDefRow>,
   type_refs: Table<'a, TypeRefRow>,
   type_specs: Table<'a, TypeSpecRow>,
@@ -68,11 +86,38 @@ impl<'a> Tables<'a> {
   /// The stream header must be for the `#~` stream and isn't verified here in release builds.  
   /// Don't be a dummy.
   pub fn parse_from_header(header: StreamHeader<'a>) -> Result<Self> {
+    Self::parse(header, false, [0; 64])
+  }
+
+  /// Parses the metadata tables stream of a minimal ENC delta, metadata with a `#JTD` stream, from
+  /// the given stream header.
+  pub fn parse_minimal_delta_from_header(header: StreamHeader<'a>) -> Result<Self> {
+    Self::parse(header, true, [0; 64])
+  }
+
+  /// Parses the metadata tables stream of a portable PDB from the given stream header.
+  ///
+  /// The row counts of the type system tables the debug tables refer to are taken from the `#Pdb`
+  /// stream, the tables themselves are in the module the PDB belongs to.
+  pub fn parse_pdb_from_header(header: StreamHeader<'a>, pdb: &PdbStream) -> Result<Self> {
+    Self::parse(header, false, pdb.type_system_rows)
+  }
+
+  fn parse(
+    header: StreamHeader<'a>,
+    minimal_delta: bool,
+    external_rows: [u32; 64],
+  ) -> Result<Self> {
     debug_assert!(matches!(header.name.to_bytes(), b"#~" | b"#-"));
 
     let buf = header.data()?;
     let offset = &mut 0;
-    let header = buf.gread::<TablesHeader>(offset)?;
+    let header = TablesHeader {
+      external_rows,
+      minimal_delta,
+      ..buf.gread::<TablesHeader>(offset)?
+    };
+
     let mut tables = Self {
       header,
       ..Default::default()
@@ -99,13 +144,21 @@ impl<'a> Tables<'a> {
         ClassLayoutRow::ID => tables.class_layouts = buf.gread_with(offset, header)?,
         ConstantRow::ID => tables.constants = buf.gread_with(offset, header)?,
         CustomAttributeRow::ID => tables.custom_attributes = buf.gread_with(offset, header)?,
+        CustomDebugInformationRow::ID => {
+          tables.custom_debug_information = buf.gread_with(offset, header)?
+        }
         DeclSecurityRow::ID => tables.decl_securities = buf.gread_with(offset, header)?,
+        DocumentRow::ID => tables.documents = buf.gread_with(offset, header)?,
+        EncLogRow::ID => tables.enc_logs = buf.gread_with(offset, header)?,
+        EncMapRow::ID => tables.enc_maps = buf.gread_with(offset, header)?,
         EventRow::ID => tables.events = buf.gread_with(offset, header)?,
         EventMapRow::ID => tables.event_maps = buf.gread_with(offset, header)?,
+        EventPtrRow::ID => tables.event_ptrs = buf.gread_with(offset, header)?,
         ExportedTypeRow::ID => tables.exported_types = buf.gread_with(offset, header)?,
         FieldRow::ID => tables.fields = buf.gread_with(offset, header)?,
         FieldLayoutRow::ID => tables.field_layouts = buf.gread_with(offset, header)?,
         FieldMarshalRow::ID => tables.field_marshals = buf.gread_with(offset, header)?,
+        FieldPtrRow::ID => tables.field_ptrs = buf.gread_with(offset, header)?,
         FieldRvaRow::ID => tables.field_rvas = buf.gread_with(offset, header)?,
         FileRow::ID => tables.files = buf.gread_with(offset, header)?,
         GenericParamRow::ID => tables.generic_params = buf.gread_with(offset, header)?,
@@ -113,30 +166,118 @@ impl<'a> Tables<'a> {
           tables.generic_param_constraints = buf.gread_with(offset, header)?
         }
         ImplMapRow::ID => tables.impl_maps = buf.gread_with(offset, header)?,
+        ImportScopeRow::ID => tables.import_scopes = buf.gread_with(offset, header)?,
         InterfaceImplRow::ID => tables.interface_impls = buf.gread_with(offset, header)?,
+        LocalConstantRow::ID => tables.local_constants = buf.gread_with(offset, header)?,
+        LocalScopeRow::ID => tables.local_scopes = buf.gread_with(offset, header)?,
+        LocalVariableRow::ID => tables.local_variables = buf.gread_with(offset, header)?,
         ManifestResourceRow::ID => tables.manifest_resources = buf.gread_with(offset, header)?,
         MemberRefRow::ID => tables.member_refs = buf.gread_with(offset, header)?,
+        MethodDebugInformationRow::ID => {
+          tables.method_debug_information = buf.gread_with(offset, header)?
+        }
         MethodDefRow::ID => tables.method_defs = buf.gread_with(offset, header)?,
         MethodImplRow::ID => tables.method_impls = buf.gread_with(offset, header)?,
+        MethodPtrRow::ID => tables.method_ptrs = buf.gread_with(offset, header)?,
         MethodSemanticsRow::ID => tables.method_semantics = buf.gread_with(offset, header)?,
         MethodSpecRow::ID => tables.method_specs = buf.gread_with(offset, header)?,
         ModuleRow::ID => tables.modules = buf.gread_with(offset, header)?,
         ModuleRefRow::ID => tables.module_refs = buf.gread_with(offset, header)?,
         NestedClassRow::ID => tables.nested_classes = buf.gread_with(offset, header)?,
         ParamRow::ID => tables.params = buf.gread_with(offset, header)?,
+        ParamPtrRow::ID => tables.param_ptrs = buf.gread_with(offset, header)?,
         PropertyRow::ID => tables.properties = buf.gread_with(offset, header)?,
         PropertyMapRow::ID => tables.property_maps = buf.gread_with(offset, header)?,
+        PropertyPtrRow::ID => tables.property_ptrs = buf.gread_with(offset, header)?,
         StandAloneSigRow::ID => tables.stand_alone_sigs = buf.gread_with(offset, header)?,
+        StateMachineMethodRow::ID => {
+          tables.state_machine_methods = buf.gread_with(offset, header)?
+        }
         TypeDefRow::ID => tables.type_defs = buf.gread_with(offset, header)?,
         TypeRefRow::ID => tables.type_refs = buf.gread_with(offset, header)?,
         TypeSpecRow::ID => tables.type_specs = buf.gread_with(offset, header)?,
-        _ => {}
+        // The size of an unknown table's rows isn't known, so the tables after it can't be found.
+        _ => bail!("Unknown metadata table {:#x}", i),
       }
     }
 
     Ok(tables)
   }
 
+  /// Gets the header of the `#~` stream.
+  pub fn header(&self) -> &TablesHeader {
+    &self.header
+  }
+
+  /// Reads a row of any table, e.g. `tables.read::<FieldRow>(id)`.
+  pub fn read<R: Row<'a>>(&self, index: R::Index) -> Result<R> {
+    if index.offset().is_none() {
+      bail!("Cannot read a null row from table {:#x}", R::ID);
+    }
+
+    R::parse(self.data(R::ID), index, &self.header)
+  }
+
+  /// Gets the row data of a table.
+  fn data(&self, table: usize) -> &'a [u8] {
+    match table {
+      AssemblyRow::ID => self.assemblies.buf,
+      AssemblyOsRow::ID => self.assembly_oses.buf,
+      AssemblyProcessorRow::ID => self.assembly_processors.buf,
+      AssemblyRefRow::ID => self.assembly_refs.buf,
+      AssemblyRefOsRow::ID => self.assembly_ref_oses.buf,
+      AssemblyRefProcessorRow::ID => self.assembly_ref_processors.buf,
+      ClassLayoutRow::ID => self.class_layouts.buf,
+      ConstantRow::ID => self.constants.buf,
+      CustomAttributeRow::ID => self.custom_attributes.buf,
+      CustomDebugInformationRow::ID => self.custom_debug_information.buf,
+      DeclSecurityRow::ID => self.decl_securities.buf,
+      DocumentRow::ID => self.documents.buf,
+      EncLogRow::ID => self.enc_logs.buf,
+      EncMapRow::ID => self.enc_maps.buf,
+      EventRow::ID => self.events.buf,
+      EventMapRow::ID => self.event_maps.buf,
+      EventPtrRow::ID => self.event_ptrs.buf,
+      ExportedTypeRow::ID => self.exported_types.buf,
+      FieldRow::ID => self.fields.buf,
+      FieldLayoutRow::ID => self.field_layouts.buf,
+      FieldMarshalRow::ID => self.field_marshals.buf,
+      FieldPtrRow::ID => self.field_ptrs.buf,
+      FieldRvaRow::ID => self.field_rvas.buf,
+      FileRow::ID => self.files.buf,
+      GenericParamRow::ID => self.generic_params.buf,
+      GenericParamConstraintRow::ID => self.generic_param_constraints.buf,
+      ImplMapRow::ID => self.impl_maps.buf,
+      ImportScopeRow::ID => self.import_scopes.buf,
+      InterfaceImplRow::ID => self.interface_impls.buf,
+      LocalConstantRow::ID => self.local_constants.buf,
+      LocalScopeRow::ID => self.local_scopes.buf,
+      LocalVariableRow::ID => self.local_variables.buf,
+      ManifestResourceRow::ID => self.manifest_resources.buf,
+      MemberRefRow::ID => self.member_refs.buf,
+      MethodDebugInformationRow::ID => self.method_debug_information.buf,
+      MethodDefRow::ID => self.method_defs.buf,
+      MethodImplRow::ID => self.method_impls.buf,
+      MethodPtrRow::ID => self.method_ptrs.buf,
+      MethodSemanticsRow::ID => self.method_semantics.buf,
+      MethodSpecRow::ID => self.method_specs.buf,
+      ModuleRow::ID => self.modules.buf,
+      ModuleRefRow::ID => self.module_refs.buf,
+      NestedClassRow::ID => self.nested_classes.buf,
+      ParamRow::ID => self.params.buf,
+      ParamPtrRow::ID => self.param_ptrs.buf,
+      PropertyRow::ID => self.properties.buf,
+      PropertyMapRow::ID => self.property_maps.buf,
+      PropertyPtrRow::ID => self.property_ptrs.buf,
+      StandAloneSigRow::ID => self.stand_alone_sigs.buf,
+      StateMachineMethodRow::ID => self.state_machine_methods.buf,
+      TypeDefRow::ID => self.type_defs.buf,
+      TypeRefRow::ID => self.type_refs.buf,
+      TypeSpecRow::ID => self.type_specs.buf,
+      _ => &[],
+    }
+  }
+
   pub fn assemblies<'t: 'a>(&'t self) -> TableRowReader<'a, 't, AssemblyRow> {
     TableRowReader::new(&self.assemblies, &self.header)
   }
@@ -175,10 +316,28 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.custom_attributes, &self.header)
   }
 
+  pub fn custom_debug_information<'t: 'a>(
+    &'t self,
+  ) -> TableRowReader<'a, 't, CustomDebugInformationRow> {
+    TableRowReader::new(&self.custom_debug_information, &self.header)
+  }
+
   pub fn decl_securities<'t: 'a>(&'t self) -> TableRowReader<'a, 't, DeclSecurityRow> {
     TableRowReader::new(&self.decl_securities, &self.header)
   }
 
+  pub fn documents<'t: 'a>(&'t self) -> TableRowReader<'a, 't, DocumentRow> {
+    TableRowReader::new(&self.documents, &self.header)
+  }
+
+  pub fn enc_logs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, EncLogRow> {
+    TableRowReader::new(&self.enc_logs, &self.header)
+  }
+
+  pub fn enc_maps<'t: 'a>(&'t self) -> TableRowReader<'a, 't, EncMapRow> {
+    TableRowReader::new(&self.enc_maps, &self.header)
+  }
+
   pub fn events<'t: 'a>(&'t self) -> TableRowReader<'a, 't, EventRow> {
     TableRowReader::new(&self.events, &self.header)
   }
@@ -187,6 +346,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.event_maps, &self.header)
   }
 
+  pub fn event_ptrs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, EventPtrRow> {
+    TableRowReader::new(&self.event_ptrs, &self.header)
+  }
+
   pub fn exported_types<'t: 'a>(&'t self) -> TableRowReader<'a, 't, ExportedTypeRow> {
     TableRowReader::new(&self.exported_types, &self.header)
   }
@@ -203,6 +366,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.field_marshals, &self.header)
   }
 
+  pub fn field_ptrs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, FieldPtrRow> {
+    TableRowReader::new(&self.field_ptrs, &self.header)
+  }
+
   pub fn field_rvas<'t: 'a>(&'t self) -> TableRowReader<'a, 't, FieldRvaRow> {
     TableRowReader::new(&self.field_rvas, &self.header)
   }
@@ -225,10 +392,26 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.impl_maps, &self.header)
   }
 
+  pub fn import_scopes<'t: 'a>(&'t self) -> TableRowReader<'a, 't, ImportScopeRow> {
+    TableRowReader::new(&self.import_scopes, &self.header)
+  }
+
   pub fn interface_impls<'t: 'a>(&'t self) -> TableRowReader<'a, 't, InterfaceImplRow> {
     TableRowReader::new(&self.interface_impls, &self.header)
   }
 
+  pub fn local_constants<'t: 'a>(&'t self) -> TableRowReader<'a, 't, LocalConstantRow> {
+    TableRowReader::new(&self.local_constants, &self.header)
+  }
+
+  pub fn local_scopes<'t: 'a>(&'t self) -> TableRowReader<'a, 't, LocalScopeRow> {
+    TableRowReader::new(&self.local_scopes, &self.header)
+  }
+
+  pub fn local_variables<'t: 'a>(&'t self) -> TableRowReader<'a, 't, LocalVariableRow> {
+    TableRowReader::new(&self.local_variables, &self.header)
+  }
+
   pub fn manifest_resources<'t: 'a>(&'t self) -> TableRowReader<'a, 't, ManifestResourceRow> {
     TableRowReader::new(&self.manifest_resources, &self.header)
   }
@@ -237,6 +420,12 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.member_refs, &self.header)
   }
 
+  pub fn method_debug_information<'t: 'a>(
+    &'t self,
+  ) -> TableRowReader<'a, 't, MethodDebugInformationRow> {
+    TableRowReader::new(&self.method_debug_information, &self.header)
+  }
+
   pub fn method_defs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, MethodDefRow> {
     TableRowReader::new(&self.method_defs, &self.header)
   }
@@ -245,6 +434,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.method_impls, &self.header)
   }
 
+  pub fn method_ptrs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, MethodPtrRow> {
+    TableRowReader::new(&self.method_ptrs, &self.header)
+  }
+
   pub fn method_semantics<'t: 'a>(&'t self) -> TableRowReader<'a, 't, MethodSemanticsRow> {
     TableRowReader::new(&self.method_semantics, &self.header)
   }
@@ -269,6 +462,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.params, &self.header)
   }
 
+  pub fn param_ptrs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, ParamPtrRow> {
+    TableRowReader::new(&self.param_ptrs, &self.header)
+  }
+
   pub fn properties<'t: 'a>(&'t self) -> TableRowReader<'a, 't, PropertyRow> {
     TableRowReader::new(&self.properties, &self.header)
   }
@@ -277,6 +474,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.property_maps, &self.header)
   }
 
+  pub fn property_ptrs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, PropertyPtrRow> {
+    TableRowReader::new(&self.property_ptrs, &self.header)
+  }
+
   pub fn stand_alone_sigs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, StandAloneSigRow> {
     TableRowReader::new(&self.stand_alone_sigs, &self.header)
   }
@@ -289,6 +490,10 @@ impl<'a> Tables<'a> {
     TableRowReader::new(&self.type_refs, &self.header)
   }
 
+  pub fn state_machine_methods<'t: 'a>(&'t self) -> TableRowReader<'a, 't, StateMachineMethodRow> {
+    TableRowReader::new(&self.state_machine_methods, &self.header)
+  }
+
   pub fn type_specs<'t: 'a>(&'t self) -> TableRowReader<'a, 't, TypeSpecRow> {
     TableRowReader::new(&self.type_specs, &self.header)
   }
@@ -370,9 +575,45 @@ impl<'a, 't, R: Row<'a>> TableRowReader<'a, 't, R> {
     self.table.is_empty()
   }
 
+  /// Reads the row at the given index, failing if the index is null.
   pub fn read(&self, index: R::Index) -> Result<R> {
+    if index.offset().is_none() {
+      bail!("Cannot read a null row from table {:#x}", R::ID);
+    }
+
     R::parse(self.table.buf, index, self.header)
   }
+
+  /// Finds a row whose key, as returned by `key_of`, equals the given key.
+  ///
+  /// Tables marked as sorted are binary searched, anything else is scanned.
+  pub fn find<K: Ord>(&self, key: &K, key_of: impl Fn(&R) -> K) -> Result<Option<R>> {
+    if !self.header.is_sorted(R::ID) {
+      for row in Self::new(self.table, self.header) {
+        let row = row?;
+        if key_of(&row) == *key {
+          return Ok(Some(row));
+        }
+      }
+
+      return Ok(None);
+    }
+
+    let (mut lo, mut hi) = (1, self.len() as u32 + 1);
+
+    while lo < hi {
+      let mid = lo + (hi - lo) / 2;
+      let row = self.read(R::Index::from_row(mid, self.header)?)?;
+
+      match key_of(&row).cmp(key) {
+        Ordering::Less => lo = mid + 1,
+        Ordering::Greater => hi = mid,
+        Ordering::Equal => return Ok(Some(row)),
+      }
+    }
+
+    Ok(None)
+  }
 }
 
 impl<'a, 't, R: Row<'a>> IntoIterator for TableRowReader<'a, 't, R> {
@@ -430,6 +671,12 @@ pub struct TablesHeader {
   /// The array containing the values representing the number of rows in a table, indexed by the id
   /// of the table.
   pub rows: [u32; 64],
+  /// The row counts of the type system tables of the module a portable PDB belongs to, indexed by
+  /// the id of the table.  The tables aren't in the PDB, but its indexes refer to them.
+  pub external_rows: [u32; 64],
+  /// Set for minimal ENC deltas, marked by a `#JTD` stream, whose row and coded indexes are always
+  /// 4 bytes wide and refer to rows of the aggregate metadata.
+  pub minimal_delta: bool,
 }
 
 impl TablesHeader {
@@ -437,6 +684,31 @@ impl TablesHeader {
   pub fn has_table(&self, id: usize) -> bool {
     self.valid & (1 << id) != 0
   }
+
+  /// Determines if the table is marked as sorted by its primary key.
+  pub fn is_sorted(&self, id: usize) -> bool {
+    self.sorted & (1 << id) != 0
+  }
+
+  /// Gets the number of rows indexes into the table may refer to, the rows of the table in the
+  /// module a portable PDB belongs to if the table isn't in this metadata.
+  pub fn index_rows(&self, id: usize) -> u32 {
+    match self.rows[id] {
+      0 => self.external_rows[id],
+      rows => rows,
+    }
+  }
+
+  /// Builds a header with the given `(table id, rows)` counts for unit tests.
+  #[cfg(test)]
+  pub(crate) fn with_rows(rows: &[(usize, u32)]) -> Self {
+    let mut header = Self::default();
+    for &(id, count) in rows {
+      header.rows[id] = count;
+    }
+
+    header
+  }
 }
 
 impl<'a> TryFromCtx<'a> for TablesHeader {
@@ -448,7 +720,7 @@ impl<'a> TryFromCtx<'a> for TablesHeader {
     *offset += 4;
     let major_version = from.gread_with(offset, LE)?;
     let minor_version = from.gread_with(offset, LE)?;
-    let heap_sizes = from.gread_with(offset, LE)?;
+    let heap_sizes: HeapSizes = from.gread_with(offset, LE)?;
     *offset += 1;
     let valid = from.gread_with(offset, LE)?;
     let sorted = from.gread_with(offset, LE)?;
@@ -460,6 +732,11 @@ impl<'a> TryFromCtx<'a> for TablesHeader {
       }
     }
 
+    // Uncompressed streams can have a dword of extra data between the row counts and the tables.
+    if heap_sizes.contains(HeapSizes::EXTRA_DATA) {
+      *offset += 4;
+    }
+
     Ok((
       Self {
         _reserved_0: 0,
@@ -470,6 +747,8 @@ impl<'a> TryFromCtx<'a> for TablesHeader {
         valid,
         sorted,
         rows,
+        external_rows: [0; 64],
+        minimal_delta: false,
       },
       *offset,
     ))
@@ -487,6 +766,8 @@ impl Default for TablesHeader {
       valid: 0,
       sorted: 0,
       rows: [0; 64],
+      external_rows: [0; 64],
+      minimal_delta: false,
     }
   }
 }
@@ -501,5 +782,11 @@ bitflags::bitflags! {
     const WIDE_GUID_HEAP = 0x02;
     /// If set indicates the `#Blob` heap index should be `4` bytes wide, otherwise `2`.
     const WIDE_BLOB_HEAP = 0x04;
+    /// Set in ENC deltas holding only the changes to the metadata.
+    const DELTA_ONLY = 0x20;
+    /// If set a dword of extra data follows the row counts in a `#-` stream.
+    const EXTRA_DATA = 0x40;
+    /// Set in `#-` streams that may contain rows deleted by ENC, named `_Deleted`.
+    const HAS_DELETE = 0x80;
   }
 }
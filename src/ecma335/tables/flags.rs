@@ -1,8 +1,9 @@
 use anyhow::Error;
 use scroll::{ctx::TryFromCtx, Pread, SizeWith};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AssemblyHashAlgorithm {
+  #[default]
   None = 0x0000,
   MD5 = 0x8003,
   SHA1 = 0x8004,
@@ -25,12 +26,6 @@ impl From<u32> for AssemblyHashAlgorithm {
   }
 }
 
-impl Default for AssemblyHashAlgorithm {
-  fn default() -> Self {
-    Self::None
-  }
-}
-
 impl TryFromCtx<'_> for AssemblyHashAlgorithm {
   type Error = Error;
 
@@ -45,6 +40,155 @@ impl scroll::ctx::SizeWith<()> for AssemblyHashAlgorithm {
   }
 }
 
+/// The security action of a `DeclSecurity` row, the `System.Security.Permissions.SecurityAction`
+/// enum and a few actions only the runtime uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SecurityAction {
+  Request,
+  Demand,
+  Assert,
+  Deny,
+  PermitOnly,
+  LinkDemand,
+  InheritanceDemand,
+  RequestMinimum,
+  RequestOptional,
+  RequestRefuse,
+  PrejitGrant,
+  PrejitDenied,
+  NonCasDemand,
+  NonCasLinkDemand,
+  NonCasInheritance,
+  /// An action not defined by the runtime.
+  Unknown(u16),
+}
+
+impl SecurityAction {
+  /// Gets the encoded value.
+  pub fn value(&self) -> u16 {
+    match self {
+      Self::Request => 0x0001,
+      Self::Demand => 0x0002,
+      Self::Assert => 0x0003,
+      Self::Deny => 0x0004,
+      Self::PermitOnly => 0x0005,
+      Self::LinkDemand => 0x0006,
+      Self::InheritanceDemand => 0x0007,
+      Self::RequestMinimum => 0x0008,
+      Self::RequestOptional => 0x0009,
+      Self::RequestRefuse => 0x000a,
+      Self::PrejitGrant => 0x000b,
+      Self::PrejitDenied => 0x000c,
+      Self::NonCasDemand => 0x000d,
+      Self::NonCasLinkDemand => 0x000e,
+      Self::NonCasInheritance => 0x000f,
+      Self::Unknown(value) => *value,
+    }
+  }
+}
+
+impl From<u16> for SecurityAction {
+  fn from(value: u16) -> Self {
+    match value {
+      0x0001 => Self::Request,
+      0x0002 => Self::Demand,
+      0x0003 => Self::Assert,
+      0x0004 => Self::Deny,
+      0x0005 => Self::PermitOnly,
+      0x0006 => Self::LinkDemand,
+      0x0007 => Self::InheritanceDemand,
+      0x0008 => Self::RequestMinimum,
+      0x0009 => Self::RequestOptional,
+      0x000a => Self::RequestRefuse,
+      0x000b => Self::PrejitGrant,
+      0x000c => Self::PrejitDenied,
+      0x000d => Self::NonCasDemand,
+      0x000e => Self::NonCasLinkDemand,
+      0x000f => Self::NonCasInheritance,
+      value => Self::Unknown(value),
+    }
+  }
+}
+
+impl TryFromCtx<'_> for SecurityAction {
+  type Error = Error;
+
+  fn try_from_ctx(value: &[u8], _: ()) -> Result<(Self, usize), Self::Error> {
+    Ok((Self::from(value.pread_with::<u16>(0, scroll::LE)?), 2))
+  }
+}
+
+impl scroll::ctx::SizeWith<()> for SecurityAction {
+  fn size_with(_: &()) -> usize {
+    2
+  }
+}
+
+/// The edit an `ENCLog` row records.
+///
+/// The `Add*` codes are logged against the parent of the added member, the row logged next is the
+/// member itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EncFuncCode {
+  /// The row was added or updated.
+  Default,
+  /// A method is added to the logged type.
+  AddMethod,
+  /// A field is added to the logged type.
+  AddField,
+  /// A parameter is added to the logged method.
+  AddParameter,
+  /// A property is added to the logged property map.
+  AddProperty,
+  /// An event is added to the logged event map.
+  AddEvent,
+  /// A function code not defined by the runtime.
+  Unknown(u32),
+}
+
+impl EncFuncCode {
+  /// Gets the encoded value.
+  pub fn value(&self) -> u32 {
+    match self {
+      Self::Default => 0,
+      Self::AddMethod => 1,
+      Self::AddField => 2,
+      Self::AddParameter => 3,
+      Self::AddProperty => 4,
+      Self::AddEvent => 5,
+      Self::Unknown(value) => *value,
+    }
+  }
+}
+
+impl From<u32> for EncFuncCode {
+  fn from(value: u32) -> Self {
+    match value {
+      0 => Self::Default,
+      1 => Self::AddMethod,
+      2 => Self::AddField,
+      3 => Self::AddParameter,
+      4 => Self::AddProperty,
+      5 => Self::AddEvent,
+      value => Self::Unknown(value),
+    }
+  }
+}
+
+impl TryFromCtx<'_> for EncFuncCode {
+  type Error = Error;
+
+  fn try_from_ctx(value: &[u8], _: ()) -> Result<(Self, usize), Self::Error> {
+    Ok((Self::from(value.pread_with::<u32>(0, scroll::LE)?), 4))
+  }
+}
+
+impl scroll::ctx::SizeWith<()> for EncFuncCode {
+  fn size_with(_: &()) -> usize {
+    4
+  }
+}
+
 bitflags::bitflags! {
   #[derive(Pread, SizeWith)]
   pub struct AssemblyFlags : u32 {
@@ -162,6 +306,14 @@ bitflags::bitflags! {
   }
 }
 
+bitflags::bitflags! {
+  #[derive(Pread, SizeWith)]
+  pub struct LocalVariableAttributes : u16 {
+    /// The variable is compiler generated and shouldn't be shown in the debugger.
+    const DEBUGGER_HIDDEN = 0x0001;
+  }
+}
+
 bitflags::bitflags! {
   #[derive(Pread, SizeWith)]
   pub struct ManifestResourceAttributes : u32 {
@@ -175,58 +327,40 @@ bitflags::bitflags! {
 bitflags::bitflags! {
   #[derive(Pread, SizeWith)]
   pub struct MethodImplAttributes : u16 {
-    /// These 3 bits contain one of the following values:
-    const MEMBER_ACCESS_MASK = 0x0007;
-    /// Member not referenceable
-    const COMPILER_CONTROLLED = 0x0000;
-    /// Accessible only by the parent type
-    const PRIVATE = 0x0001;
-    /// Accessible by sub-types only in this Assembly
-    const FAM_AND_ASSEM = 0x0002;
-    /// Accessibly by anyone in the Assembly
-    const ASSEM = 0x0003;
-    /// Accessible only by type and sub-types
-    const FAMILY = 0x0004;
-    /// Accessibly by sub-types anywhere, plus anyone in assembly
-    const FAM_OR_ASSEM = 0x0005;
-    /// Accessibly by anyone who has visibility to this scope
-    const PUBLIC = 0x0006;
-    /// Defined on type, else per instance
-    const STATIC = 0x0010;
-    /// Method cannot be overridden
-    const FINAL = 0x0020;
-    /// Method is virtual
-    const VIRTUAL = 0x0040;
-    /// Method hides by name+sig, else just by name
-    const HIDE_BY_SIG = 0x0080;
-    /// Use this mask to retrieve vtable attributes. This bit contains one of the following values:
-    const VTABLE_LAYOUT_MASK = 0x0100;
-    /// Method reuses existing slot in vtable
-    const REUSE_SLOT = 0x0000;
-    /// Method always gets a new slot in the vtable
-    const NEW_SLOT = 0x0100;
-    /// Method can only be overriden if also accessible
-    const STRICT = 0x0200;
-    /// Method does not provide an implementation
-    const ABSTRACT = 0x0400;
-    /// Method is special
-    const SPECIAL_NAME = 0x0800;
-
-    // Interop attributes
-
-    /// Implementation is forwarded through PInvoke
-    const P_INVOKE_IMPL = 0x2000;
-    /// Reserved: shall be zero for conforming implementations
-    const UNMANAGED_EXPORT = 0x0008;
-
-    // Additional flags
-
-    /// CLI provides 'special' behavior, depending upon the name of the method
-    const RT_SPECIAL_NAME = 0x1000;
-    /// Method has security associate with it
-    const HAS_SECURITY = 0x4000;
-    /// Method calls another method containing security code.
-    const REQUIRE_SEC_OBJECT = 0x8000;
+    /// These 2 bits contain one of the following values:
+    const CODE_TYPE_MASK = 0x0003;
+    /// Method impl is CIL
+    const IL = 0x0000;
+    /// Method impl is native
+    const NATIVE = 0x0001;
+    /// Reserved: shall be zero in conforming implementations
+    const OPTIL = 0x0002;
+    /// Method impl is provided by the runtime
+    const RUNTIME = 0x0003;
+    /// Flags specifying whether the code is managed or unmanaged. This bit contains one of the
+    /// following values:
+    const MANAGED_MASK = 0x0004;
+    /// Method impl is unmanaged, otherwise managed
+    const UNMANAGED = 0x0004;
+    /// Method impl is managed
+    const MANAGED = 0x0000;
+
+    // Implementation info and interop
+
+    /// Indicates method is defined; used primarily in merge scenarios
+    const FORWARD_REF = 0x0010;
+    /// Reserved: conforming implementations can ignore
+    const PRESERVE_SIG = 0x0080;
+    /// Reserved: shall be zero in conforming implementations
+    const INTERNAL_CALL = 0x1000;
+    /// Method is single threaded through the body
+    const SYNCHRONIZED = 0x0020;
+    /// Method cannot be inlined
+    const NO_INLINING = 0x0008;
+    /// Method should be inlined if possible
+    const AGGRESSIVE_INLINING = 0x0100;
+    /// Method will not be optimized when generating native code
+    const NO_OPTIMIZATION = 0x0040;
   }
 }
 
@@ -466,5 +600,10 @@ bitflags::bitflags! {
     const ELEMENT_TYPE_MODIFIER =  0x40; //  Or’d with following element types
     const ELEMENT_TYPE_SENTINEL =  0x41; //  Sentinel for vararg method signature
     const ELEMENT_TYPE_PINNED =  0x45; //  Denotes a local variable that points at a pinned object
+    const ELEMENT_TYPE_SYSTEM_TYPE =  0x50; //  Indicates an argument of type System.Type in a custom attribute
+    const ELEMENT_TYPE_BOXED =  0x51; //  Used in custom attributes to specify a boxed object
+    const ELEMENT_TYPE_FIELD =  0x53; //  Used in custom attributes to indicate a FIELD
+    const ELEMENT_TYPE_PROPERTY =  0x54; //  Used in custom attributes to indicate a PROPERTY
+    const ELEMENT_TYPE_ENUM =  0x55; //  Used in custom attributes to specify an enum
   }
 }
@@ -1,38 +1,53 @@
 use super::{rows::*, TablesHeader};
-use anyhow::{bail, Error, Result};
+use crate::ecma335::token::Token;
+use anyhow::{anyhow, bail, Error, Result};
 use scroll::{
   ctx::{SizeWith, TryFromCtx},
   Pread, LE,
 };
 
 /// A row index.
+///
+/// Row ids are 1-based, a row id of 0 is null and doesn't refer to any row.  Columns that are
+/// allowed to be null are typed as `Option<_>` and parse a 0 row id as `None`, everywhere else a
+/// null row id is rejected while parsing.
+///
+/// The row ids of a minimal ENC delta refer to rows of the aggregate metadata, so they aren't
+/// checked against the row counts of the delta.
 pub trait RowIndex: Sized {
   /// Gets the next row id of the same type.
   fn next(self) -> Option<Self>;
 
   /// Gets the first index.
   fn first(header: &TablesHeader) -> Option<Self>;
+
+  /// Gets the zero-based position of the row in the table data, or `None` if the row id is null.
+  fn offset(self) -> Option<usize>;
+
+  /// Creates an index from a 1-based row id, failing if it's null or out of range.
+  fn from_row(row: u32, header: &TablesHeader) -> Result<Self>;
 }
 
 macro_rules! simple_index {
   ($name:ident, $row:ident) => {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub struct $name {
-      /// The row index.
+      /// The 1-based row id.
       pub(crate) row: u32,
       /// The total number of rows in the table.
       rows: u32,
     }
 
     impl $name {
-      #[doc = concat!("Creates a new [", stringify!($name), "].")]
+      #[doc = concat!("Creates a new [", stringify!($name), "] from a 1-based row id.")]
       pub fn new(row: u32, header: &TablesHeader) -> Result<Self> {
-        let rows = header.rows[$row::ID];
+        let rows = header.index_rows($row::ID);
 
-        match row <= rows {
-          true => Ok(Self { row, rows }),
-          false => bail!(
-            "`{}`({}) too large, expected less than {}",
+        match row {
+          0 => bail!("`{}` is null", stringify!($name)),
+          _ if row <= rows || header.minimal_delta => Ok(Self { row, rows }),
+          _ => bail!(
+            "`{}`({}) too large, expected at most {}",
             stringify!($name),
             row,
             rows
@@ -44,6 +59,37 @@ macro_rules! simple_index {
       pub(crate) unsafe fn new_unchecked(row: u32) -> Self {
         Self { row, rows: 0 }
       }
+
+      /// Gets the 1-based row id.
+      pub fn row(&self) -> u32 {
+        self.row
+      }
+
+      #[doc = concat!(
+        "Creates a [", stringify!($name), "] from a metadata token, failing if the token is for ",
+        "another table."
+      )]
+      pub fn from_token(token: Token, header: &TablesHeader) -> Result<Self> {
+        match token.table() as usize {
+          $row::ID => Self::new(token.row(), header),
+          table => bail!(
+            "Expected a `{}` token, found table {:#x}",
+            stringify!($name),
+            table
+          ),
+        }
+      }
+
+      /// Gets the metadata token of the row.
+      pub fn token(&self) -> Token {
+        Token::from((($row::ID as u32) << 24) | self.row)
+      }
+    }
+
+    impl From<$name> for Token {
+      fn from(index: $name) -> Self {
+        index.token()
+      }
     }
 
     impl RowIndex for $name {
@@ -51,7 +97,7 @@ macro_rules! simple_index {
         let row = self.row + 1;
         let rows = self.rows;
 
-        match row < rows {
+        match row <= rows {
           true => Some(Self { row, rows }),
           false => None,
         }
@@ -61,37 +107,65 @@ macro_rules! simple_index {
         let rows = header.rows[$row::ID];
 
         match rows > 0 {
-          true => Some(Self { row: 0, rows }),
+          true => Some(Self { row: 1, rows }),
           false => None,
         }
       }
+
+      fn offset(self) -> Option<usize> {
+        self.row.checked_sub(1).map(|row| row as usize)
+      }
+
+      fn from_row(row: u32, header: &TablesHeader) -> Result<Self> {
+        Self::new(row, header)
+      }
     }
 
     impl<'a> TryFromCtx<'a, TablesHeader> for $name {
       type Error = Error;
 
+      fn try_from_ctx(from: &'a [u8], ctx: TablesHeader) -> Result<(Self, usize)> {
+        let (row, size) = <Option<$name>>::try_from_ctx(from, ctx)?;
+        let row = row.ok_or_else(|| anyhow!("`{}` is null", stringify!($name)))?;
+
+        Ok((row, size))
+      }
+    }
+
+    impl<'a> TryFromCtx<'a, TablesHeader> for Option<$name> {
+      type Error = Error;
+
       fn try_from_ctx(from: &'a [u8], ctx: TablesHeader) -> Result<(Self, usize)> {
         let offset = &mut 0;
-        let row = match Self::size_with(&ctx) {
+        let row = match $name::size_with(&ctx) {
           4 => from.gread_with::<u32>(offset, LE)?,
           2 => from.gread_with::<u16>(offset, LE)?.into(),
           _ => panic!("Invalid size"),
         };
 
-        Ok(($name::new(row, &ctx)?, *offset))
+        match row {
+          0 => Ok((None, *offset)),
+          _ => Ok((Some($name::new(row, &ctx)?), *offset)),
+        }
       }
     }
 
     impl SizeWith<TablesHeader> for $name {
       fn size_with(header: &TablesHeader) -> usize {
-        let rows = header.rows[$row::ID];
+        let rows = header.index_rows($row::ID);
 
-        match rows < 1 << 16 {
+        match rows < 1 << 16 && !header.minimal_delta {
           true => 2,
           false => 4,
         }
       }
     }
+
+    impl SizeWith<TablesHeader> for Option<$name> {
+      fn size_with(header: &TablesHeader) -> usize {
+        $name::size_with(header)
+      }
+    }
   };
 }
 
@@ -106,7 +180,7 @@ macro_rules! coded_index {
       }
     ) => {
       $(#[$attr])*
-      #[derive(Debug, PartialEq, Eq)]
+      #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
       pub enum $name {
         $(
           $(#[$variant_attr])*
@@ -127,6 +201,38 @@ macro_rules! coded_index {
           }
         }
 
+        /// Gets the encoded value, the row shifted left by the tag width with the tag in the low
+        /// bits.
+        pub fn encode(&self) -> u32 {
+          match self {
+            $(
+              Self::$variant(index) => (index.row << $bits) | $tag,
+            )*
+          }
+        }
+
+        #[doc = concat!(
+          "Creates a [", stringify!($name), "] from a metadata token for any of its tables."
+        )]
+        pub fn from_token(token: Token, header: &TablesHeader) -> Result<Self> {
+          $(
+            if token.table() as usize == $table::ID {
+              return Ok(Self::$variant($index::new(token.row(), header)?));
+            }
+          )*
+
+          bail!("Unexpected table {:#x} in `{}` token", token.table(), stringify!($name))
+        }
+
+        /// Gets the metadata token of the row.
+        pub fn token(&self) -> Token {
+          match self {
+            $(
+              Self::$variant(index) => index.token(),
+            )*
+          }
+        }
+
         #[allow(dead_code)]
         pub(crate) unsafe fn new_unchecked(val: u32) -> Self {
           let tag = val & ((1 << $bits) - 1);
@@ -141,26 +247,50 @@ macro_rules! coded_index {
         }
       }
 
+      impl From<$name> for Token {
+        fn from(index: $name) -> Self {
+          index.token()
+        }
+      }
+
       impl<'a> TryFromCtx<'a, TablesHeader> for $name {
         type Error = Error;
 
+        fn try_from_ctx(from: &'a [u8], ctx: TablesHeader) -> Result<(Self, usize)> {
+          let (val, size) = <Option<$name>>::try_from_ctx(from, ctx)?;
+          let val = val.ok_or_else(|| anyhow!("`{}` is null", stringify!($name)))?;
+
+          Ok((val, size))
+        }
+      }
+
+      impl<'a> TryFromCtx<'a, TablesHeader> for Option<$name> {
+        type Error = Error;
+
         fn try_from_ctx(from: &'a [u8], ctx: TablesHeader) -> Result<(Self, usize)> {
           let offset = &mut 0;
-          let size = Self::size_with(&ctx);
-          let val = match Self::size_with(&ctx) {
+          let val = match $name::size_with(&ctx) {
             4 => from.gread_with::<u32>(offset, LE)?,
             2 => from.gread_with::<u16>(offset, LE)?.into(),
             _ => panic!("Invalid size"),
           };
 
-          Self::new(val, &ctx).map(|val| (val, size))
+          // A null coded index has a row id of 0, the tag is meaningless.
+          match val >> $bits {
+            0 => Ok((None, *offset)),
+            _ => Ok((Some($name::new(val, &ctx)?), *offset)),
+          }
         }
       }
 
       impl SizeWith<TablesHeader> for $name {
         fn size_with(header: &TablesHeader) -> usize {
+          if header.minimal_delta {
+            return 4;
+          }
+
           $(
-            if header.rows[$table::ID] >= (1u32 << (16 - $bits)) {
+            if header.index_rows($table::ID) >= (1u32 << (16 - $bits)) {
               return 4;
             }
           )+
@@ -168,6 +298,12 @@ macro_rules! coded_index {
           2
         }
       }
+
+      impl SizeWith<TablesHeader> for Option<$name> {
+        fn size_with(header: &TablesHeader) -> usize {
+          $name::size_with(header)
+        }
+      }
     };
 }
 
@@ -180,32 +316,47 @@ simple_index!(AssemblyRefProcessorRowId, AssemblyRefProcessorRow);
 simple_index!(ClassLayoutRowId, ClassLayoutRow);
 simple_index!(ConstantRowId, ConstantRow);
 simple_index!(CustomAttributeRowId, CustomAttributeRow);
+simple_index!(CustomDebugInformationRowId, CustomDebugInformationRow);
 simple_index!(DeclSecurityRowId, DeclSecurityRow);
+simple_index!(DocumentRowId, DocumentRow);
+simple_index!(EncLogRowId, EncLogRow);
+simple_index!(EncMapRowId, EncMapRow);
 simple_index!(EventRowId, EventRow);
 simple_index!(EventMapRowId, EventMapRow);
+simple_index!(EventPtrRowId, EventPtrRow);
 simple_index!(ExportedTypeRowId, ExportedTypeRow);
 simple_index!(FieldRowId, FieldRow);
 simple_index!(FieldLayoutRowId, FieldLayoutRow);
 simple_index!(FieldMarshalRowId, FieldMarshalRow);
+simple_index!(FieldPtrRowId, FieldPtrRow);
 simple_index!(FieldRvaRowId, FieldRvaRow);
 simple_index!(FileRowId, FileRow);
 simple_index!(GenericParamRowId, GenericParamRow);
 simple_index!(GenericParamConstraintRowId, GenericParamConstraintRow);
 simple_index!(ImplMapRowId, ImplMapRow);
+simple_index!(ImportScopeRowId, ImportScopeRow);
 simple_index!(InterfaceImplRowId, InterfaceImplRow);
+simple_index!(LocalConstantRowId, LocalConstantRow);
+simple_index!(LocalScopeRowId, LocalScopeRow);
+simple_index!(LocalVariableRowId, LocalVariableRow);
 simple_index!(ManifestResourceRowId, ManifestResourceRow);
 simple_index!(MemberRefRowId, MemberRefRow);
+simple_index!(MethodDebugInformationRowId, MethodDebugInformationRow);
 simple_index!(MethodDefRowId, MethodDefRow);
 simple_index!(MethodImplRowId, MethodImplRow);
+simple_index!(MethodPtrRowId, MethodPtrRow);
 simple_index!(MethodSemanticsRowId, MethodSemanticsRow);
 simple_index!(MethodSpecRowId, MethodSpecRow);
 simple_index!(ModuleRowId, ModuleRow);
 simple_index!(ModuleRefRowId, ModuleRefRow);
 simple_index!(NestedClassRowId, NestedClassRow);
 simple_index!(ParamRowId, ParamRow);
+simple_index!(ParamPtrRowId, ParamPtrRow);
 simple_index!(PropertyRowId, PropertyRow);
 simple_index!(PropertyMapRowId, PropertyMapRow);
+simple_index!(PropertyPtrRowId, PropertyPtrRow);
 simple_index!(StandAloneSigRowId, StandAloneSigRow);
+simple_index!(StateMachineMethodRowId, StateMachineMethodRow);
 simple_index!(TypeDefRowId, TypeDefRow);
 simple_index!(TypeRefRowId, TypeRefRow);
 simple_index!(TypeSpecRowId, TypeSpecRow);
@@ -253,6 +404,39 @@ coded_index! {
   }
 }
 
+coded_index! {
+  /// The parent of a portable PDB `CustomDebugInformation` row.
+  pub enum HasCustomDebugInformation : 5 {
+    MethodDef(MethodDefRow::MethodDefRowId) = 0,
+    Field(FieldRow::FieldRowId) = 1,
+    TypeRef(TypeRefRow::TypeRefRowId) = 2,
+    TypeDef(TypeDefRow::TypeDefRowId) = 3,
+    Param(ParamRow::ParamRowId) = 4,
+    InterfaceImpl(InterfaceImplRow::InterfaceImplRowId) = 5,
+    MemberRef(MemberRefRow::MemberRefRowId) = 6,
+    Module(ModuleRow::ModuleRowId) = 7,
+    DeclSecurity(DeclSecurityRow::DeclSecurityRowId) = 8,
+    Property(PropertyRow::PropertyRowId) = 9,
+    Event(EventRow::EventRowId) = 10,
+    StandAloneSig(StandAloneSigRow::StandAloneSigRowId) = 11,
+    ModuleRef(ModuleRefRow::ModuleRefRowId) = 12,
+    TypeSpec(TypeSpecRow::TypeSpecRowId) = 13,
+    Assembly(AssemblyRow::AssemblyRowId) = 14,
+    AssemblyRef(AssemblyRefRow::AssemblyRefRowId) = 15,
+    File(FileRow::FileRowId) = 16,
+    ExportedType(ExportedTypeRow::ExportedTypeRowId) = 17,
+    ManifestResource(ManifestResourceRow::ManifestResourceRowId) = 18,
+    GenericParam(GenericParamRow::GenericParamRowId) = 19,
+    GenericParamConstraint(GenericParamConstraintRow::GenericParamConstraintRowId) = 20,
+    MethodSpec(MethodSpecRow::MethodSpecRowId) = 21,
+    Document(DocumentRow::DocumentRowId) = 22,
+    LocalScope(LocalScopeRow::LocalScopeRowId) = 23,
+    LocalVariable(LocalVariableRow::LocalVariableRowId) = 24,
+    LocalConstant(LocalConstantRow::LocalConstantRowId) = 25,
+    ImportScope(ImportScopeRow::ImportScopeRowId) = 26
+  }
+}
+
 coded_index! {
   pub enum HasFieldMarshal : 1 {
     Field(FieldRow::FieldRowId) = 0,
@@ -332,8 +516,67 @@ coded_index! {
 
 #[cfg(test)]
 mod tests {
-  use super::ResolutionScope;
-  use crate::ecma335::tables::{AssemblyRefRowId, ModuleRefRowId, ModuleRowId, TypeRefRowId};
+  use super::{ResolutionScope, RowIndex, TypeDefOrRef};
+  use crate::ecma335::tables::{
+    AssemblyRefRowId, ModuleRefRowId, ModuleRowId, Row, TablesHeader, TypeDefRow, TypeDefRowId,
+    TypeRefRowId,
+  };
+  use scroll::Pread;
+
+  #[test]
+  fn simple_index_is_one_based() {
+    let header = TablesHeader::with_rows(&[(TypeDefRow::ID, 3)]);
+
+    assert!(TypeDefRowId::new(0, &header).is_err());
+    assert!(TypeDefRowId::new(4, &header).is_err());
+
+    let first = TypeDefRowId::first(&header).unwrap();
+    assert_eq!(first.row(), 1);
+    assert_eq!(first.offset(), Some(0));
+
+    let last = first.next().and_then(RowIndex::next).unwrap();
+    assert_eq!(last.row(), 3);
+    assert_eq!(last.offset(), Some(2));
+    assert_eq!(last.next(), None);
+
+    assert_eq!(unsafe { TypeDefRowId::new_unchecked(0) }.offset(), None);
+  }
+
+  #[test]
+  fn nullable_index() {
+    let header = TablesHeader::with_rows(&[(TypeDefRow::ID, 3)]);
+
+    assert_eq!(
+      [0u8, 0]
+        .pread_with::<Option<TypeDefRowId>>(0, header)
+        .unwrap(),
+      None
+    );
+    assert!([0u8, 0].pread_with::<TypeDefRowId>(0, header).is_err());
+    assert_eq!(
+      [2u8, 0]
+        .pread_with::<Option<TypeDefRowId>>(0, header)
+        .unwrap(),
+      Some(TypeDefRowId::new(2, &header).unwrap())
+    );
+
+    // `<Module>` extends nothing, encoded as a `TypeDef` tag with a null row.
+    assert_eq!(
+      [0u8, 0]
+        .pread_with::<Option<TypeDefOrRef>>(0, header)
+        .unwrap(),
+      None
+    );
+    assert!([0u8, 0].pread_with::<TypeDefOrRef>(0, header).is_err());
+    assert_eq!(
+      [3u8 << 2, 0]
+        .pread_with::<Option<TypeDefOrRef>>(0, header)
+        .unwrap(),
+      Some(TypeDefOrRef::TypeDef(
+        TypeDefRowId::new(3, &header).unwrap()
+      ))
+    );
+  }
 
   #[test]
   fn new_resolution_scope() {
@@ -0,0 +1,477 @@
+//! Run-length row lists (II.22).
+//!
+//! `TypeDef`, `MethodDef`, `EventMap` and `PropertyMap` own runs of rows in another table through
+//! a list column holding the first row of the run.  A run ends where the next row's list starts,
+//! or at the end of the table for the last row, so an empty run starts at the same row as the next
+//! one and may start one past the last row of the table.
+//!
+//! Portable PDB `LocalScope` rows own runs of `LocalVariable` and `LocalConstant` rows the same
+//! way.
+//!
+//! Unoptimized metadata can have an indirection table, e.g. `FieldPtr`, in which case the list
+//! columns index the indirection table and its rows hold the ids of the owned rows, which allows
+//! ENC to add members to a type without moving every row after it.
+
+use super::{index::*, rows::*, Row, TableRowReader, Tables, TablesHeader};
+use alloc::{vec, vec::Vec};
+use anyhow::{bail, Error, Result};
+use scroll::{
+  ctx::{SizeWith, TryFromCtx},
+  Pread, LE,
+};
+
+macro_rules! list_index {
+  ($(#[$attr:meta])* $name:ident, $index:ident, $row:ident $(, $ptr:ident)?) => {
+    $(#[$attr])*
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct $name(u32);
+
+    impl $name {
+      /// Gets the 1-based row id the list starts at, one past the last row for an empty list at
+      /// the end of the table.  The row is in the indirection table if it's present.
+      pub fn start(&self) -> u32 {
+        self.0
+      }
+    }
+
+    impl<'a> TryFromCtx<'a, TablesHeader> for $name {
+      type Error = Error;
+
+      fn try_from_ctx(from: &'a [u8], ctx: TablesHeader) -> Result<(Self, usize)> {
+        let offset = &mut 0;
+        let row = match $name::size_with(&ctx) {
+          4 => from.gread_with::<u32>(offset, LE)?,
+          2 => from.gread_with::<u16>(offset, LE)?.into(),
+          _ => panic!("Invalid size"),
+        };
+
+        let rows = ctx.rows[$row::ID];
+        $(
+          let rows = match ctx.rows[$ptr::ID] {
+            0 => rows,
+            ptrs => ptrs,
+          };
+        )?
+
+        match row {
+          0 => bail!("`{}` is null", stringify!($name)),
+          _ if row <= rows + 1 || ctx.minimal_delta => Ok((Self(row), *offset)),
+          _ => bail!(
+            "`{}`({}) too large, expected at most {}",
+            stringify!($name),
+            row,
+            rows + 1
+          ),
+        }
+      }
+    }
+
+    impl SizeWith<TablesHeader> for $name {
+      /// The column indexes the indirection table if it's present, so it's 4 bytes wide if either
+      /// table is large.
+      fn size_with(header: &TablesHeader) -> usize {
+        $(
+          if header.rows[$ptr::ID] >= 1 << 16 {
+            return 4;
+          }
+        )?
+
+        $index::size_with(header)
+      }
+    }
+  };
+}
+
+list_index!(
+  /// The first of the fields owned by a type.
+  FieldList,
+  FieldRowId,
+  FieldRow,
+  FieldPtrRow
+);
+list_index!(
+  /// The first of the methods owned by a type.
+  MethodList,
+  MethodDefRowId,
+  MethodDefRow,
+  MethodPtrRow
+);
+list_index!(
+  /// The first of the parameters owned by a method.
+  ParamList,
+  ParamRowId,
+  ParamRow,
+  ParamPtrRow
+);
+list_index!(
+  /// The first of the events owned by an event map.
+  EventList,
+  EventRowId,
+  EventRow,
+  EventPtrRow
+);
+list_index!(
+  /// The first of the properties owned by a property map.
+  PropertyList,
+  PropertyRowId,
+  PropertyRow,
+  PropertyPtrRow
+);
+list_index!(
+  /// The first of the local variables of a portable PDB local scope.
+  VariableList,
+  LocalVariableRowId,
+  LocalVariableRow
+);
+list_index!(
+  /// The first of the local constants of a portable PDB local scope.
+  ConstantList,
+  LocalConstantRowId,
+  LocalConstantRow
+);
+
+/// Iterates over the ids of a run of rows, e.g. the fields of a type.
+#[derive(Debug, Clone)]
+pub struct RowRange<I>(Rows<I>);
+
+#[derive(Debug, Clone)]
+enum Rows<I> {
+  /// Consecutive rows up to the zero-based offset `end`.
+  Direct { next: Option<I>, end: usize },
+  /// Rows read from an indirection table.
+  Indirect(vec::IntoIter<I>),
+}
+
+impl<I: RowIndex + Copy> RowRange<I> {
+  /// Creates a range over the 1-based rows `start..end`, failing if the range is out of bounds.
+  pub fn new(start: u32, end: u32, header: &TablesHeader) -> Result<Self> {
+    if start == 0 {
+      bail!("Row list {}..{} starts at a null row", start, end);
+    }
+
+    if start > end {
+      bail!("Row list {}..{} runs backwards", start, end);
+    }
+
+    let next = match start < end {
+      true => {
+        I::from_row(end - 1, header)?;
+        Some(I::from_row(start, header)?)
+      }
+      false => None,
+    };
+
+    Ok(Self(Rows::Direct {
+      next,
+      end: end as usize - 1,
+    }))
+  }
+
+  /// Creates an empty range.
+  pub fn empty() -> Self {
+    Self(Rows::Direct { next: None, end: 0 })
+  }
+}
+
+impl<I: RowIndex + Copy> Iterator for RowRange<I> {
+  type Item = I;
+
+  fn next(&mut self) -> Option<I> {
+    match &mut self.0 {
+      Rows::Direct { next, end } => {
+        let end = *end;
+        let index = next.filter(|index| index.offset().is_some_and(|offset| offset < end))?;
+
+        *next = index.next();
+        Some(index)
+      }
+      Rows::Indirect(ids) => ids.next(),
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = match &self.0 {
+      Rows::Direct { next, end } => match next.and_then(RowIndex::offset) {
+        Some(offset) => end.saturating_sub(offset),
+        None => 0,
+      },
+      Rows::Indirect(ids) => ids.len(),
+    };
+
+    (len, Some(len))
+  }
+}
+
+impl<I: RowIndex + Copy> ExactSizeIterator for RowRange<I> {}
+
+impl<'a> Tables<'a> {
+  /// Gets the fields of a type.
+  pub fn type_def_fields(&self, id: TypeDefRowId) -> Result<RowRange<FieldRowId>> {
+    let type_defs = self.type_defs();
+    let (start, end) = match id.next() {
+      Some(next) => (
+        type_defs.read(id)?.field_list.start(),
+        type_defs.read(next)?.field_list.start(),
+      ),
+      None => (type_defs.read(id)?.field_list.start(), 0),
+    };
+
+    self.list(start, end, self.fields().len(), self.field_ptrs(), |ptr| {
+      ptr.field
+    })
+  }
+
+  /// Gets the methods of a type.
+  pub fn type_def_methods(&self, id: TypeDefRowId) -> Result<RowRange<MethodDefRowId>> {
+    let type_defs = self.type_defs();
+    let (start, end) = match id.next() {
+      Some(next) => (
+        type_defs.read(id)?.method_list.start(),
+        type_defs.read(next)?.method_list.start(),
+      ),
+      None => (type_defs.read(id)?.method_list.start(), 0),
+    };
+
+    self.list(
+      start,
+      end,
+      self.method_defs().len(),
+      self.method_ptrs(),
+      |ptr| ptr.method,
+    )
+  }
+
+  /// Gets the parameter rows of a method, which can be fewer than its signature's parameters and
+  /// include the return value as sequence 0.
+  pub fn method_def_params(&self, id: MethodDefRowId) -> Result<RowRange<ParamRowId>> {
+    let method_defs = self.method_defs();
+    let (start, end) = match id.next() {
+      Some(next) => (
+        method_defs.read(id)?.param_list.start(),
+        method_defs.read(next)?.param_list.start(),
+      ),
+      None => (method_defs.read(id)?.param_list.start(), 0),
+    };
+
+    self.list(start, end, self.params().len(), self.param_ptrs(), |ptr| {
+      ptr.param
+    })
+  }
+
+  /// Gets the events of an event map.
+  pub fn event_map_events(&self, id: EventMapRowId) -> Result<RowRange<EventRowId>> {
+    let event_maps = self.event_maps();
+    let (start, end) = match id.next() {
+      Some(next) => (
+        event_maps.read(id)?.event_list.start(),
+        event_maps.read(next)?.event_list.start(),
+      ),
+      None => (event_maps.read(id)?.event_list.start(), 0),
+    };
+
+    self.list(start, end, self.events().len(), self.event_ptrs(), |ptr| {
+      ptr.event
+    })
+  }
+
+  /// Gets the properties of a property map.
+  pub fn property_map_properties(&self, id: PropertyMapRowId) -> Result<RowRange<PropertyRowId>> {
+    let property_maps = self.property_maps();
+    let (start, end) = match id.next() {
+      Some(next) => (
+        property_maps.read(id)?.property_list.start(),
+        property_maps.read(next)?.property_list.start(),
+      ),
+      None => (property_maps.read(id)?.property_list.start(), 0),
+    };
+
+    self.list(
+      start,
+      end,
+      self.properties().len(),
+      self.property_ptrs(),
+      |ptr| ptr.property,
+    )
+  }
+
+  /// Gets the local variables of a scope.
+  pub fn local_scope_variables(&self, id: LocalScopeRowId) -> Result<RowRange<LocalVariableRowId>> {
+    let local_scopes = self.local_scopes();
+    let start = local_scopes.read(id)?.variable_list.start();
+    let end = match id.next() {
+      Some(next) => local_scopes.read(next)?.variable_list.start(),
+      None => self.local_variables().len() as u32 + 1,
+    };
+
+    RowRange::new(start, end, self.header())
+  }
+
+  /// Gets the local constants of a scope.
+  pub fn local_scope_constants(&self, id: LocalScopeRowId) -> Result<RowRange<LocalConstantRowId>> {
+    let local_scopes = self.local_scopes();
+    let start = local_scopes.read(id)?.constant_list.start();
+    let end = match id.next() {
+      Some(next) => local_scopes.read(next)?.constant_list.start(),
+      None => self.local_constants().len() as u32 + 1,
+    };
+
+    RowRange::new(start, end, self.header())
+  }
+
+  /// Gets the rows of a list running from `start` to `end`, or to the end of the table if `end` is
+  /// 0, through the indirection table if it's present.
+  fn list<'t, I, P>(
+    &'t self,
+    start: u32,
+    end: u32,
+    rows: usize,
+    ptrs: TableRowReader<'a, 't, P>,
+    deref: impl Fn(P) -> I,
+  ) -> Result<RowRange<I>>
+  where
+    I: RowIndex + Copy,
+    P: Row<'a>,
+  {
+    if ptrs.is_empty() {
+      let end = match end {
+        0 => rows as u32 + 1,
+        end => end,
+      };
+
+      return RowRange::new(start, end, self.header());
+    }
+
+    let end = match end {
+      0 => ptrs.len() as u32 + 1,
+      end => end,
+    };
+
+    let ids = RowRange::<P::Index>::new(start, end, self.header())?
+      .map(|ptr| ptrs.read(ptr).map(&deref))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(RowRange(Rows::Indirect(ids.into_iter())))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{FieldList, RowRange};
+  use crate::ecma335::tables::{
+    FieldPtrRow, FieldRow, FieldRowId, Row, Tables, TablesHeader, TypeDefRow, TypeDefRowId,
+  };
+  use alloc::vec::Vec;
+  use scroll::{ctx::SizeWith, Pread};
+
+  #[test]
+  fn list_may_start_past_the_end() {
+    let header = TablesHeader::with_rows(&[(FieldRow::ID, 3)]);
+
+    assert_eq!(
+      [1u8, 0].pread_with::<FieldList>(0, header).unwrap().start(),
+      1
+    );
+    assert_eq!(
+      [4u8, 0].pread_with::<FieldList>(0, header).unwrap().start(),
+      4
+    );
+    assert!([5u8, 0].pread_with::<FieldList>(0, header).is_err());
+    assert!([0u8, 0].pread_with::<FieldList>(0, header).is_err());
+  }
+
+  #[test]
+  fn row_ranges() {
+    let header = TablesHeader::with_rows(&[(FieldRow::ID, 3)]);
+    let rows = |start, end| {
+      RowRange::<FieldRowId>::new(start, end, &header)
+        .map(|range| range.map(|id| id.row()).collect::<Vec<_>>())
+    };
+
+    assert_eq!(rows(1, 4).unwrap(), [1, 2, 3]);
+    assert_eq!(rows(2, 3).unwrap(), [2]);
+    assert_eq!(rows(4, 4).unwrap(), []);
+    assert!(rows(3, 2).is_err());
+    assert!(rows(3, 5).is_err());
+    assert!(rows(0, 0).is_err());
+    assert!(rows(0, 2).is_err());
+
+    let mut range = RowRange::<FieldRowId>::new(1, 4, &header).unwrap();
+    assert_eq!(range.len(), 3);
+    range.next();
+    assert_eq!(range.len(), 2);
+    assert_eq!(RowRange::<FieldRowId>::empty().len(), 0);
+  }
+
+  #[test]
+  fn list_width_follows_indirection_table() {
+    let header = TablesHeader::with_rows(&[(FieldRow::ID, 3)]);
+    assert_eq!(FieldList::size_with(&header), 2);
+
+    // A large `FieldPtr` widens the field list even though `Field` is small.
+    let header = TablesHeader::with_rows(&[
+      (TypeDefRow::ID, 1),
+      (FieldPtrRow::ID, 0x10000),
+      (FieldRow::ID, 3),
+    ]);
+    assert_eq!(FieldList::size_with(&header), 4);
+    assert_eq!(FieldPtrRow::size_with(&header), 2);
+    assert_eq!(TypeDefRow::size_with(&header), 16);
+
+    let list = [1u8, 0, 1, 0].pread_with::<FieldList>(0, header).unwrap();
+    assert_eq!(list.start(), 0x10001);
+    assert!([2u8, 0, 1, 0].pread_with::<FieldList>(0, header).is_err());
+
+    #[rustfmt::skip]
+    let type_def = [
+      // flags, name, namespace, extends, field_list, method_list
+      0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0,
+    ];
+    let tables = Tables {
+      header,
+      type_defs: type_def.pread_with(0, header).unwrap(),
+      ..Default::default()
+    };
+    let id = TypeDefRowId::new(1, &header).unwrap();
+    let row = tables.type_defs().read(id).unwrap();
+    assert_eq!(row.field_list.start(), 2);
+    assert_eq!(row.method_list.start(), 1);
+  }
+
+  #[test]
+  fn lists_through_indirection_tables() {
+    let header =
+      TablesHeader::with_rows(&[(TypeDefRow::ID, 2), (FieldPtrRow::ID, 3), (FieldRow::ID, 3)]);
+
+    // The field list indexes the 3 rows of `FieldPtr` rather than `Field`.
+    let mut ptr_header = header;
+    ptr_header.rows[FieldRow::ID] = 1;
+    assert!([4u8, 0].pread_with::<FieldList>(0, ptr_header).is_ok());
+
+    #[rustfmt::skip]
+    let type_defs = [
+      // flags, name, namespace, extends, field_list, method_list
+      0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0,
+    ];
+    let field_ptrs = [3u8, 0, 1, 0, 2, 0];
+    let fields = [0u8; 18];
+
+    let tables = Tables {
+      header,
+      type_defs: type_defs.pread_with(0, header).unwrap(),
+      field_ptrs: field_ptrs.pread_with(0, header).unwrap(),
+      fields: fields.pread_with(0, header).unwrap(),
+      ..Default::default()
+    };
+    let fields = |row| {
+      let id = TypeDefRowId::new(row, &header).unwrap();
+      let range = tables.type_def_fields(id).unwrap();
+      assert_eq!(range.len(), range.clone().count());
+
+      range.map(|id| id.row()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(fields(1), [3, 1]);
+    assert_eq!(fields(2), [2]);
+  }
+}
@@ -1,5 +1,5 @@
-use super::{flags::*, index::*, TablesHeader};
-use crate::ecma335::{blobs::BlobIndex, guids::GuidIndex, strings::StringIndex};
+use super::{flags::*, index::*, lists::*, TablesHeader};
+use crate::ecma335::{blobs::BlobIndex, guids::GuidIndex, strings::StringIndex, token::Token};
 use anyhow::{Context, Result};
 use scroll::{ctx::SizeWith, Endian, Pread};
 
@@ -39,19 +39,19 @@ macro_rules! row {
       type Index = $index;
 
       fn parse(table_buf: &'a [u8], index: Self::Index, header: &TablesHeader) -> Result<Self> {
-        let row = index.row as usize;
+        let row = index
+          .offset()
+          .with_context(|| format!("Null `{}` row id", stringify!($name)))?;
         let row_size = Self::size_with(header);
         let offset = &mut (row * row_size);
 
-        Ok((
-          Self {
-            $(
-              $field: table_buf
-                .gread_with(offset, header.into())
-                .with_context(|| format!("`{}.{}`", stringify!($name), stringify!($field)))?,
-            )*
-          }
-        ))
+        Ok(Self {
+          $(
+            $field: table_buf
+              .gread_with(offset, header.into())
+              .with_context(|| format!("`{}.{}`", stringify!($name), stringify!($field)))?,
+          )*
+        })
       }
     }
 
@@ -83,7 +83,9 @@ row! {
 
 row! {
   pub struct TypeRefRow, TypeRefRowId : 0x01 {
-    resolution_scope: ResolutionScope,
+    /// The scope the type is defined in, `None` if the type is resolved through the
+    /// [ExportedTypeRow] table of the current assembly.
+    resolution_scope: Option<ResolutionScope>,
     name: StringIndex,
     namespace: StringIndex
   }
@@ -94,9 +96,24 @@ row! {
     flags: TypeAttributes,
     name: StringIndex,
     namespace: StringIndex,
-    extends: TypeDefOrRef,
-    field_list: FieldRowId, // FieldRowIdList,
-    method_list: MethodDefRowId // MethodRowIdList
+    /// The base type, `None` for interfaces and `System.Object` or `<Module>`.
+    extends: Option<TypeDefOrRef>,
+    /// The first field of the type, see [Tables::type_def_fields].
+    ///
+    /// [Tables::type_def_fields]: super::Tables::type_def_fields
+    field_list: FieldList,
+    /// The first method of the type, see [Tables::type_def_methods].
+    ///
+    /// [Tables::type_def_methods]: super::Tables::type_def_methods
+    method_list: MethodList
+  }
+}
+
+row! {
+  /// An indirection in unoptimized metadata, the `field_list` of types index this table instead
+  /// of [FieldRow] when it's present.
+  pub struct FieldPtrRow, FieldPtrRowId : 0x03 {
+    field: FieldRowId
   }
 }
 
@@ -108,6 +125,14 @@ row! {
   }
 }
 
+row! {
+  /// An indirection in unoptimized metadata, the `method_list` of types index this table instead
+  /// of [MethodDefRow] when it's present.
+  pub struct MethodPtrRow, MethodPtrRowId : 0x05 {
+    method: MethodDefRowId
+  }
+}
+
 row! {
   pub struct MethodDefRow, MethodDefRowId : 0x06 {
     rva: u32,
@@ -115,7 +140,10 @@ row! {
     flags: MethodAttributes,
     name: StringIndex,
     signature: BlobIndex,
-    param_list: ParamRowId // ParamRowIdList
+    /// The first parameter of the method, see [Tables::method_def_params].
+    ///
+    /// [Tables::method_def_params]: super::Tables::method_def_params
+    param_list: ParamList
   }
 }
 
@@ -213,7 +241,7 @@ row! {
 
 row! {
   pub struct DeclSecurityRow, DeclSecurityRowId : 0x0e {
-    action: u16,
+    action: SecurityAction,
     parent: HasDeclSecurity,
     permission_set: BlobIndex
   }
@@ -230,14 +258,43 @@ row! {
 row! {
   pub struct EventMapRow, EventMapRowId : 0x12 {
     parent: TypeDefRowId,
-    event_list: EventRowId // EventRowIdList
+    /// The first event of the type, see [Tables::event_map_events].
+    ///
+    /// [Tables::event_map_events]: super::Tables::event_map_events
+    event_list: EventList
+  }
+}
+
+row! {
+  /// An indirection in unoptimized metadata, the `event_list` of event maps index this table
+  /// instead of [EventRow] when it's present.
+  pub struct EventPtrRow, EventPtrRowId : 0x13 {
+    event: EventRowId
+  }
+}
+
+row! {
+  /// An edit made by an ENC delta, in the order the edits were made.
+  pub struct EncLogRow, EncLogRowId : 0x1e {
+    /// The edited row, or the parent of the added member for the `Add*` function codes.
+    token: Token,
+    func_code: EncFuncCode
+  }
+}
+
+row! {
+  /// A row held by an ENC delta, sorted by token.  The n-th entry of a table is the n-th row of
+  /// that table in the delta.
+  pub struct EncMapRow, EncMapRowId : 0x1f {
+    token: Token
   }
 }
 
 row! {
   pub struct ExportedTypeRow, ExportedTypeRowId : 0x27 {
     flags: TypeAttributes,
-    type_def_id: TypeDefRowId,
+    /// A hint to the row id of the type in the module that defines it, not in this module.
+    type_def_id: u32,
     type_name: StringIndex,
     type_namespace: StringIndex,
     implementation: Implementation
@@ -313,7 +370,8 @@ row! {
     offset: u32,
     flags: ManifestResourceAttributes,
     name: StringIndex,
-    implementation: Implementation
+    /// The file or assembly containing the resource, `None` if it's embedded in this file.
+    implementation: Option<Implementation>
   }
 }
 
@@ -370,6 +428,14 @@ row! {
   }
 }
 
+row! {
+  /// An indirection in unoptimized metadata, the `param_list` of methods index this table instead
+  /// of [ParamRow] when it's present.
+  pub struct ParamPtrRow, ParamPtrRowId : 0x07 {
+    param: ParamRowId
+  }
+}
+
 row! {
   pub struct PropertyRow, PropertyRowId : 0x17 {
     flags: PropertyAttributes,
@@ -381,7 +447,18 @@ row! {
 row! {
   pub struct PropertyMapRow, PropertyMapRowId : 0x15 {
     parent: TypeDefRowId,
-    property_list: PropertyRowId // PropertyRowIdList
+    /// The first property of the type, see [Tables::property_map_properties].
+    ///
+    /// [Tables::property_map_properties]: super::Tables::property_map_properties
+    property_list: PropertyList
+  }
+}
+
+row! {
+  /// An indirection in unoptimized metadata, the `property_list` of property maps index this table
+  /// instead of [PropertyRow] when it's present.
+  pub struct PropertyPtrRow, PropertyPtrRowId : 0x16 {
+    property: PropertyRowId
   }
 }
 
@@ -397,6 +474,89 @@ row! {
   }
 }
 
+row! {
+  /// A source document of a portable PDB.
+  pub struct DocumentRow, DocumentRowId : 0x30 {
+    /// The document name blob, see [Md::document_name](crate::ecma335::Md::document_name).
+    name: BlobIndex,
+    hash_algorithm: GuidIndex,
+    hash: BlobIndex,
+    language: GuidIndex
+  }
+}
+
+row! {
+  /// The sequence points of a method, the table has a row for every `MethodDef` row.
+  pub struct MethodDebugInformationRow, MethodDebugInformationRowId : 0x31 {
+    /// The document of the sequence points, `None` if they span documents or there are none.
+    document: Option<DocumentRowId>,
+    /// The sequence points blob, see
+    /// [Md::sequence_points](crate::ecma335::Md::sequence_points).
+    sequence_points: BlobIndex
+  }
+}
+
+row! {
+  /// A lexical scope of a method's local variables and constants, sorted by method then by start
+  /// offset, with enclosing scopes before the scopes they contain.
+  pub struct LocalScopeRow, LocalScopeRowId : 0x32 {
+    method: MethodDefRowId,
+    import_scope: ImportScopeRowId,
+    /// The first variable of the scope, see [Tables::local_scope_variables].
+    ///
+    /// [Tables::local_scope_variables]: super::Tables::local_scope_variables
+    variable_list: VariableList,
+    /// The first constant of the scope, see [Tables::local_scope_constants].
+    ///
+    /// [Tables::local_scope_constants]: super::Tables::local_scope_constants
+    constant_list: ConstantList,
+    start_offset: u32,
+    length: u32
+  }
+}
+
+row! {
+  pub struct LocalVariableRow, LocalVariableRowId : 0x33 {
+    attributes: LocalVariableAttributes,
+    /// The slot of the variable in the method's local signature.
+    index: u16,
+    name: StringIndex
+  }
+}
+
+row! {
+  pub struct LocalConstantRow, LocalConstantRowId : 0x34 {
+    name: StringIndex,
+    signature: BlobIndex
+  }
+}
+
+row! {
+  /// The namespaces and types imported by a scope, e.g. C# `using` directives.
+  pub struct ImportScopeRow, ImportScopeRowId : 0x35 {
+    parent: Option<ImportScopeRowId>,
+    imports: BlobIndex
+  }
+}
+
+row! {
+  /// Maps the `MoveNext` method of an async or iterator state machine to the method it was
+  /// generated from.
+  pub struct StateMachineMethodRow, StateMachineMethodRowId : 0x36 {
+    move_next_method: MethodDefRowId,
+    kickoff_method: MethodDefRowId
+  }
+}
+
+row! {
+  /// Custom debug information, a blob of a kind identified by a guid.
+  pub struct CustomDebugInformationRow, CustomDebugInformationRowId : 0x37 {
+    parent: HasCustomDebugInformation,
+    kind: GuidIndex,
+    value: BlobIndex
+  }
+}
+
 impl From<&TablesHeader> for () {
   fn from(_: &TablesHeader) -> Self {}
 }
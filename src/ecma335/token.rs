@@ -0,0 +1,268 @@
+//! ECMA-335 metadata tokens (II.22, III.1.9).
+//!
+//! A token is a table id in the high byte and a 1-based row id in the low three bytes.  Tokens
+//! appear as IL operands, in method headers and in the CLI header's entry point, and `ldstr` uses
+//! the pseudo table id `0x70` with a `#US` heap offset in place of a row.
+
+use super::{
+  tables::*,
+  user_strings::{UserString, UserStringIndex},
+  Md,
+};
+use anyhow::{bail, Error, Result};
+use core::fmt;
+use scroll::{
+  ctx::{SizeWith, TryFromCtx},
+  Pread, LE,
+};
+
+/// A metadata token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(u32);
+
+impl Token {
+  /// The table id of `#US` string tokens.
+  pub const USER_STRING: u8 = 0x70;
+
+  /// Creates a token from a table id and a row, failing if the row doesn't fit in 24 bits.
+  pub fn new(table: u8, row: u32) -> Result<Self> {
+    match row <= 0x00ff_ffff {
+      true => Ok(Self(((table as u32) << 24) | row)),
+      false => bail!("Row {:#x} of table {:#x} too large for a token", row, table),
+    }
+  }
+
+  /// Gets the table id, or [Token::USER_STRING] for `#US` string tokens.
+  pub fn table(&self) -> u8 {
+    (self.0 >> 24) as u8
+  }
+
+  /// Gets the 1-based row id, or the `#US` heap offset for string tokens.
+  pub fn row(&self) -> u32 {
+    self.0 & 0x00ff_ffff
+  }
+
+  /// Determines if the token doesn't refer to any row, e.g. a method without an entry point.
+  pub fn is_null(&self) -> bool {
+    self.row() == 0 && self.table() != Self::USER_STRING
+  }
+
+  /// Gets the encoded token.
+  pub fn value(&self) -> u32 {
+    self.0
+  }
+}
+
+impl From<u32> for Token {
+  fn from(value: u32) -> Self {
+    Self(value)
+  }
+}
+
+impl From<Token> for u32 {
+  fn from(token: Token) -> Self {
+    token.0
+  }
+}
+
+impl From<UserStringIndex> for Token {
+  fn from(index: UserStringIndex) -> Self {
+    Self(index.token())
+  }
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:#010x}", self.0)
+  }
+}
+
+/// Tokens are stored in full in the `ENCLog` and `ENCMap` tables.
+impl TryFromCtx<'_> for Token {
+  type Error = Error;
+
+  fn try_from_ctx(from: &[u8], _: ()) -> Result<(Self, usize)> {
+    Ok((Self(from.pread_with(0, LE)?), 4))
+  }
+}
+
+impl SizeWith<()> for Token {
+  fn size_with(_: &()) -> usize {
+    4
+  }
+}
+
+/// The row or user string a [Token] refers to.
+#[derive(Debug)]
+pub enum TokenRow<'a> {
+  Module(ModuleRowId, ModuleRow),
+  TypeRef(TypeRefRowId, TypeRefRow),
+  TypeDef(TypeDefRowId, TypeDefRow),
+  Field(FieldRowId, FieldRow),
+  MethodDef(MethodDefRowId, MethodDefRow),
+  Param(ParamRowId, ParamRow),
+  InterfaceImpl(InterfaceImplRowId, InterfaceImplRow),
+  MemberRef(MemberRefRowId, MemberRefRow),
+  Constant(ConstantRowId, ConstantRow),
+  CustomAttribute(CustomAttributeRowId, CustomAttributeRow),
+  FieldMarshal(FieldMarshalRowId, FieldMarshalRow),
+  DeclSecurity(DeclSecurityRowId, DeclSecurityRow),
+  ClassLayout(ClassLayoutRowId, ClassLayoutRow),
+  FieldLayout(FieldLayoutRowId, FieldLayoutRow),
+  StandAloneSig(StandAloneSigRowId, StandAloneSigRow),
+  EventMap(EventMapRowId, EventMapRow),
+  Event(EventRowId, EventRow),
+  PropertyMap(PropertyMapRowId, PropertyMapRow),
+  Property(PropertyRowId, PropertyRow),
+  MethodSemantics(MethodSemanticsRowId, MethodSemanticsRow),
+  MethodImpl(MethodImplRowId, MethodImplRow),
+  ModuleRef(ModuleRefRowId, ModuleRefRow),
+  TypeSpec(TypeSpecRowId, TypeSpecRow),
+  ImplMap(ImplMapRowId, ImplMapRow),
+  FieldRva(FieldRvaRowId, FieldRvaRow),
+  Assembly(AssemblyRowId, AssemblyRow),
+  AssemblyProcessor(AssemblyProcessorRowId, AssemblyProcessorRow),
+  AssemblyOs(AssemblyOsRowId, AssemblyOsRow),
+  AssemblyRef(AssemblyRefRowId, AssemblyRefRow),
+  AssemblyRefProcessor(AssemblyRefProcessorRowId, AssemblyRefProcessorRow),
+  AssemblyRefOs(AssemblyRefOsRowId, AssemblyRefOsRow),
+  File(FileRowId, FileRow),
+  ExportedType(ExportedTypeRowId, ExportedTypeRow),
+  ManifestResource(ManifestResourceRowId, ManifestResourceRow),
+  NestedClass(NestedClassRowId, NestedClassRow),
+  GenericParam(GenericParamRowId, GenericParamRow),
+  MethodSpec(MethodSpecRowId, MethodSpecRow),
+  GenericParamConstraint(GenericParamConstraintRowId, GenericParamConstraintRow),
+  /// An `ldstr` operand.
+  UserString(UserStringIndex, UserString<'a>),
+}
+
+impl<'a> Md<'a> {
+  /// Resolves a token to the row or user string it refers to.
+  pub fn resolve_token(&self, token: Token) -> Result<TokenRow<'a>> {
+    let tables = self.tables();
+    let header = tables.header();
+
+    macro_rules! resolve {
+      ($($variant:ident($row:ident, $index:ident, $rows:ident)),* $(,)?) => {
+        match token.table() as usize {
+          $(
+            $row::ID => {
+              let id = $index::from_token(token, header)?;
+              Ok(TokenRow::$variant(id, tables.$rows().read(id)?))
+            }
+          )*
+          _ if token.table() == Token::USER_STRING => {
+            let index = UserStringIndex::from_token(token.value())?;
+            Ok(TokenRow::UserString(index, self.user_strings().get(index)?))
+          }
+          _ => bail!("Unknown table {:#x} in token {}", token.table(), token),
+        }
+      };
+    }
+
+    resolve! {
+      Module(ModuleRow, ModuleRowId, modules),
+      TypeRef(TypeRefRow, TypeRefRowId, type_refs),
+      TypeDef(TypeDefRow, TypeDefRowId, type_defs),
+      Field(FieldRow, FieldRowId, fields),
+      MethodDef(MethodDefRow, MethodDefRowId, method_defs),
+      Param(ParamRow, ParamRowId, params),
+      InterfaceImpl(InterfaceImplRow, InterfaceImplRowId, interface_impls),
+      MemberRef(MemberRefRow, MemberRefRowId, member_refs),
+      Constant(ConstantRow, ConstantRowId, constants),
+      CustomAttribute(CustomAttributeRow, CustomAttributeRowId, custom_attributes),
+      FieldMarshal(FieldMarshalRow, FieldMarshalRowId, field_marshals),
+      DeclSecurity(DeclSecurityRow, DeclSecurityRowId, decl_securities),
+      ClassLayout(ClassLayoutRow, ClassLayoutRowId, class_layouts),
+      FieldLayout(FieldLayoutRow, FieldLayoutRowId, field_layouts),
+      StandAloneSig(StandAloneSigRow, StandAloneSigRowId, stand_alone_sigs),
+      EventMap(EventMapRow, EventMapRowId, event_maps),
+      Event(EventRow, EventRowId, events),
+      PropertyMap(PropertyMapRow, PropertyMapRowId, property_maps),
+      Property(PropertyRow, PropertyRowId, properties),
+      MethodSemantics(MethodSemanticsRow, MethodSemanticsRowId, method_semantics),
+      MethodImpl(MethodImplRow, MethodImplRowId, method_impls),
+      ModuleRef(ModuleRefRow, ModuleRefRowId, module_refs),
+      TypeSpec(TypeSpecRow, TypeSpecRowId, type_specs),
+      ImplMap(ImplMapRow, ImplMapRowId, impl_maps),
+      FieldRva(FieldRvaRow, FieldRvaRowId, field_rvas),
+      Assembly(AssemblyRow, AssemblyRowId, assemblies),
+      AssemblyProcessor(AssemblyProcessorRow, AssemblyProcessorRowId, assembly_processors),
+      AssemblyOs(AssemblyOsRow, AssemblyOsRowId, assembly_oses),
+      AssemblyRef(AssemblyRefRow, AssemblyRefRowId, assembly_refs),
+      AssemblyRefProcessor(
+        AssemblyRefProcessorRow,
+        AssemblyRefProcessorRowId,
+        assembly_ref_processors
+      ),
+      AssemblyRefOs(AssemblyRefOsRow, AssemblyRefOsRowId, assembly_ref_oses),
+      File(FileRow, FileRowId, files),
+      ExportedType(ExportedTypeRow, ExportedTypeRowId, exported_types),
+      ManifestResource(ManifestResourceRow, ManifestResourceRowId, manifest_resources),
+      NestedClass(NestedClassRow, NestedClassRowId, nested_classes),
+      GenericParam(GenericParamRow, GenericParamRowId, generic_params),
+      MethodSpec(MethodSpecRow, MethodSpecRowId, method_specs),
+      GenericParamConstraint(
+        GenericParamConstraintRow,
+        GenericParamConstraintRowId,
+        generic_param_constraints
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Token;
+  use crate::ecma335::{
+    tables::{
+      HasCustomAttribute, MemberRefRowId, Row, TablesHeader, TypeDefOrRef, TypeDefRow,
+      TypeDefRowId, TypeSpecRow,
+    },
+    user_strings::UserStringIndex,
+  };
+
+  #[test]
+  fn token_parts() {
+    let token = Token::from(0x0600_002a);
+    assert_eq!(token.table(), 0x06);
+    assert_eq!(token.row(), 0x2a);
+    assert_eq!(u32::from(token), 0x0600_002a);
+    assert_eq!(Token::new(0x06, 0x2a).unwrap(), token);
+    assert!(Token::new(0x06, 0x0100_0000).is_err());
+
+    assert!(Token::from(0x0600_0000).is_null());
+    assert!(!Token::from(0x7000_0000).is_null());
+    assert_eq!(
+      Token::from(UserStringIndex::from_token(0x7000_0010).unwrap()),
+      Token::from(0x7000_0010)
+    );
+  }
+
+  #[test]
+  fn row_id_tokens() {
+    let header = TablesHeader::with_rows(&[(TypeDefRow::ID, 3), (TypeSpecRow::ID, 1)]);
+    let id = TypeDefRowId::new(3, &header).unwrap();
+
+    assert_eq!(id.token(), Token::from(0x0200_0003));
+    assert_eq!(TypeDefRowId::from_token(id.into(), &header).unwrap(), id);
+    assert!(TypeDefRowId::from_token(0x0200_0004.into(), &header).is_err());
+    assert!(TypeDefRowId::from_token(0x0100_0001.into(), &header).is_err());
+    assert!(MemberRefRowId::from_token(0x0a00_0001.into(), &header).is_err());
+  }
+
+  #[test]
+  fn coded_index_tokens() {
+    let header = TablesHeader::with_rows(&[(TypeDefRow::ID, 3), (TypeSpecRow::ID, 1)]);
+    let ty = TypeDefOrRef::from_token(0x1b00_0001.into(), &header).unwrap();
+
+    assert!(matches!(ty, TypeDefOrRef::TypeSpec(id) if id.row() == 1));
+    assert_eq!(Token::from(ty), Token::from(0x1b00_0001));
+    assert!(TypeDefOrRef::from_token(0x0600_0001.into(), &header).is_err());
+
+    let attributed = HasCustomAttribute::from_token(0x0200_0002.into(), &header).unwrap();
+    assert!(matches!(attributed, HasCustomAttribute::TypeDef(id) if id.row() == 2));
+    assert_eq!(attributed.token(), Token::from(0x0200_0002));
+  }
+}
@@ -0,0 +1,195 @@
+//! ECMA-335 Metadata `#US` stream data.
+
+use super::{blobs::read_blob, StreamHeader};
+use alloc::string::String;
+use anyhow::{bail, Context, Result};
+use core::{char::decode_utf16, fmt};
+
+/// The metadata token table id used by `ldstr` to reference the `#US` stream.
+const USER_STRING_TOKEN_TYPE: u32 = 0x70;
+
+/// Contains the string literals in the `#US` stream.
+///
+/// User strings are indexable by their offset into the stream data, which is the row part of the
+/// `ldstr` token.  Each entry is prefixed with its length in bytes stored as a compressed unsigned
+/// integer, followed by the UTF-16LE characters and a trailing byte indicating whether any of the
+/// characters require special handling.
+#[derive(Default)]
+pub struct UserStrings<'a>(&'a [u8]);
+
+impl<'a> UserStrings<'a> {
+  /// Creates an instance of [UserStrings] from the given [StreamHeader].
+  ///
+  /// # Note
+  /// The stream header must be for the `#US` stream and isn't verified here in release builds.
+  pub fn parse_from_header(header: StreamHeader<'a>) -> Result<Self> {
+    debug_assert!(matches!(header.name.to_bytes(), b"#US"));
+
+    Ok(Self(header.data()?))
+  }
+
+  /// Gets the user string at the given [UserStringIndex].
+  pub fn get(&self, index: UserStringIndex) -> Result<UserString<'a>> {
+    if index.0 == 0 && self.0.is_empty() {
+      return Ok(UserString::default());
+    }
+
+    if index.0 >= self.0.len() {
+      bail!(
+        "User string index {:#x} out of range, heap size is {:#x}",
+        index.0,
+        self.0.len()
+      );
+    }
+
+    let mut offset = index.0;
+
+    read_user_string(self.0, &mut offset).with_context(|| format!("User string at {:#x}", index.0))
+  }
+
+  /// Gets the size of the heap in bytes.
+  pub fn size(&self) -> usize {
+    self.0.len()
+  }
+
+  /// Creates an iterator over every user string in the heap along with its [UserStringIndex].
+  pub fn iter(&self) -> UserStringIterator<'a> {
+    UserStringIterator {
+      buf: self.0,
+      offset: 0,
+    }
+  }
+}
+
+impl<'a> IntoIterator for &UserStrings<'a> {
+  type Item = Result<(UserStringIndex, UserString<'a>)>;
+  type IntoIter = UserStringIterator<'a>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+/// Iterates over the user strings in the `#US` stream.
+pub struct UserStringIterator<'a> {
+  buf: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Iterator for UserStringIterator<'a> {
+  type Item = Result<(UserStringIndex, UserString<'a>)>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.offset >= self.buf.len() {
+      return None;
+    }
+
+    let index = UserStringIndex(self.offset);
+    match read_user_string(self.buf, &mut self.offset) {
+      Ok(string) => Some(Ok((index, string))),
+      Err(err) => {
+        // The remaining heap can't be walked without a valid length.
+        self.offset = self.buf.len();
+        Some(Err(err.context(format!("User string at {:#x}", index.0))))
+      }
+    }
+  }
+}
+
+/// Reads a length prefixed user string at the given offset.
+fn read_user_string<'a>(buf: &'a [u8], offset: &mut usize) -> Result<UserString<'a>> {
+  let blob = read_blob(buf, offset)?;
+
+  match blob.split_last() {
+    None => Ok(UserString::default()),
+    Some((&special, data)) if blob.len() % 2 == 1 => Ok(UserString {
+      data,
+      has_special_chars: special != 0,
+    }),
+    Some(_) => bail!("Malformed user string, even length {:#x}", blob.len()),
+  }
+}
+
+/// A string literal from the `#US` stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UserString<'a> {
+  data: &'a [u8],
+  has_special_chars: bool,
+}
+
+impl<'a> UserString<'a> {
+  /// Gets the raw UTF-16LE bytes of the string, without the trailing byte.
+  pub fn as_bytes(&self) -> &'a [u8] {
+    self.data
+  }
+
+  /// Gets the number of UTF-16 code units in the string.
+  pub fn len(&self) -> usize {
+    self.data.len() / 2
+  }
+
+  /// Determines if the string is empty.
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Determines if any character has a non-zero top byte or is one of the special low characters
+  /// listed in II.24.2.4.
+  pub fn has_special_chars(&self) -> bool {
+    self.has_special_chars
+  }
+
+  /// Gets an iterator over the UTF-16 code units of the string.
+  pub fn code_units(&self) -> impl Iterator<Item = u16> + 'a {
+    self
+      .data
+      .chunks_exact(2)
+      .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+  }
+
+  /// Decodes the string, failing if it contains unpaired surrogates.
+  pub fn decode(&self) -> Result<String> {
+    decode_utf16(self.code_units())
+      .collect::<Result<String, _>>()
+      .context("Malformed UTF-16 user string")
+  }
+}
+
+impl fmt::Display for UserString<'_> {
+  /// Writes the string, replacing unpaired surrogates with `U+FFFD`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    use fmt::Write;
+
+    decode_utf16(self.code_units())
+      .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+      .try_for_each(|c| f.write_char(c))
+  }
+}
+
+// An index into the [UserStrings] stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UserStringIndex(usize);
+
+impl UserStringIndex {
+  pub(crate) fn new(offset: usize) -> Self {
+    Self(offset)
+  }
+
+  /// Creates a [UserStringIndex] from the `0x70` metadata token used as the operand of `ldstr`.
+  pub fn from_token(token: u32) -> Result<Self> {
+    match token >> 24 {
+      USER_STRING_TOKEN_TYPE => Ok(Self((token & 0x00ff_ffff) as usize)),
+      table => bail!("Expected a user string token, found table {:#x}", table),
+    }
+  }
+
+  /// Gets the `ldstr` metadata token referencing the user string.
+  pub fn token(&self) -> u32 {
+    (USER_STRING_TOKEN_TYPE << 24) | self.0 as u32
+  }
+
+  /// Gets the byte offset of the user string in the `#US` stream.
+  pub fn offset(&self) -> usize {
+    self.0
+  }
+}
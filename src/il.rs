@@ -0,0 +1,29 @@
+//! Common Intermediate Language (Partition III).
+//!
+//! Method bodies hold a stream of CIL instructions, a one or two byte opcode followed by an inline
+//! operand whose type is determined by the opcode.  [opcodes] holds the complete opcode table,
+//! [instructions] decodes a method body into [Instruction]s, [disasm] renders them ildasm style and
+//! [asm] encodes ilasm style text back into code.  [cfg] builds the control flow graph analyses
+//! start from, and [verify] tracks the types on the evaluation stack through it to check code is
+//! verifiable.
+//!
+//! [cfg]: mod@cfg
+
+pub mod asm;
+pub mod cfg;
+pub mod disasm;
+pub mod instructions;
+pub mod opcodes;
+pub mod verify;
+#[doc(inline)]
+pub use asm::*;
+#[doc(inline)]
+pub use cfg::*;
+#[doc(inline)]
+pub use disasm::*;
+#[doc(inline)]
+pub use instructions::*;
+#[doc(inline)]
+pub use opcodes::*;
+#[doc(inline)]
+pub use verify::*;
@@ -0,0 +1,711 @@
+//! ilasm style assembly of instruction text.
+//!
+//! [Assembler] is the reverse of [Disassembler]: it accepts one instruction per line in the syntax
+//! the disassembler prints and encodes it as IL code.
+//!
+//! ```text
+//!         ldarg.0
+//!         brtrue.s   not_null
+//!         ldstr      "value"
+//!         newobj     instance void [System.Runtime]System.ArgumentNullException::.ctor(string)
+//!         throw
+//! not_null:
+//!         ret
+//! ```
+//!
+//! Type, member and signature operands are resolved by name against a target [Md], string
+//! literals against its `#US` heap.  Operands the target has no row or heap entry for are left as
+//! nil tokens and listed as [Fixup]s for whoever adds them.
+
+use super::{
+  disasm::Disassembler,
+  instructions::is_local,
+  opcodes::{OpCode, OperandType, TWO_BYTE_PREFIX},
+};
+use crate::ecma335::{
+  signatures::StandAloneSig,
+  tables::{MethodDefRowId, Row, RowIndex, StandAloneSigRow, StandAloneSigRowId},
+  Md,
+};
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use anyhow::{anyhow, bail, Context, Result};
+
+/// The tables whose rows can be named by a type operand.
+const TYPE_TABLES: [u32; 3] = [0x01, 0x02, 0x1b];
+/// The tables whose rows can be named by a field or method operand.
+const MEMBER_TABLES: [u32; 4] = [0x04, 0x06, 0x0a, 0x2b];
+
+/// Encoded IL code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembledCode {
+  /// The encoded instructions.
+  pub code: Vec<u8>,
+  /// The token operands that couldn't be resolved against the target metadata, a nil token is
+  /// encoded in their place.
+  pub fixups: Vec<Fixup>,
+}
+
+/// A token operand the target metadata has no row or heap entry for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixup {
+  /// The offset of the 4 byte token in the code.
+  pub offset: u32,
+  /// What the token has to reference.
+  pub kind: FixupKind,
+}
+
+/// What the token of a [Fixup] has to reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixupKind {
+  /// A `#US` string, as UTF-16 code units.
+  UserString(Vec<u16>),
+  /// A type, as written in the operand.
+  Type(String),
+  /// A field, as written in the operand.
+  Field(String),
+  /// A method, as written in the operand.
+  Method(String),
+  /// A `calli` call site signature, as written in the operand.
+  Signature(String),
+}
+
+/// Assembles instruction text against the tables and heaps of a target [Md].
+///
+/// Creating an assembler names every type, member and call site signature in the target, so it
+/// should be reused when assembling more than one method.
+pub struct Assembler<'m, 'a> {
+  disassembler: Disassembler<'m, 'a>,
+  /// Type tokens by normalized name.
+  types: BTreeMap<String, u32>,
+  /// Field and method tokens by normalized name.
+  members: BTreeMap<String, u32>,
+  /// Call site signature tokens by normalized signature.
+  signatures: BTreeMap<String, u32>,
+  /// `#US` tokens by value.
+  user_strings: BTreeMap<Vec<u16>, u32>,
+}
+
+impl<'m, 'a> Assembler<'m, 'a> {
+  /// Creates an assembler targeting the given metadata.
+  ///
+  /// Rows that can't be formatted, e.g. a field without an owning type, are left out and can't be
+  /// referenced by name.
+  pub fn new(md: &'m Md<'a>) -> Result<Self> {
+    let disassembler = Disassembler::new(md)?;
+    let header = md.tables().header();
+    let mut types = BTreeMap::new();
+    let mut members = BTreeMap::new();
+    let mut signatures = BTreeMap::new();
+    let mut user_strings = BTreeMap::new();
+
+    // The first of several rows with the same name wins.
+    for (tables, names) in [
+      (&TYPE_TABLES[..], &mut types),
+      (&MEMBER_TABLES[..], &mut members),
+    ] {
+      for table in tables {
+        for row in 1..=header.rows[*table as usize] {
+          let token = (table << 24) | row;
+          let Ok(name) = disassembler.token(token) else {
+            continue;
+          };
+
+          names.entry(normalize(&name)).or_insert(token);
+        }
+      }
+    }
+
+    let mut index = StandAloneSigRowId::first(header);
+    while let Some(id) = index {
+      let row = md.tables().stand_alone_sigs().read(id)?;
+      if let StandAloneSig::Method(_) = md.stand_alone_sig(row.signature)? {
+        let token = ((StandAloneSigRow::ID as u32) << 24) | id.row();
+        let sig = disassembler.call_site(id)?;
+
+        signatures.entry(normalize(&sig)).or_insert(token);
+      }
+
+      index = id.next();
+    }
+
+    // The empty entry at offset 0 only pads the heap, a nil token isn't a valid `ldstr` operand.
+    for string in md.user_strings().iter().skip(1) {
+      let (index, string) = string?;
+      user_strings
+        .entry(string.code_units().collect())
+        .or_insert(index.token());
+    }
+
+    Ok(Self {
+      disassembler,
+      types,
+      members,
+      signatures,
+      user_strings,
+    })
+  }
+
+  /// Assembles instruction text, arguments can only be referenced by index or as `A_n`.
+  pub fn assemble(&self, text: &str) -> Result<AssembledCode> {
+    self.assemble_with_args(text, &[])
+  }
+
+  /// Assembles instruction text for the body of a method, arguments can also be referenced by
+  /// their parameter names.
+  pub fn assemble_method(&self, id: MethodDefRowId, text: &str) -> Result<AssembledCode> {
+    let arg_names = self.disassembler.method_arg_names(id)?;
+
+    self.assemble_with_args(text, &arg_names)
+  }
+
+  fn assemble_with_args(&self, text: &str, arg_names: &[Option<String>]) -> Result<AssembledCode> {
+    let mut code = Vec::new();
+    let mut fixups = Vec::new();
+    let mut labels = BTreeMap::new();
+    let mut branches = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+      let mut line = strip_comment(line).trim();
+
+      while let Some((label, rest)) = split_label(line) {
+        if labels.insert(label, code.len() as u32).is_some() {
+          bail!("Line {}, duplicate label `{}`", i + 1, label);
+        }
+
+        line = rest.trim_start();
+      }
+
+      if line.is_empty() {
+        continue;
+      }
+
+      let (mnemonic, operand) = match line.split_once(char::is_whitespace) {
+        Some((mnemonic, operand)) => (mnemonic, operand.trim()),
+        None => (line, ""),
+      };
+
+      let instruction = Line {
+        number: i + 1,
+        opcode: OpCode::from_name(mnemonic)
+          .ok_or_else(|| anyhow!("Unknown instruction `{}`", mnemonic))
+          .with_context(|| format!("Line {}", i + 1))?,
+        operand,
+      };
+
+      self
+        .encode(
+          &instruction,
+          arg_names,
+          &mut code,
+          &mut fixups,
+          &mut branches,
+        )
+        .with_context(|| format!("Line {}, `{}`", i + 1, line))?;
+    }
+
+    for branch in branches {
+      let target = *labels
+        .get(branch.label)
+        .ok_or_else(|| anyhow!("Line {}, undefined label `{}`", branch.line, branch.label))?;
+      let delta = target as i64 - branch.next as i64;
+
+      match branch.short {
+        true => match i8::try_from(delta) {
+          Ok(delta) => code[branch.offset] = delta as u8,
+          Err(_) => bail!(
+            "Line {}, `{}` is {:+} bytes away, out of range of a short branch",
+            branch.line,
+            branch.label,
+            delta
+          ),
+        },
+        false => {
+          let delta = i32::try_from(delta)?;
+          code[branch.offset..branch.offset + 4].copy_from_slice(&delta.to_le_bytes());
+        }
+      }
+    }
+
+    Ok(AssembledCode { code, fixups })
+  }
+
+  /// Encodes an instruction, leaving branch targets to be patched once every label is known.
+  fn encode<'t>(
+    &self,
+    line: &Line<'t>,
+    arg_names: &[Option<String>],
+    code: &mut Vec<u8>,
+    fixups: &mut Vec<Fixup>,
+    branches: &mut Vec<Branch<'t>>,
+  ) -> Result<()> {
+    let Line {
+      number,
+      opcode,
+      operand,
+    } = *line;
+
+    match opcode.value() {
+      value if value > 0xff => code.extend([TWO_BYTE_PREFIX, value as u8]),
+      value => code.push(value as u8),
+    }
+
+    let operand_type = opcode.operand_type();
+    if operand_type == OperandType::InlineNone {
+      match operand.is_empty() {
+        true => return Ok(()),
+        false => bail!("Unexpected operand"),
+      }
+    }
+
+    if operand.is_empty() {
+      bail!("Missing operand");
+    }
+
+    match operand_type {
+      OperandType::InlineNone => {}
+      OperandType::ShortInlineBrTarget | OperandType::InlineBrTarget => {
+        let short = operand_type == OperandType::ShortInlineBrTarget;
+        let size = if short { 1 } else { 4 };
+
+        branches.push(Branch {
+          line: number,
+          label: operand,
+          offset: code.len(),
+          next: code.len() + size,
+          short,
+        });
+        code.resize(code.len() + size, 0);
+      }
+      OperandType::InlineSwitch => {
+        let labels = operand
+          .strip_prefix('(')
+          .and_then(|operand| operand.strip_suffix(')'))
+          .ok_or_else(|| anyhow!("Expected a parenthesized label list"))?
+          .split(',')
+          .map(str::trim)
+          .filter(|label| !label.is_empty())
+          .collect::<Vec<_>>();
+
+        code.extend((labels.len() as u32).to_le_bytes());
+
+        // Targets are relative to the end of the whole instruction.
+        let next = code.len() + labels.len() * 4;
+        for label in labels {
+          branches.push(Branch {
+            line: number,
+            label,
+            offset: code.len(),
+            next,
+            short: false,
+          });
+          code.extend([0; 4]);
+        }
+      }
+      OperandType::ShortInlineI => {
+        let range = match opcode {
+          OpCode::LdcI4S => -0x80..=0x7f,
+          _ => 0..=0xff,
+        };
+
+        code.push(parse_int(operand, range)? as u8);
+      }
+      OperandType::InlineI => {
+        code.extend((parse_int(operand, -0x8000_0000..=0xffff_ffff)? as u32).to_le_bytes())
+      }
+      OperandType::InlineI8 => {
+        code.extend((parse_int(operand, i64::MIN as i128..=u64::MAX as i128)? as u64).to_le_bytes())
+      }
+      OperandType::ShortInlineR => match parse_byte_array(operand)? {
+        Some(bytes) if bytes.len() == 4 => code.extend(bytes),
+        Some(_) => bail!("Expected 4 bytes"),
+        None => code.extend(parse_float::<f32>(operand)?.to_le_bytes()),
+      },
+      OperandType::InlineR => match parse_byte_array(operand)? {
+        Some(bytes) if bytes.len() == 8 => code.extend(bytes),
+        Some(_) => bail!("Expected 8 bytes"),
+        None => code.extend(parse_float::<f64>(operand)?.to_le_bytes()),
+      },
+      OperandType::ShortInlineVar | OperandType::InlineVar => {
+        let index = parse_var(opcode, operand, arg_names)?;
+
+        match operand_type {
+          OperandType::ShortInlineVar => {
+            code.push(u8::try_from(index).map_err(|_| anyhow!("Index {} is out of range", index))?)
+          }
+          _ => code.extend(index.to_le_bytes()),
+        }
+      }
+      OperandType::InlineString => {
+        let value = parse_string(operand)?;
+
+        match self.user_strings.get(&value) {
+          Some(token) => code.extend(token.to_le_bytes()),
+          None => self.fixup(code, fixups, FixupKind::UserString(value)),
+        }
+      }
+      OperandType::InlineMethod => self.member(code, fixups, operand, FixupKind::Method)?,
+      OperandType::InlineField => self.member(code, fixups, operand, FixupKind::Field)?,
+      OperandType::InlineType => self.type_token(code, fixups, operand)?,
+      OperandType::InlineTok => {
+        if let Some(field) = operand.strip_prefix("field ") {
+          self.member(code, fixups, field.trim(), FixupKind::Field)?
+        } else if let Some(method) = operand.strip_prefix("method ") {
+          self.member(code, fixups, method.trim(), FixupKind::Method)?
+        } else {
+          self.type_token(code, fixups, operand)?
+        }
+      }
+      OperandType::InlineSig => match parse_token(operand)? {
+        Some(token) => code.extend(token.to_le_bytes()),
+        None => match self.signatures.get(&normalize(operand)) {
+          Some(token) => code.extend(token.to_le_bytes()),
+          None => self.fixup(code, fixups, FixupKind::Signature(operand.into())),
+        },
+      },
+    }
+
+    Ok(())
+  }
+
+  /// Encodes a field or method token.
+  fn member(
+    &self,
+    code: &mut Vec<u8>,
+    fixups: &mut Vec<Fixup>,
+    operand: &str,
+    kind: fn(String) -> FixupKind,
+  ) -> Result<()> {
+    match parse_token(operand)? {
+      Some(token) => code.extend(token.to_le_bytes()),
+      None => match self.members.get(&normalize(operand)) {
+        Some(token) => code.extend(token.to_le_bytes()),
+        None => self.fixup(code, fixups, kind(operand.into())),
+      },
+    }
+
+    Ok(())
+  }
+
+  /// Encodes a type token, a `class` or `valuetype` prefix is optional for definitions and
+  /// references.
+  fn type_token(&self, code: &mut Vec<u8>, fixups: &mut Vec<Fixup>, operand: &str) -> Result<()> {
+    if let Some(token) = parse_token(operand)? {
+      code.extend(token.to_le_bytes());
+      return Ok(());
+    }
+
+    let name = normalize(operand);
+    let unprefixed = name
+      .strip_prefix("class ")
+      .or_else(|| name.strip_prefix("valuetype "));
+
+    let token = self
+      .types
+      .get(&name)
+      .or_else(|| unprefixed.and_then(|name| self.types.get(name)));
+
+    match token {
+      Some(token) => code.extend(token.to_le_bytes()),
+      None => self.fixup(code, fixups, FixupKind::Type(operand.into())),
+    }
+
+    Ok(())
+  }
+
+  /// Encodes a nil token and records what it should reference.
+  fn fixup(&self, code: &mut Vec<u8>, fixups: &mut Vec<Fixup>, kind: FixupKind) {
+    fixups.push(Fixup {
+      offset: code.len() as u32,
+      kind,
+    });
+
+    code.extend([0; 4]);
+  }
+}
+
+/// An instruction line.
+struct Line<'t> {
+  number: usize,
+  opcode: OpCode,
+  operand: &'t str,
+}
+
+/// A branch operand waiting for its label to be placed.
+struct Branch<'t> {
+  line: usize,
+  label: &'t str,
+  /// The offset of the operand in the code.
+  offset: usize,
+  /// The offset branches are relative to.
+  next: usize,
+  short: bool,
+}
+
+/// Removes a trailing `//` comment, ignoring any inside a string literal.
+fn strip_comment(line: &str) -> &str {
+  let mut quoted = None;
+  let mut escaped = false;
+
+  for (i, c) in line.char_indices() {
+    match (quoted, c) {
+      (Some(_), '\\') if !escaped => {
+        escaped = true;
+        continue;
+      }
+      (Some(quote), c) if c == quote && !escaped => quoted = None,
+      (None, '"' | '\'') => quoted = Some(c),
+      (None, '/') if line[i + 1..].starts_with('/') => return &line[..i],
+      _ => {}
+    }
+
+    escaped = false;
+  }
+
+  line
+}
+
+/// Splits a leading `label:` from a line.
+fn split_label(line: &str) -> Option<(&str, &str)> {
+  let end = line.find(|c: char| !(c.is_ascii_alphanumeric() || "_$@?`.".contains(c)))?;
+  let rest = line[end..].strip_prefix(':')?;
+
+  match end > 0 && !rest.starts_with(':') {
+    true => Some((&line[..end], rest)),
+    false => None,
+  }
+}
+
+/// Collapses whitespace in a name, keeping a single space only between two words so names match
+/// regardless of formatting.
+fn normalize(text: &str) -> String {
+  let is_punct = |c: char| "()<>[],:/*&".contains(c);
+  let mut normalized = String::with_capacity(text.len());
+  let mut quoted = false;
+  let mut space = false;
+
+  for c in text.chars() {
+    if quoted {
+      quoted = c != '\'';
+      normalized.push(c);
+      continue;
+    }
+
+    if c.is_whitespace() {
+      space = true;
+      continue;
+    }
+
+    if space && !is_punct(c) && !normalized.is_empty() && !normalized.ends_with(is_punct) {
+      normalized.push(' ');
+    }
+
+    space = false;
+    quoted = c == '\'';
+    normalized.push(c);
+  }
+
+  normalized
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal integer within the given range.
+fn parse_int(text: &str, range: core::ops::RangeInclusive<i128>) -> Result<i128> {
+  let (negative, digits) = match text.strip_prefix('-') {
+    Some(digits) => (true, digits),
+    None => (false, text),
+  };
+
+  let value = match digits
+    .strip_prefix("0x")
+    .or_else(|| digits.strip_prefix("0X"))
+  {
+    Some(hex) => i128::from_str_radix(hex, 16),
+    None => digits.parse::<i128>(),
+  }
+  .map_err(|_| anyhow!("Expected an integer, found `{}`", text))?;
+
+  let value = if negative { -value } else { value };
+  match range.contains(&value) {
+    true => Ok(value),
+    false => bail!("{} is out of range", text),
+  }
+}
+
+/// Parses a floating point number.
+fn parse_float<T: core::str::FromStr>(text: &str) -> Result<T> {
+  text
+    .parse()
+    .map_err(|_| anyhow!("Expected a floating point number, found `{}`", text))
+}
+
+/// Parses a `0x` prefixed hexadecimal metadata token.
+fn parse_token(text: &str) -> Result<Option<u32>> {
+  match text.strip_prefix("0x") {
+    Some(hex) => match u32::from_str_radix(hex, 16) {
+      Ok(token) => Ok(Some(token)),
+      Err(_) => bail!("Expected a metadata token, found `{}`", text),
+    },
+    None => Ok(None),
+  }
+}
+
+/// Parses a byte list, `(00 00 C0 FF)`, returning `None` if the text isn't one.
+fn parse_byte_array(text: &str) -> Result<Option<Vec<u8>>> {
+  let bytes = match text
+    .strip_prefix('(')
+    .and_then(|text| text.strip_suffix(')'))
+  {
+    Some(bytes) => bytes,
+    None => return Ok(None),
+  };
+
+  bytes
+    .split_whitespace()
+    .map(|byte| {
+      u8::from_str_radix(byte, 16).map_err(|_| anyhow!("Expected a hex byte, found `{}`", byte))
+    })
+    .collect::<Result<Vec<_>>>()
+    .map(Some)
+}
+
+/// Parses the operand of `ldstr`, a quoted string or a `bytearray` of UTF-16LE code units.
+fn parse_string(text: &str) -> Result<Vec<u16>> {
+  if let Some(bytes) = text.strip_prefix("bytearray") {
+    let bytes = parse_byte_array(bytes.trim())?.ok_or_else(|| anyhow!("Expected a byte list"))?;
+    if !bytes.len().is_multiple_of(2) {
+      bail!("Expected UTF-16 code units, found {} bytes", bytes.len());
+    }
+
+    return Ok(
+      bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect(),
+    );
+  }
+
+  let quoted = text
+    .strip_prefix('"')
+    .and_then(|text| text.strip_suffix('"'))
+    .ok_or_else(|| anyhow!("Expected a string literal"))?;
+
+  let mut value = String::with_capacity(quoted.len());
+  let mut chars = quoted.chars();
+
+  while let Some(c) = chars.next() {
+    value.push(match c {
+      '\\' => match chars.next() {
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('0') => '\0',
+        Some(c @ ('"' | '\\' | '\'')) => c,
+        Some(c) => bail!("Unknown escape `\\{}`", c),
+        None => bail!("Unterminated escape"),
+      },
+      '"' => bail!("Unescaped `\"` in a string literal"),
+      c => c,
+    });
+  }
+
+  Ok(value.encode_utf16().collect())
+}
+
+/// Parses the index of a local, `V_n`, or an argument, `A_n` or a parameter name.
+fn parse_var(opcode: OpCode, text: &str, arg_names: &[Option<String>]) -> Result<u16> {
+  let is_local = is_local(opcode);
+  let prefix = if is_local { "V_" } else { "A_" };
+
+  if !is_local {
+    let named = arg_names
+      .iter()
+      .position(|name| name.as_deref() == Some(text));
+
+    if let Some(index) = named {
+      return Ok(index as u16);
+    }
+  }
+
+  let index = text.strip_prefix(prefix).unwrap_or(text);
+  index.parse().map_err(|_| {
+    anyhow!(
+      "Expected a {}, found `{}`",
+      if is_local { "local" } else { "argument" },
+      text
+    )
+  })
+}
+
+impl<'a> Md<'a> {
+  /// Assembles instruction text against this metadata, use an [Assembler] when assembling more
+  /// than once.
+  pub fn assemble(&self, text: &str) -> Result<AssembledCode> {
+    Assembler::new(self)?.assemble(text)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{normalize, parse_int, parse_string, split_label, strip_comment, Assembler};
+  use crate::ecma335::{
+    tables::{FieldRow, Row, TypeRefRow},
+    Md,
+  };
+
+  #[test]
+  fn unformattable_rows() {
+    // TypeRef 1 is `A`.
+    let type_refs = [0x00u8, 0x00, 0x01, 0x00, 0x00, 0x00];
+    // Field 1 has neither an owning type nor a signature.
+    let fields = [0x00u8, 0x00, 0x03, 0x00, 0x00, 0x00];
+    let root = Md::root_with_tables(
+      &[(TypeRefRow::ID, 1, &type_refs), (FieldRow::ID, 1, &fields)],
+      b"\0A\0f\0",
+      &[],
+    );
+    let md = Md::from_cli_data(&root).unwrap();
+    let assembler = Assembler::new(&md).unwrap();
+
+    let code = assembler.assemble("box A").unwrap();
+    assert_eq!(code.code, [0x8c, 0x01, 0x00, 0x00, 0x01]);
+    assert!(code.fixups.is_empty());
+  }
+
+  #[test]
+  fn normalize_names() {
+    assert_eq!(
+      normalize(
+        "instance  void class [System.Runtime] \
+         System.Collections.Generic.List`1 < int32 >::Add( !0 )"
+      ),
+      "instance void class[System.Runtime]System.Collections.Generic.List`1<int32>::Add(!0)"
+    );
+    assert_eq!(normalize("native   int *"), "native int*");
+    assert_eq!(normalize("string 'a  b'::'c'"), "string 'a  b'::'c'");
+  }
+
+  #[test]
+  fn parse_lines() {
+    assert_eq!(strip_comment("ldstr \"a // b\" // c"), "ldstr \"a // b\" ");
+    assert_eq!(strip_comment("ldstr \"\\\"//\" // c"), "ldstr \"\\\"//\" ");
+    assert_eq!(split_label("IL_0000:  nop"), Some(("IL_0000", "  nop")));
+    assert_eq!(split_label("loop: br.s loop"), Some(("loop", " br.s loop")));
+    assert_eq!(split_label("call void X::Y()"), None);
+    assert_eq!(split_label("nop"), None);
+  }
+
+  #[test]
+  fn parse_literals() {
+    assert_eq!(parse_int("-1", -0x80..=0xff).unwrap(), -1);
+    assert_eq!(parse_int("0xff", -0x80..=0xff).unwrap(), 0xff);
+    assert!(parse_int("0x100", -0x80..=0xff).is_err());
+    assert!(parse_int("one", -0x80..=0xff).is_err());
+
+    assert_eq!(parse_string("\"a\\\"\\n\"").unwrap(), [0x61, 0x22, 0x0a]);
+    assert_eq!(
+      parse_string("bytearray (3D D8 00 DE)").unwrap(),
+      [0xd83d, 0xde00]
+    );
+    assert!(parse_string("bytearray (00)").is_err());
+    assert!(parse_string("\"a\"b\"").is_err());
+  }
+}
@@ -0,0 +1,620 @@
+//! Control flow graphs of method bodies.
+//!
+//! A [ControlFlowGraph] splits the instructions of a body into [BasicBlock]s, straight line runs of
+//! code entered only at their first instruction and left only after their last, linked by
+//! [Edge]s.  Blocks start at branch and `switch` targets, after any instruction that transfers
+//! control and at the boundaries of try blocks, filters and handlers, so every block lies within
+//! a single set of protected regions.
+//!
+//! Each block inside a try block has an [EdgeKind::Exception] edge to the entry of the clause's
+//! filter and handler, which makes handlers reachable from the entry block and lets dominators be
+//! computed over the whole method.
+
+use super::{
+  instructions::{Instruction, Operand},
+  opcodes::{FlowControl, OpCode},
+};
+use crate::ecma335::{
+  body::{ExceptionClause, MethodBody},
+  tables::TablesHeader,
+};
+use alloc::{collections::BTreeSet, format, vec, vec::Vec};
+use anyhow::{bail, Context, Result};
+use core::ops::Range;
+
+/// The kind of control transfer along an [Edge].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+  /// Execution continues with the next instruction, including a conditional branch not taken.
+  FallThrough,
+  /// An unconditional or taken conditional branch.
+  Branch,
+  /// One of the targets of a `switch`.
+  Switch,
+  /// A `leave` out of a protected region or handler.
+  Leave,
+  /// An exception thrown in a try block entering its filter or handler.
+  Exception,
+}
+
+/// An edge to a successor block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+  /// The kind of control transfer.
+  pub kind: EdgeKind,
+  /// The index of the successor block.
+  pub target: usize,
+}
+
+/// The way control leaves the method, or the handler, at the end of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Exit {
+  /// `ret`.
+  Return,
+  /// `throw`.
+  Throw,
+  /// `rethrow`.
+  Rethrow,
+  /// `endfinally`, control continues at the target of the `leave` running the handler.
+  EndFinally,
+  /// `endfilter`, control continues in the handler or the search for one.
+  EndFilter,
+  /// `jmp`, control continues in another method.
+  Jmp,
+}
+
+/// A straight line run of instructions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+  /// The range of code of the block.
+  pub range: Range<u32>,
+  /// The range of the block's instructions in [ControlFlowGraph::instructions].
+  pub instructions: Range<usize>,
+  /// The edges to the blocks control can continue in.
+  pub successors: Vec<Edge>,
+  /// The indices of the blocks with an edge to this one.
+  pub predecessors: Vec<usize>,
+  /// How control leaves the method or handler at the end of the block, if it does.
+  pub exit: Option<Exit>,
+}
+
+/// The control flow graph of a method body.
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+  instructions: Vec<Instruction>,
+  blocks: Vec<BasicBlock>,
+  /// The immediate dominator of each block, the entry block and unreachable blocks have none.
+  dominators: Vec<Option<usize>>,
+}
+
+impl ControlFlowGraph {
+  /// Builds the control flow graph of a method's decoded instructions and exception clauses.
+  pub fn new(instructions: Vec<Instruction>, clauses: &[ExceptionClause]) -> Result<Self> {
+    let end = match instructions.last() {
+      Some(instruction) => instruction.next_offset(),
+      None => bail!("Empty method body"),
+    };
+
+    let is_instruction = |offset: u32| {
+      offset == end
+        || instructions
+          .binary_search_by_key(&offset, |instruction| instruction.offset)
+          .is_ok()
+    };
+
+    // Find the offsets starting a block.
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+
+    for instruction in &instructions {
+      for target in targets(instruction) {
+        if !is_instruction(target) {
+          bail!(
+            "Branch target IL_{:04x} of IL_{:04x} isn't an instruction",
+            target,
+            instruction.offset
+          );
+        }
+
+        leaders.insert(target);
+      }
+
+      if ends_block(instruction) {
+        leaders.insert(instruction.next_offset());
+      }
+    }
+
+    for clause in clauses {
+      let ranges = [
+        Some(clause.try_range()),
+        Some(clause.handler_range()),
+        clause.filter_range(),
+      ];
+
+      for range in ranges.into_iter().flatten() {
+        if !is_instruction(range.start) || !is_instruction(range.end) {
+          bail!(
+            "Exception handling range IL_{:04x}..IL_{:04x} doesn't fall on instructions",
+            range.start,
+            range.end
+          );
+        }
+
+        leaders.insert(range.start);
+        leaders.insert(range.end);
+      }
+    }
+
+    leaders.remove(&end);
+
+    // Split the instructions at each leader.
+    let leaders = leaders.into_iter().collect::<Vec<_>>();
+    let mut blocks = Vec::with_capacity(leaders.len());
+    let mut first = 0;
+
+    for (i, start) in leaders.iter().enumerate() {
+      let block_end = leaders.get(i + 1).copied().unwrap_or(end);
+      let last = first
+        + instructions[first..]
+          .iter()
+          .take_while(|instruction| instruction.offset < block_end)
+          .count();
+
+      blocks.push(BasicBlock {
+        range: *start..block_end,
+        instructions: first..last,
+        successors: Vec::new(),
+        predecessors: Vec::new(),
+        exit: None,
+      });
+
+      first = last;
+    }
+
+    let block_at = |offset: u32| {
+      leaders
+        .binary_search(&offset)
+        .ok()
+        .with_context(|| format!("No block starts at IL_{:04x}", offset))
+    };
+
+    for i in 0..blocks.len() {
+      let last = &instructions[blocks[i].instructions.end - 1];
+      let mut successors = Vec::new();
+
+      let kind = match last.opcode {
+        OpCode::Leave | OpCode::LeaveS => EdgeKind::Leave,
+        OpCode::Switch => EdgeKind::Switch,
+        _ => EdgeKind::Branch,
+      };
+      for target in targets(last) {
+        successors.push(Edge {
+          kind,
+          target: block_at(target)?,
+        });
+      }
+
+      let exit = exit(last);
+      let falls_through = exit.is_none()
+        && !matches!(last.opcode, OpCode::Leave | OpCode::LeaveS)
+        && last.opcode.flow_control() != FlowControl::Branch;
+
+      if falls_through {
+        match blocks.get(i + 1) {
+          Some(_) => successors.push(Edge {
+            kind: EdgeKind::FallThrough,
+            target: i + 1,
+          }),
+          None => bail!("IL_{:04x} falls through the end of the method", last.offset),
+        }
+      }
+
+      blocks[i].successors = successors;
+      blocks[i].exit = exit;
+    }
+
+    for clause in clauses {
+      let try_range = clause.try_range();
+      let mut handlers = vec![block_at(clause.handler_offset)?];
+      if let Some(filter) = clause.filter_range() {
+        handlers.insert(0, block_at(filter.start)?);
+      }
+
+      for block in &mut blocks {
+        if try_range.start <= block.range.start && block.range.end <= try_range.end {
+          for target in &handlers {
+            block.successors.push(Edge {
+              kind: EdgeKind::Exception,
+              target: *target,
+            });
+          }
+        }
+      }
+    }
+
+    for i in 0..blocks.len() {
+      blocks[i].successors.sort();
+      blocks[i].successors.dedup();
+
+      for edge in blocks[i].successors.clone() {
+        let predecessors = &mut blocks[edge.target].predecessors;
+        if predecessors.last() != Some(&i) {
+          predecessors.push(i);
+        }
+      }
+    }
+
+    let dominators = dominators(&blocks);
+
+    Ok(Self {
+      instructions,
+      blocks,
+      dominators,
+    })
+  }
+
+  /// Gets the decoded instructions of the method.
+  pub fn instructions(&self) -> &[Instruction] {
+    &self.instructions
+  }
+
+  /// Gets the basic blocks, ordered by offset with the entry block first.
+  pub fn blocks(&self) -> &[BasicBlock] {
+    &self.blocks
+  }
+
+  /// Gets the instructions of a block.
+  pub fn block_instructions(&self, block: usize) -> &[Instruction] {
+    &self.instructions[self.blocks[block].instructions.clone()]
+  }
+
+  /// Gets the index of the block containing the instruction at the given offset.
+  pub fn block_at(&self, offset: u32) -> Option<usize> {
+    let i = self
+      .blocks
+      .partition_point(|block| block.range.start <= offset);
+
+    i.checked_sub(1)
+      .filter(|i| self.blocks[*i].range.contains(&offset))
+  }
+
+  /// Gets the immediate dominator of a block, `None` for the entry block and unreachable blocks.
+  pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+    self.dominators[block]
+  }
+
+  /// Determines if a block is reachable from the entry block.
+  pub fn is_reachable(&self, block: usize) -> bool {
+    block == 0 || self.dominators[block].is_some()
+  }
+
+  /// Determines if every path from the entry block to `block` passes through `dominator`.
+  pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+    if !self.is_reachable(block) {
+      return false;
+    }
+
+    let mut current = Some(block);
+    while let Some(block) = current {
+      if block == dominator {
+        return true;
+      }
+
+      current = self.dominators[block];
+    }
+
+    false
+  }
+
+  /// Gets the back edges, `(from, header)` pairs where the header dominates the block branching
+  /// back to it.
+  pub fn back_edges(&self) -> Vec<(usize, usize)> {
+    let mut back_edges = Vec::new();
+
+    for (i, block) in self.blocks.iter().enumerate() {
+      for edge in &block.successors {
+        if self.dominates(edge.target, i) {
+          back_edges.push((i, edge.target));
+        }
+      }
+    }
+
+    back_edges
+  }
+
+  /// Gets the loop headers, the targets of back edges, in order.
+  pub fn loop_headers(&self) -> Vec<usize> {
+    let headers = self
+      .back_edges()
+      .into_iter()
+      .map(|(_, header)| header)
+      .collect::<BTreeSet<_>>();
+
+    headers.into_iter().collect()
+  }
+}
+
+/// Gets the branch and `switch` targets of an instruction.
+fn targets(instruction: &Instruction) -> Vec<u32> {
+  match &instruction.operand {
+    Operand::Branch(target) => vec![*target],
+    Operand::Switch(targets) => targets.clone(),
+    _ => Vec::new(),
+  }
+}
+
+/// Gets how an instruction leaves the method or handler, if it does.
+fn exit(instruction: &Instruction) -> Option<Exit> {
+  match instruction.opcode {
+    OpCode::Ret => Some(Exit::Return),
+    OpCode::Throw => Some(Exit::Throw),
+    OpCode::Rethrow => Some(Exit::Rethrow),
+    OpCode::Endfinally => Some(Exit::EndFinally),
+    OpCode::Endfilter => Some(Exit::EndFilter),
+    OpCode::Jmp => Some(Exit::Jmp),
+    _ => None,
+  }
+}
+
+/// Determines if an instruction ends a block.
+fn ends_block(instruction: &Instruction) -> bool {
+  exit(instruction).is_some()
+    || matches!(
+      instruction.opcode.flow_control(),
+      FlowControl::Branch | FlowControl::CondBranch
+    )
+}
+
+/// Computes the immediate dominator of each block with the iterative algorithm of Cooper, Harvey
+/// and Kennedy.
+fn dominators(blocks: &[BasicBlock]) -> Vec<Option<usize>> {
+  // Number the blocks reachable from the entry in postorder.
+  let mut postorder = Vec::with_capacity(blocks.len());
+  let mut visited = vec![false; blocks.len()];
+  let mut stack = vec![(0, 0)];
+  visited[0] = true;
+
+  while let Some((block, next)) = stack.last_mut() {
+    match blocks[*block].successors.get(*next) {
+      Some(edge) => {
+        *next += 1;
+        if !visited[edge.target] {
+          visited[edge.target] = true;
+          stack.push((edge.target, 0));
+        }
+      }
+      None => {
+        postorder.push(*block);
+        stack.pop();
+      }
+    }
+  }
+
+  let mut number = vec![usize::MAX; blocks.len()];
+  for (i, block) in postorder.iter().enumerate() {
+    number[*block] = i;
+  }
+
+  let mut dominators = vec![None; blocks.len()];
+  dominators[0] = Some(0);
+
+  let mut changed = true;
+  while changed {
+    changed = false;
+
+    for block in postorder.iter().rev().skip(1) {
+      let mut processed = blocks[*block]
+        .predecessors
+        .iter()
+        .copied()
+        .filter(|predecessor| dominators[*predecessor].is_some());
+
+      let mut dominator = match processed.next() {
+        Some(predecessor) => predecessor,
+        None => continue,
+      };
+
+      for predecessor in processed {
+        // Walk both up the dominator tree until they meet.
+        let mut a = predecessor;
+        let mut b = dominator;
+
+        while a != b {
+          while number[a] < number[b] {
+            a = dominators[a].unwrap();
+          }
+          while number[b] < number[a] {
+            b = dominators[b].unwrap();
+          }
+        }
+
+        dominator = a;
+      }
+
+      if dominators[*block] != Some(dominator) {
+        dominators[*block] = Some(dominator);
+        changed = true;
+      }
+    }
+  }
+
+  dominators[0] = None;
+  dominators
+}
+
+impl<'a> MethodBody<'a> {
+  /// Builds the control flow graph of the body.
+  pub fn control_flow_graph(&self, header: &TablesHeader) -> Result<ControlFlowGraph> {
+    let instructions = self.instructions().collect::<Result<Vec<_>>>()?;
+    let clauses = self.exception_clauses(header)?;
+
+    ControlFlowGraph::new(instructions, &clauses)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ControlFlowGraph, Edge, EdgeKind, Exit};
+  use crate::{
+    ecma335::body::{ExceptionClause, HandlerKind},
+    il::instructions::Instructions,
+  };
+  use alloc::vec::Vec;
+
+  fn build(code: &[u8], clauses: &[ExceptionClause]) -> anyhow::Result<ControlFlowGraph> {
+    let instructions = Instructions::new(code).collect::<anyhow::Result<Vec<_>>>()?;
+
+    ControlFlowGraph::new(instructions, clauses)
+  }
+
+  fn successors(cfg: &ControlFlowGraph, block: usize) -> Vec<(EdgeKind, usize)> {
+    cfg.blocks()[block]
+      .successors
+      .iter()
+      .map(|Edge { kind, target }| (*kind, *target))
+      .collect()
+  }
+
+  #[test]
+  fn loop_blocks() {
+    #[rustfmt::skip]
+    let code = [
+      // IL_0000: ldc.i4.0
+      0x16,
+      // IL_0001: stloc.0
+      0x0a,
+      // IL_0002: br.s IL_0008
+      0x2b, 0x04,
+      // IL_0004: ldloc.0
+      0x06,
+      // IL_0005: ldc.i4.1
+      0x17,
+      // IL_0006: add
+      0x58,
+      // IL_0007: stloc.0
+      0x0a,
+      // IL_0008: ldloc.0
+      0x06,
+      // IL_0009: ldarg.0
+      0x02,
+      // IL_000a: blt.s IL_0004
+      0x32, 0xf8,
+      // IL_000c: ret
+      0x2a,
+    ];
+
+    let cfg = build(&code, &[]).unwrap();
+    let ranges = cfg
+      .blocks()
+      .iter()
+      .map(|block| block.range.clone())
+      .collect::<Vec<_>>();
+
+    assert_eq!(ranges, [0x0..0x4, 0x4..0x8, 0x8..0xc, 0xc..0xd]);
+    assert_eq!(successors(&cfg, 0), [(EdgeKind::Branch, 2)]);
+    assert_eq!(successors(&cfg, 1), [(EdgeKind::FallThrough, 2)]);
+    assert_eq!(
+      successors(&cfg, 2),
+      [(EdgeKind::FallThrough, 3), (EdgeKind::Branch, 1)]
+    );
+    assert_eq!(cfg.blocks()[2].predecessors, [0, 1]);
+    assert_eq!(cfg.blocks()[3].exit, Some(Exit::Return));
+
+    assert_eq!(cfg.immediate_dominator(0), None);
+    assert_eq!(cfg.immediate_dominator(1), Some(2));
+    assert_eq!(cfg.immediate_dominator(2), Some(0));
+    assert_eq!(cfg.immediate_dominator(3), Some(2));
+    assert!(cfg.dominates(0, 3));
+    assert!(!cfg.dominates(1, 2));
+
+    assert_eq!(cfg.back_edges(), [(1, 2)]);
+    assert_eq!(cfg.loop_headers(), [2]);
+    assert_eq!(cfg.block_at(0x6), Some(1));
+    assert_eq!(cfg.block_at(0xd), None);
+  }
+
+  #[test]
+  fn switch_and_handlers() {
+    #[rustfmt::skip]
+    let code = [
+      // IL_0000: ldarg.0
+      0x02,
+      // IL_0001: switch (IL_000f, IL_0011)
+      0x45, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+      // IL_000e: ret
+      0x2a,
+      // IL_000f: leave.s IL_0014
+      0xde, 0x03,
+      // IL_0011: nop
+      0x00,
+      // IL_0012: endfinally
+      0xdc,
+      // IL_0013: nop
+      0x00,
+      // IL_0014: ret
+      0x2a,
+    ];
+
+    // A finally handler IL_0011..IL_0013 protecting IL_000f..IL_0011.
+    let clauses = [ExceptionClause {
+      kind: HandlerKind::Finally,
+      try_offset: 0x0f,
+      try_length: 2,
+      handler_offset: 0x11,
+      handler_length: 2,
+    }];
+
+    let cfg = build(&code, &clauses).unwrap();
+    let starts = cfg
+      .blocks()
+      .iter()
+      .map(|block| block.range.start)
+      .collect::<Vec<_>>();
+
+    assert_eq!(starts, [0x00, 0x0e, 0x0f, 0x11, 0x13, 0x14]);
+    assert_eq!(
+      successors(&cfg, 0),
+      [
+        (EdgeKind::FallThrough, 1),
+        (EdgeKind::Switch, 2),
+        (EdgeKind::Switch, 3)
+      ]
+    );
+    assert_eq!(
+      successors(&cfg, 2),
+      [(EdgeKind::Leave, 5), (EdgeKind::Exception, 3)]
+    );
+    assert_eq!(cfg.blocks()[3].exit, Some(Exit::EndFinally));
+    assert!(cfg.blocks()[3].successors.is_empty());
+
+    // Only reachable by falling through the handler, which ends with endfinally.
+    assert!(!cfg.is_reachable(4));
+    assert!(cfg.loop_headers().is_empty());
+  }
+
+  #[test]
+  fn malformed_graphs() {
+    let finally = |try_offset, handler_offset| ExceptionClause {
+      kind: HandlerKind::Finally,
+      try_offset,
+      try_length: 1,
+      handler_offset,
+      handler_length: 1,
+    };
+
+    let cases: &[(&[u8], &[ExceptionClause])] = &[
+      // Empty.
+      (&[], &[]),
+      // Branch into the middle of an instruction.
+      (&[0x2b, 0x01, 0x20, 0x00, 0x00, 0x00, 0x00, 0x2a], &[]),
+      // Falls through the end.
+      (&[0x00], &[]),
+      // Handler in the middle of an instruction.
+      (&[0x20, 0x00, 0x00, 0x00, 0x00, 0x2a], &[finally(0, 1)]),
+    ];
+
+    for (code, clauses) in cases {
+      assert!(build(code, clauses).is_err());
+    }
+  }
+}
@@ -0,0 +1,1262 @@
+//! ildasm style disassembly.
+//!
+//! [Disassembler] resolves everything a method's disassembly needs up front, the method header,
+//! locals, instruction operands and exception handling blocks, and returns a [MethodDisassembly]
+//! that renders it with [Display](fmt::Display):
+//!
+//! ```text
+//! .method public hidebysig static string ToString(string 'value') cil managed
+//! {
+//!   .maxstack  8
+//!   IL_0000:  ldarg.0
+//!   IL_0001:  ldc.i4.s   34
+//!   IL_0003:  call       string Newtonsoft.Json.JsonConvert::ToString(string, char)
+//!   IL_0008:  ret
+//! } // end of method JsonConvert::ToString
+//! ```
+
+use super::{
+  instructions::{Instruction, Operand},
+  opcodes::{OpCode, OperandType},
+};
+use crate::ecma335::{
+  body::{HandlerKind, TryBlock},
+  signatures::{
+    ArrayShape, MethodSig, SignatureAttributes, SignatureHeader, SignatureKind, StandAloneSig,
+    TypeSig,
+  },
+  tables::{
+    flags::{MethodAttributes, MethodImplAttributes, ParamAttributes},
+    FieldRowId, GenericParamRowId, ImplMapRowId, MemberForwarded, MemberRefParent, MemberRefRowId,
+    MethodDefOrRef, MethodDefRow, MethodDefRowId, MethodSpecRowId, ModuleRefRowId, ParamRow,
+    ResolutionScope, Row, RowIndex, StandAloneSigRowId, TypeDefOrRef, TypeDefRowId,
+    TypeOrMethodDef, TypeRefRowId, TypeSpecRowId,
+  },
+  user_strings::UserStringIndex,
+  Md, MAX_NESTING,
+};
+use alloc::{
+  borrow::Cow,
+  format,
+  string::{String, ToString},
+  vec,
+  vec::Vec,
+};
+use anyhow::{bail, Context, Result};
+use core::fmt;
+
+/// The identifiers ilasm treats as keywords, a name matching one has to be quoted.
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+  "abstract", "ansi", "as", "assembly", "at", "auto", "bool", "catch", "char", "cil", "class",
+  "default", "demand", "explicit", "extends", "famandassem", "family", "famorassem", "fault",
+  "field", "filter", "final", "finally", "float32", "float64", "handler", "hidebysig", "implements",
+  "in", "init", "instance", "int", "int16", "int32", "int64", "int8", "interface", "internalcall",
+  "literal", "managed", "method", "modopt", "modreq", "native", "newslot", "object", "opt", "out",
+  "pinned", "private", "property", "public", "runtime", "sealed", "specialname", "static", "string",
+  "to", "type", "typedref", "uint16", "uint32", "uint64", "uint8", "unmanaged", "value",
+  "valuetype", "vararg", "virtual", "void", "with",
+];
+
+/// Resolves methods into their ildasm style disassembly.
+///
+/// Creating a disassembler indexes the owners of every method and field, the enclosing types of
+/// nested types and the generic parameters and pinvoke imports of methods, so it should be reused
+/// when disassembling more than one method.
+pub struct Disassembler<'m, 'a> {
+  md: &'m Md<'a>,
+  /// The type owning each method, indexed by zero-based row.
+  method_owners: Vec<Option<TypeDefRowId>>,
+  /// The type owning each field, indexed by zero-based row.
+  field_owners: Vec<Option<TypeDefRowId>>,
+  /// The enclosing type of each type, indexed by zero-based row.
+  enclosing_types: Vec<Option<TypeDefRowId>>,
+  /// The generic parameters of each method, indexed by zero-based row.
+  method_generic_params: Vec<Vec<GenericParamRowId>>,
+  /// The pinvoke import of each method, indexed by zero-based row.
+  method_impl_maps: Vec<Option<ImplMapRowId>>,
+}
+
+impl<'m, 'a> Disassembler<'m, 'a> {
+  /// Creates a disassembler for the given metadata.
+  pub fn new(md: &'m Md<'a>) -> Result<Self> {
+    let header = md.tables().header();
+    let mut method_owners = vec![None; md.tables().method_defs().len()];
+    let mut field_owners = vec![None; md.tables().fields().len()];
+    let mut enclosing_types = vec![None; md.tables().type_defs().len()];
+
+    let mut index = TypeDefRowId::first(header);
+    while let Some(id) = index {
+      for method in md.tables().type_def_methods(id)? {
+        method_owners[method.row() as usize - 1] = Some(id);
+      }
+
+      for field in md.tables().type_def_fields(id)? {
+        field_owners[field.row() as usize - 1] = Some(id);
+      }
+
+      index = id.next();
+    }
+
+    for nested in md.tables().nested_classes() {
+      let nested = nested?;
+      if let Some(enclosing) = enclosing_types.get_mut(nested.nested_class.row() as usize - 1) {
+        *enclosing = Some(nested.enclosing_class);
+      }
+    }
+
+    let mut method_generic_params = vec![Vec::new(); md.tables().method_defs().len()];
+    let mut index = GenericParamRowId::first(header);
+    while let Some(id) = index {
+      let row = md.tables().generic_params().read(id)?;
+      if let TypeOrMethodDef::MethodDef(method) = row.owner {
+        if let Some(params) = method_generic_params.get_mut(method.row() as usize - 1) {
+          params.push(id);
+        }
+      }
+
+      index = id.next();
+    }
+
+    let mut method_impl_maps = vec![None; md.tables().method_defs().len()];
+    let mut index = ImplMapRowId::first(header);
+    while let Some(id) = index {
+      let row = md.tables().impl_maps().read(id)?;
+      if let MemberForwarded::MethodDef(method) = row.member_forwarded {
+        if let Some(impl_map) = method_impl_maps.get_mut(method.row() as usize - 1) {
+          impl_map.get_or_insert(id);
+        }
+      }
+
+      index = id.next();
+    }
+
+    Ok(Self {
+      md,
+      method_owners,
+      field_owners,
+      enclosing_types,
+      method_generic_params,
+      method_impl_maps,
+    })
+  }
+
+  /// Disassembles a method.
+  pub fn method(&self, id: MethodDefRowId) -> Result<MethodDisassembly> {
+    let md = self.md;
+    let row = md.tables().method_defs().read(id)?;
+    let name = md.strings().get(row.name)?;
+    let owner = self.method_owner(id)?;
+
+    let disassemble = || -> Result<MethodDisassembly> {
+      let (header, body) = self.disassemble(id, &row)?;
+      let entry_point = md
+        .image()
+        .map(|image| image.cor20_header().entry_point_token);
+
+      Ok(MethodDisassembly {
+        header,
+        entry_point: entry_point == Some(((MethodDefRow::ID as u32) << 24) | id.row()),
+        owner: quote(
+          md.strings()
+            .get(md.tables().type_defs().read(owner)?.name)?,
+        )
+        .into_owned(),
+        owner_full_name: self.type_def(owner)?,
+        name: quote(name).into_owned(),
+        body,
+      })
+    };
+
+    disassemble().with_context(|| format!("Method `{}`", name))
+  }
+
+  /// Disassembles the header and body of a method.
+  fn disassemble(
+    &self,
+    id: MethodDefRowId,
+    row: &MethodDefRow,
+  ) -> Result<(String, Option<BodyDisassembly>)> {
+    let md = self.md;
+    let sig = md.method_sig(row.signature)?;
+    let params = self.params(id, &sig)?;
+
+    let mut header = String::from(".method ");
+    header.push_str(&method_flags(row.flags));
+    if row.flags.contains(MethodAttributes::P_INVOKE_IMPL) {
+      header.push_str(&self.pinvoke(id)?);
+    }
+    header.push_str(&calling_convention(sig.header));
+    header.push_str(&self.type_sig(&sig.ret)?);
+    header.push(' ');
+    header.push_str(&quote(md.strings().get(row.name)?));
+    header.push_str(&self.generic_params(id)?);
+
+    let mut args = Vec::new();
+    for (i, ty) in sig.params.iter().enumerate() {
+      let mut arg = String::new();
+      let param = params.get(i + 1).and_then(Option::as_ref);
+
+      if let Some(param) = param {
+        for (flag, keyword) in [
+          (ParamAttributes::IN, "[in] "),
+          (ParamAttributes::OUT, "[out] "),
+          (ParamAttributes::OPTIONAL, "[opt] "),
+        ] {
+          if param.flags.contains(flag) {
+            arg.push_str(keyword);
+          }
+        }
+      }
+
+      arg.push_str(&self.type_sig(ty)?);
+
+      if let Some(param) = param {
+        let name = md.strings().get(param.name)?;
+        if !name.is_empty() {
+          arg.push(' ');
+          arg.push_str(&quote(name));
+        }
+      }
+
+      args.push(arg);
+    }
+
+    header.push('(');
+    header.push_str(&args.join(", "));
+    header.push_str(") ");
+    header.push_str(&impl_flags(row.impl_flags));
+
+    let body = match md.method_body(row)? {
+      Some(body) => body,
+      None => return Ok((header, None)),
+    };
+
+    let arg_names = self.arg_names(&sig, &params)?;
+
+    let locals = match body.local_var_sig {
+      Some(id) => {
+        let sig = md
+          .tables()
+          .stand_alone_sigs()
+          .read(id)
+          .and_then(|row| md.stand_alone_sig(row.signature))?;
+
+        let locals = match sig {
+          StandAloneSig::Locals(sig) => sig.locals,
+          StandAloneSig::Method(_) => bail!("Expected a local variable signature"),
+        };
+
+        let locals = locals
+          .iter()
+          .enumerate()
+          .map(|(i, ty)| Ok(format!("[{}] {} V_{}", i, self.type_sig(ty)?, i)))
+          .collect::<Result<Vec<_>>>()?;
+
+        Some(locals)
+      }
+      None => None,
+    };
+
+    let instructions = body
+      .instructions()
+      .map(|instruction| {
+        let instruction = instruction?;
+        let operand = self
+          .operand(&instruction, &arg_names)
+          .with_context(|| format!("Operand of IL_{:04x}", instruction.offset))?;
+
+        Ok((instruction, operand))
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    let mut regions = Vec::new();
+    for block in body.try_blocks(md.tables().header())? {
+      self.regions(&block, &mut regions)?;
+    }
+    regions.sort_by_key(|region| (region.start, core::cmp::Reverse(region.end)));
+
+    Ok((
+      header,
+      Some(BodyDisassembly {
+        max_stack: body.max_stack,
+        init_locals: body.init_locals(),
+        locals,
+        instructions,
+        regions,
+      }),
+    ))
+  }
+
+  /// Flattens a try block and its nested blocks into the regions opened and closed while
+  /// printing.
+  fn regions(&self, block: &TryBlock, regions: &mut Vec<Region>) -> Result<()> {
+    regions.push(Region {
+      start: block.range.start,
+      end: block.range.end,
+      open: Some(String::from(".try")),
+      close: ".try",
+    });
+
+    for clause in &block.clauses {
+      let open = match clause.kind {
+        HandlerKind::Catch(ty) => format!("catch {}", self.type_def_or_ref(ty)?),
+        HandlerKind::Filter(filter_offset) => {
+          regions.push(Region {
+            start: filter_offset,
+            end: clause.handler_offset,
+            open: Some(String::from("filter")),
+            close: "filter",
+          });
+
+          regions.push(Region {
+            start: clause.handler_offset,
+            end: clause.handler_range().end,
+            open: None,
+            close: "handler",
+          });
+
+          continue;
+        }
+        HandlerKind::Finally => String::from("finally"),
+        HandlerKind::Fault => String::from("fault"),
+      };
+
+      regions.push(Region {
+        start: clause.handler_offset,
+        end: clause.handler_range().end,
+        open: Some(open),
+        close: "handler",
+      });
+    }
+
+    for child in &block.children {
+      self.regions(child, regions)?;
+    }
+
+    Ok(())
+  }
+
+  /// Gets the parameter rows of a method, indexed by sequence number with the return value at 0.
+  fn params(&self, id: MethodDefRowId, sig: &MethodSig) -> Result<Vec<Option<ParamRow>>> {
+    let md = self.md;
+    let mut params = (0..=sig.params.len()).map(|_| None).collect::<Vec<_>>();
+
+    for id in md.tables().method_def_params(id)? {
+      let param = md.tables().params().read(id)?;
+      if let Some(slot) = params.get_mut(param.sequence as usize) {
+        *slot = Some(param);
+      }
+    }
+
+    Ok(params)
+  }
+
+  /// Gets the quoted names of the arguments of a method, `None` for unnamed parameters.
+  pub(crate) fn method_arg_names(&self, id: MethodDefRowId) -> Result<Vec<Option<String>>> {
+    let row = self.md.tables().method_defs().read(id)?;
+    let sig = self.md.method_sig(row.signature)?;
+    let params = self.params(id, &sig)?;
+
+    self.arg_names(&sig, &params)
+  }
+
+  fn arg_names(&self, sig: &MethodSig, params: &[Option<ParamRow>]) -> Result<Vec<Option<String>>> {
+    // Argument 0 is `this` in instance methods, otherwise the first parameter.
+    let mut arg_names = Vec::new();
+    if sig.has_this() {
+      arg_names.push(Some(String::from("this")));
+    }
+
+    for param in params.iter().skip(1) {
+      let name = match param {
+        Some(param) => self.md.strings().get(param.name)?,
+        None => "",
+      };
+
+      arg_names.push(match name.is_empty() {
+        true => None,
+        false => Some(quote(name).into_owned()),
+      });
+    }
+
+    Ok(arg_names)
+  }
+
+  /// Gets the `pinvokeimpl(...)` clause of a method.
+  fn pinvoke(&self, id: MethodDefRowId) -> Result<String> {
+    let md = self.md;
+    let Some(impl_map) = self.method_impl_maps[id.row() as usize - 1] else {
+      return Ok(String::from("pinvokeimpl() "));
+    };
+
+    let row = md.tables().impl_maps().read(impl_map)?;
+
+    let module = md.tables().module_refs().read(row.import_scope)?;
+    let module = md.strings().get(module.name)?;
+    let import = md.strings().get(row.import_name)?;
+
+    Ok(format!(
+      "pinvokeimpl(\"{}\" as \"{}\") ",
+      escape(module),
+      escape(import)
+    ))
+  }
+
+  /// Gets the generic parameter list of a method, e.g. `<TKey, TValue>`.
+  fn generic_params(&self, id: MethodDefRowId) -> Result<String> {
+    let md = self.md;
+    let mut params = Vec::new();
+
+    for &param in &self.method_generic_params[id.row() as usize - 1] {
+      let row = md.tables().generic_params().read(param)?;
+      params.push((row.number, quote(md.strings().get(row.name)?).into_owned()));
+    }
+
+    if params.is_empty() {
+      return Ok(String::new());
+    }
+
+    params.sort();
+    let names = params.into_iter().map(|(_, name)| name).collect::<Vec<_>>();
+
+    Ok(format!("<{}>", names.join(", ")))
+  }
+
+  /// Formats the inline operand of an instruction.
+  fn operand(&self, instruction: &Instruction, arg_names: &[Option<String>]) -> Result<String> {
+    Ok(match &instruction.operand {
+      Operand::None => String::new(),
+      Operand::Branch(target) => label(*target),
+      Operand::Switch(targets) => {
+        let labels = targets
+          .iter()
+          .map(|target| label(*target))
+          .collect::<Vec<_>>();
+        format!("({})", labels.join(", "))
+      }
+      Operand::Int8(value) => value.to_string(),
+      Operand::UInt8(value) => value.to_string(),
+      Operand::Int32(value) => format!("{:#x}", value),
+      Operand::Int64(value) => format!("{:#x}", value),
+      Operand::Float32(value) => match value.is_finite() {
+        true => format!("{:?}", value),
+        false => byte_array(&value.to_le_bytes()),
+      },
+      Operand::Float64(value) => match value.is_finite() {
+        true => format!("{:?}", value),
+        false => byte_array(&value.to_le_bytes()),
+      },
+      Operand::Local(index) => format!("V_{}", index),
+      Operand::Arg(index) => match arg_names.get(*index as usize) {
+        Some(Some(name)) => name.clone(),
+        _ => format!("A_{}", index),
+      },
+      Operand::Token(token) => match instruction.opcode.operand_type() {
+        OperandType::InlineString => {
+          let index = UserStringIndex::from_token(*token)?;
+          let value = self.md.user_strings().get(index)?;
+          let units = value.code_units().collect::<Vec<_>>();
+
+          match units.iter().all(|unit| (0x20..0x7f).contains(unit)) {
+            true => format!("\"{}\"", escape(&value.to_string())),
+            false => format!(
+              "bytearray {}",
+              byte_array(
+                &units
+                  .iter()
+                  .flat_map(|unit| unit.to_le_bytes())
+                  .collect::<Vec<_>>()
+              )
+            ),
+          }
+        }
+        OperandType::InlineSig => self.call_site(StandAloneSigRowId::from_row(
+          token & 0x00ff_ffff,
+          self.md.tables().header(),
+        )?)?,
+        _ => self.member_or_type(*token, instruction.opcode == OpCode::Ldtoken)?,
+      },
+    })
+  }
+
+  /// Formats a type, method or field token the way it's printed as an operand, e.g.
+  /// `class [System.Runtime]System.Type [System.Runtime]System.Object::GetType()`.
+  pub fn token(&self, token: u32) -> Result<String> {
+    self.member_or_type(token, false)
+  }
+
+  /// Formats the call site signature of `calli`, `int32 *(int32)`.
+  pub(crate) fn call_site(&self, id: StandAloneSigRowId) -> Result<String> {
+    let row = self.md.tables().stand_alone_sigs().read(id)?;
+
+    match self.md.stand_alone_sig(row.signature)? {
+      StandAloneSig::Method(sig) => self.method_sig(&sig, None, None),
+      StandAloneSig::Locals(_) => bail!("Expected a call site signature"),
+    }
+  }
+
+  /// Formats a type, method or field token, with a `field` or `method` prefix for `ldtoken`.
+  fn member_or_type(&self, token: u32, prefixed: bool) -> Result<String> {
+    let header = self.md.tables().header();
+    let row = token & 0x00ff_ffff;
+
+    let (prefix, text) = match (token >> 24) as usize {
+      0x01 => ("", self.type_ref(TypeRefRowId::from_row(row, header)?)?),
+      0x02 => ("", self.type_def(TypeDefRowId::from_row(row, header)?)?),
+      0x1b => ("", self.type_spec(TypeSpecRowId::from_row(row, header)?)?),
+      0x04 => ("field ", self.field(FieldRowId::from_row(row, header)?)?),
+      0x06 => (
+        "method ",
+        self.method_def(MethodDefRowId::from_row(row, header)?, None)?,
+      ),
+      0x0a => {
+        let id = MemberRefRowId::from_row(row, header)?;
+        let member = self.md.tables().member_refs().read(id)?;
+
+        match self.md.blobs().get(member.signature)?.first() {
+          Some(byte) if byte & 0x0f == SignatureKind::Field as u8 => {
+            ("field ", self.member_ref(id)?)
+          }
+          _ => ("method ", self.member_ref(id)?),
+        }
+      }
+      0x2b => (
+        "method ",
+        self.method_spec(MethodSpecRowId::from_row(row, header)?)?,
+      ),
+      _ => bail!("Unexpected token {:#010x}", token),
+    };
+
+    Ok(match prefixed {
+      true => format!("{}{}", prefix, text),
+      false => text,
+    })
+  }
+
+  /// Formats a field, `int32 Namespace.Type::name`.
+  fn field(&self, id: FieldRowId) -> Result<String> {
+    let md = self.md;
+    let row = md.tables().fields().read(id)?;
+    let sig = md.field_sig(row.signature)?;
+    let owner = match self
+      .field_owners
+      .get(id.row() as usize - 1)
+      .copied()
+      .flatten()
+    {
+      Some(owner) => self.type_def(owner)?,
+      None => bail!("Field {} has no owner", id.row()),
+    };
+
+    Ok(format!(
+      "{} {}::{}",
+      self.type_sig(&sig.ty)?,
+      owner,
+      quote(md.strings().get(row.name)?)
+    ))
+  }
+
+  /// Formats a method definition, `instance void Namespace.Type::name(int32)`.
+  fn method_def(&self, id: MethodDefRowId, generic_args: Option<&str>) -> Result<String> {
+    let md = self.md;
+    let row = md.tables().method_defs().read(id)?;
+    let sig = md.method_sig(row.signature)?;
+    let name = format!(
+      "{}::{}",
+      self.type_def(self.method_owner(id)?)?,
+      quote(md.strings().get(row.name)?)
+    );
+
+    self.method_sig(&sig, Some(&name), generic_args)
+  }
+
+  /// Formats a member reference, either a field or a method.
+  fn member_ref(&self, id: MemberRefRowId) -> Result<String> {
+    self.member_ref_with(id, None)
+  }
+
+  fn member_ref_with(&self, id: MemberRefRowId, generic_args: Option<&str>) -> Result<String> {
+    let md = self.md;
+    let row = md.tables().member_refs().read(id)?;
+    let name = quote(md.strings().get(row.name)?);
+
+    let owner = match row.class {
+      MemberRefParent::TypeDef(id) => self.type_def(id)?,
+      MemberRefParent::TypeRef(id) => self.type_ref(id)?,
+      MemberRefParent::TypeSpec(id) => self.type_spec(id)?,
+      MemberRefParent::ModuleRef(id) => self.module_ref(id)?,
+      // A vararg call site of a method defined in this module.
+      MemberRefParent::MethodDef(id) => self.type_def(self.method_owner(id)?)?,
+    };
+    let name = format!("{}::{}", owner, name);
+
+    let blob = md.blobs().get(row.signature)?;
+    match blob.first() {
+      Some(byte) if byte & 0x0f == SignatureKind::Field as u8 => {
+        let sig = md.field_sig(row.signature)?;
+        Ok(format!("{} {}", self.type_sig(&sig.ty)?, name))
+      }
+      _ => {
+        let sig = md.method_sig(row.signature)?;
+        self.method_sig(&sig, Some(&name), generic_args)
+      }
+    }
+  }
+
+  /// Formats a generic method instantiation, `void Namespace.Type::name<int32>(!!0)`.
+  fn method_spec(&self, id: MethodSpecRowId) -> Result<String> {
+    let md = self.md;
+    let row = md.tables().method_specs().read(id)?;
+    let sig = md.method_spec_sig(row.instantiation)?;
+    let args = sig
+      .args
+      .iter()
+      .map(|arg| self.type_sig(arg))
+      .collect::<Result<Vec<_>>>()?;
+    let args = format!("<{}>", args.join(", "));
+
+    match row.method {
+      MethodDefOrRef::MethodDef(id) => self.method_def(id, Some(&args)),
+      MethodDefOrRef::MemberRef(id) => self.member_ref_with(id, Some(&args)),
+    }
+  }
+
+  /// Formats a method signature, with the given name or as a function pointer, `int32 *(int32)`.
+  fn method_sig(
+    &self,
+    sig: &MethodSig,
+    name: Option<&str>,
+    generic_args: Option<&str>,
+  ) -> Result<String> {
+    let mut params = Vec::new();
+    for (i, param) in sig.params.iter().enumerate() {
+      if sig.sentinel == Some(i) {
+        params.push(String::from("..."));
+      }
+
+      params.push(self.type_sig(param)?);
+    }
+
+    Ok(format!(
+      "{}{} {}{}({})",
+      calling_convention(sig.header),
+      self.type_sig(&sig.ret)?,
+      name.unwrap_or("*"),
+      generic_args.unwrap_or_default(),
+      params.join(", ")
+    ))
+  }
+
+  /// Gets the type owning a method.
+  fn method_owner(&self, id: MethodDefRowId) -> Result<TypeDefRowId> {
+    match self
+      .method_owners
+      .get(id.row() as usize - 1)
+      .copied()
+      .flatten()
+    {
+      Some(owner) => Ok(owner),
+      None => bail!("Method {} has no owner", id.row()),
+    }
+  }
+
+  /// Formats a type definition or reference, `[mscorlib]System.Object`.
+  pub fn type_def_or_ref(&self, ty: TypeDefOrRef) -> Result<String> {
+    match ty {
+      TypeDefOrRef::TypeDef(id) => self.type_def(id),
+      TypeDefOrRef::TypeRef(id) => self.type_ref(id),
+      TypeDefOrRef::TypeSpec(id) => self.type_spec(id),
+    }
+  }
+
+  /// Formats a type definition, nested types are separated by a `/`.
+  fn type_def(&self, id: TypeDefRowId) -> Result<String> {
+    self.nested_type_def(id, 0)
+  }
+
+  /// Formats a type definition nested `depth` types deep.
+  fn nested_type_def(&self, id: TypeDefRowId, depth: usize) -> Result<String> {
+    if depth > MAX_NESTING {
+      bail!(
+        "TypeDef {} is nested deeper than {} types",
+        id.row(),
+        MAX_NESTING
+      );
+    }
+
+    let md = self.md;
+    let row = md.tables().type_defs().read(id)?;
+    let name = quote(md.strings().get(row.name)?);
+
+    match self
+      .enclosing_types
+      .get(id.row() as usize - 1)
+      .copied()
+      .flatten()
+    {
+      Some(enclosing) => Ok(format!(
+        "{}/{}",
+        self.nested_type_def(enclosing, depth + 1)?,
+        name
+      )),
+      None => Ok(dotted_name(md.strings().get(row.namespace)?, &name)),
+    }
+  }
+
+  /// Formats a type reference with its resolution scope, `[mscorlib]System.Object`.
+  fn type_ref(&self, id: TypeRefRowId) -> Result<String> {
+    self.nested_type_ref(id, 0)
+  }
+
+  /// Formats a type reference nested `depth` types deep.
+  fn nested_type_ref(&self, id: TypeRefRowId, depth: usize) -> Result<String> {
+    if depth > MAX_NESTING {
+      bail!(
+        "TypeRef {} is nested deeper than {} types",
+        id.row(),
+        MAX_NESTING
+      );
+    }
+
+    let md = self.md;
+    let row = md.tables().type_refs().read(id)?;
+    let name = quote(md.strings().get(row.name)?);
+    let full_name = dotted_name(md.strings().get(row.namespace)?, &name);
+
+    Ok(match row.resolution_scope {
+      Some(ResolutionScope::TypeRef(enclosing)) => {
+        format!("{}/{}", self.nested_type_ref(enclosing, depth + 1)?, name)
+      }
+      Some(ResolutionScope::AssemblyRef(id)) => {
+        let assembly = md.tables().assembly_refs().read(id)?;
+        format!(
+          "[{}]{}",
+          dotted_name(md.strings().get(assembly.name)?, ""),
+          full_name
+        )
+      }
+      Some(ResolutionScope::ModuleRef(id)) => format!("{}{}", self.module_ref(id)?, full_name),
+      Some(ResolutionScope::Module(_)) | None => full_name,
+    })
+  }
+
+  /// Formats a module reference scope, `[.module native.dll]`.
+  fn module_ref(&self, id: ModuleRefRowId) -> Result<String> {
+    let row = self.md.tables().module_refs().read(id)?;
+
+    Ok(format!(
+      "[.module {}]",
+      quote(self.md.strings().get(row.name)?)
+    ))
+  }
+
+  /// Formats a type specification.
+  fn type_spec(&self, id: TypeSpecRowId) -> Result<String> {
+    let row = self.md.tables().type_specs().read(id)?;
+
+    self.type_sig(&self.md.type_spec_sig(row.signature)?)
+  }
+
+  /// Formats a type signature, ``class [mscorlib]System.Collections.Generic.List`1<string>``.
+  pub fn type_sig(&self, ty: &TypeSig) -> Result<String> {
+    Ok(match ty {
+      TypeSig::Void => String::from("void"),
+      TypeSig::Boolean => String::from("bool"),
+      TypeSig::Char => String::from("char"),
+      TypeSig::I1 => String::from("int8"),
+      TypeSig::U1 => String::from("uint8"),
+      TypeSig::I2 => String::from("int16"),
+      TypeSig::U2 => String::from("uint16"),
+      TypeSig::I4 => String::from("int32"),
+      TypeSig::U4 => String::from("uint32"),
+      TypeSig::I8 => String::from("int64"),
+      TypeSig::U8 => String::from("uint64"),
+      TypeSig::R4 => String::from("float32"),
+      TypeSig::R8 => String::from("float64"),
+      TypeSig::String => String::from("string"),
+      TypeSig::I => String::from("native int"),
+      TypeSig::U => String::from("native uint"),
+      TypeSig::Object => String::from("object"),
+      TypeSig::TypedByRef => String::from("typedref"),
+      TypeSig::Ptr(ty) => format!("{}*", self.type_sig(ty)?),
+      TypeSig::ByRef(ty) => format!("{}&", self.type_sig(ty)?),
+      TypeSig::ValueType(ty) => format!("valuetype {}", self.type_def_or_ref(*ty)?),
+      TypeSig::Class(ty) => format!("class {}", self.type_def_or_ref(*ty)?),
+      TypeSig::Var(number) => format!("!{}", number),
+      TypeSig::MVar(number) => format!("!!{}", number),
+      TypeSig::GenericInst(inst) => {
+        let args = inst
+          .args
+          .iter()
+          .map(|arg| self.type_sig(arg))
+          .collect::<Result<Vec<_>>>()?;
+
+        format!(
+          "{} {}<{}>",
+          match inst.value_type {
+            true => "valuetype",
+            false => "class",
+          },
+          self.type_def_or_ref(inst.ty)?,
+          args.join(", ")
+        )
+      }
+      TypeSig::SzArray(ty) => format!("{}[]", self.type_sig(ty)?),
+      TypeSig::Array(array) => format!("{}[{}]", self.type_sig(&array.ty)?, shape(&array.shape)),
+      TypeSig::FnPtr(sig) => format!("method {}", self.method_sig(sig, None, None)?),
+      TypeSig::Modified(modifier, ty) => format!(
+        "{} {}({})",
+        self.type_sig(ty)?,
+        match modifier.required {
+          true => "modreq",
+          false => "modopt",
+        },
+        self.type_def_or_ref(modifier.ty)?
+      ),
+      TypeSig::Pinned(ty) => format!("{} pinned", self.type_sig(ty)?),
+    })
+  }
+}
+
+/// The disassembly of a method, rendered ildasm style with [Display](fmt::Display).
+#[derive(Debug, Clone)]
+pub struct MethodDisassembly {
+  /// The `.method` line.
+  header: String,
+  /// Whether the method is the entry point of the image.
+  entry_point: bool,
+  /// The simple name of the type owning the method.
+  owner: String,
+  /// The full name of the type owning the method.
+  owner_full_name: String,
+  /// The method name.
+  name: String,
+  /// The body, `None` for abstract and runtime provided methods.
+  body: Option<BodyDisassembly>,
+}
+
+impl MethodDisassembly {
+  /// Gets the `.method` line, e.g. `.method public hidebysig static void Main() cil managed`.
+  pub fn header(&self) -> &str {
+    &self.header
+  }
+
+  /// Gets the name of the method qualified by the full name of its type, e.g.
+  /// `Newtonsoft.Json.JsonConvert::SerializeObject`.
+  pub fn qualified_name(&self) -> String {
+    format!("{}::{}", self.owner_full_name, self.name)
+  }
+}
+
+#[derive(Debug, Clone)]
+struct BodyDisassembly {
+  max_stack: u16,
+  init_locals: bool,
+  locals: Option<Vec<String>>,
+  instructions: Vec<(Instruction, String)>,
+  /// The exception handling regions, ordered by offset with enclosing regions first.
+  regions: Vec<Region>,
+}
+
+/// A range of code printed as a `{ }` block.
+#[derive(Debug, Clone)]
+struct Region {
+  start: u32,
+  end: u32,
+  /// The line before the opening brace, `None` for the handler of a filter.
+  open: Option<String>,
+  /// The comment after the closing brace, `end {close}`.
+  close: &'static str,
+}
+
+impl fmt::Display for MethodDisassembly {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{}", self.header)?;
+    writeln!(f, "{{")?;
+
+    if self.entry_point {
+      writeln!(f, "  .entrypoint")?;
+    }
+
+    if let Some(body) = &self.body {
+      writeln!(f, "  .maxstack  {}", body.max_stack)?;
+
+      if let Some(locals) = &body.locals {
+        let init = match body.init_locals {
+          true => " init",
+          false => "",
+        };
+
+        writeln!(f, "  .locals{} ({})", init, locals.join(", "))?;
+      }
+
+      let mut regions = body.regions.iter().peekable();
+      let mut open = Vec::<&Region>::new();
+
+      for (instruction, operand) in &body.instructions {
+        let offset = instruction.offset;
+
+        while let Some(region) = open.last().filter(|region| region.end <= offset) {
+          writeln!(
+            f,
+            "{:indent$}}}  // end {}",
+            "",
+            region.close,
+            indent = open.len() * 2
+          )?;
+          open.pop();
+        }
+
+        while let Some(region) = regions.next_if(|region| region.start <= offset) {
+          let indent = (open.len() + 1) * 2;
+
+          match &region.open {
+            Some(line) => {
+              writeln!(f, "{:indent$}{}", "", line, indent = indent)?;
+              writeln!(f, "{:indent$}{{", "", indent = indent)?;
+            }
+            None => writeln!(f, "{:indent$}{{  // handler", "", indent = indent)?,
+          }
+
+          open.push(region);
+        }
+
+        let indent = (open.len() + 1) * 2;
+        match operand.is_empty() {
+          true => writeln!(
+            f,
+            "{:indent$}{}:  {}",
+            "",
+            label(offset),
+            instruction.opcode.name(),
+            indent = indent
+          )?,
+          false => writeln!(
+            f,
+            "{:indent$}{}:  {:<10} {}",
+            "",
+            label(offset),
+            instruction.opcode.name(),
+            operand,
+            indent = indent
+          )?,
+        }
+      }
+
+      while let Some(region) = open.pop() {
+        writeln!(
+          f,
+          "{:indent$}}}  // end {}",
+          "",
+          region.close,
+          indent = (open.len() + 1) * 2
+        )?;
+      }
+    }
+
+    write!(f, "}} // end of method {}::{}", self.owner, self.name)
+  }
+}
+
+/// Formats a branch target label.
+fn label(offset: u32) -> String {
+  format!("IL_{:04x}", offset)
+}
+
+/// Formats bytes as an ilasm byte list, `(00 00 C0 FF)`.
+fn byte_array(bytes: &[u8]) -> String {
+  let bytes = bytes
+    .iter()
+    .map(|byte| format!("{:02X}", byte))
+    .collect::<Vec<_>>();
+
+  format!("({})", bytes.join(" "))
+}
+
+/// Escapes a string for a quoted ilasm string literal.
+fn escape(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+
+  for c in value.chars() {
+    match c {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+/// Quotes a name if it isn't a valid ilasm identifier, `'<Module>'`.
+fn quote(name: &str) -> Cow<'_, str> {
+  let is_id_char = |c: char| c.is_ascii_alphanumeric() || "_$@?`".contains(c);
+  let is_id = |name: &str| {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+      && name.chars().all(is_id_char)
+      && KEYWORDS.binary_search(&name).is_err()
+      && OpCode::from_name(name).is_none()
+  };
+
+  match name == ".ctor" || name == ".cctor" || is_id(name) {
+    true => Cow::Borrowed(name),
+    false => Cow::Owned(format!(
+      "'{}'",
+      name.replace('\\', "\\\\").replace('\'', "\\'")
+    )),
+  }
+}
+
+/// Joins a namespace and an already quoted name, quoting the namespace components.
+fn dotted_name(namespace: &str, name: &str) -> String {
+  let components = namespace.split('.').map(quote).collect::<Vec<_>>();
+
+  match (namespace.is_empty(), name.is_empty()) {
+    (true, _) => name.into(),
+    (false, true) => components.join("."),
+    (false, false) => format!("{}.{}", components.join("."), name),
+  }
+}
+
+/// Formats the dimensions of a general array, `0...5,0...`.
+fn shape(shape: &ArrayShape) -> String {
+  let dimensions = (0..shape.rank as usize)
+    .map(|i| match (shape.lower_bounds.get(i), shape.sizes.get(i)) {
+      (Some(lower), Some(size)) if *size > 0 => {
+        format!("{}...{}", lower, *lower + *size as i32 - 1)
+      }
+      (Some(lower), _) => format!("{}...", lower),
+      (None, Some(size)) => size.to_string(),
+      (None, None) => String::new(),
+    })
+    .collect::<Vec<_>>();
+
+  dimensions.join(",")
+}
+
+/// Formats the calling convention of a method signature, `instance vararg `.
+fn calling_convention(header: SignatureHeader) -> String {
+  let mut convention = String::new();
+
+  if header.attributes.contains(SignatureAttributes::HAS_THIS) {
+    convention.push_str("instance ");
+  }
+  if header
+    .attributes
+    .contains(SignatureAttributes::EXPLICIT_THIS)
+  {
+    convention.push_str("explicit ");
+  }
+
+  convention.push_str(match header.kind {
+    SignatureKind::VarArg => "vararg ",
+    SignatureKind::C => "unmanaged cdecl ",
+    SignatureKind::StdCall => "unmanaged stdcall ",
+    SignatureKind::ThisCall => "unmanaged thiscall ",
+    SignatureKind::FastCall => "unmanaged fastcall ",
+    SignatureKind::Unmanaged => "unmanaged ",
+    _ => "",
+  });
+
+  convention
+}
+
+/// Formats method flags as ilasm keywords, `public hidebysig static `.
+fn method_flags(flags: MethodAttributes) -> String {
+  let mut keywords = String::from(match flags & MethodAttributes::MEMBER_ACCESS_MASK {
+    MethodAttributes::PRIVATE => "private ",
+    MethodAttributes::FAM_AND_ASSEM => "famandassem ",
+    MethodAttributes::ASSEM => "assembly ",
+    MethodAttributes::FAMILY => "family ",
+    MethodAttributes::FAM_OR_ASSEM => "famorassem ",
+    MethodAttributes::PUBLIC => "public ",
+    _ => "privatescope ",
+  });
+
+  for (flag, keyword) in [
+    (MethodAttributes::HIDE_BY_SIG, "hidebysig "),
+    (MethodAttributes::NEW_SLOT, "newslot "),
+    (MethodAttributes::SPECIAL_NAME, "specialname "),
+    (MethodAttributes::RT_SPECIAL_NAME, "rtspecialname "),
+    (MethodAttributes::STRICT, "strict "),
+    (MethodAttributes::ABSTRACT, "abstract "),
+    (MethodAttributes::VIRTUAL, "virtual "),
+    (MethodAttributes::FINAL, "final "),
+    (MethodAttributes::STATIC, "static "),
+    (MethodAttributes::UNMANAGED_EXPORT, "unmanagedexp "),
+    (MethodAttributes::REQUIRE_SEC_OBJECT, "reqsecobj "),
+  ] {
+    if flags.contains(flag) {
+      keywords.push_str(keyword);
+    }
+  }
+
+  keywords
+}
+
+/// Formats method implementation flags as ilasm keywords, `cil managed`.
+fn impl_flags(flags: MethodImplAttributes) -> String {
+  let mut keywords = vec![
+    match flags & MethodImplAttributes::CODE_TYPE_MASK {
+      MethodImplAttributes::NATIVE => "native",
+      MethodImplAttributes::OPTIL => "optil",
+      MethodImplAttributes::RUNTIME => "runtime",
+      _ => "cil",
+    },
+    match flags.contains(MethodImplAttributes::UNMANAGED) {
+      true => "unmanaged",
+      false => "managed",
+    },
+  ];
+
+  for (flag, keyword) in [
+    (MethodImplAttributes::FORWARD_REF, "forwardref"),
+    (MethodImplAttributes::PRESERVE_SIG, "preservesig"),
+    (MethodImplAttributes::INTERNAL_CALL, "internalcall"),
+    (MethodImplAttributes::SYNCHRONIZED, "synchronized"),
+    (MethodImplAttributes::NO_INLINING, "noinlining"),
+    (
+      MethodImplAttributes::AGGRESSIVE_INLINING,
+      "aggressiveinlining",
+    ),
+    (MethodImplAttributes::NO_OPTIMIZATION, "nooptimization"),
+  ] {
+    if flags.contains(flag) {
+      keywords.push(keyword);
+    }
+  }
+
+  keywords.join(" ")
+}
+
+impl<'a> Md<'a> {
+  /// Disassembles a single method, use a [Disassembler] when disassembling more than one.
+  pub fn disassemble_method(&self, id: MethodDefRowId) -> Result<MethodDisassembly> {
+    Disassembler::new(self)?.method(id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{calling_convention, impl_flags, method_flags, quote, shape, Disassembler, KEYWORDS};
+  use crate::ecma335::{
+    signatures::{ArrayShape, SignatureAttributes, SignatureHeader, SignatureKind},
+    tables::{
+      flags::{MethodAttributes, MethodImplAttributes},
+      NestedClassRow, Row, TypeDefOrRef, TypeDefRow, TypeDefRowId, TypeRefRow, TypeRefRowId,
+    },
+    Md,
+  };
+  use alloc::vec;
+
+  #[test]
+  fn cyclic_types() {
+    #[rustfmt::skip]
+    let type_defs = [
+      // flags, name, namespace, extends, field_list, method_list
+      0u8, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+      0u8, 0, 0, 0, 3, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+    ];
+    // A and B are nested in each other.
+    let nested_classes = [1u8, 0, 2, 0, 2, 0, 1, 0];
+    // TypeRef 1 is resolved in itself.
+    let type_refs = [0x07u8, 0x00, 0x01, 0x00, 0x00, 0x00];
+    let root = Md::root_with_tables(
+      &[
+        (TypeRefRow::ID, 1, &type_refs),
+        (TypeDefRow::ID, 2, &type_defs),
+        (NestedClassRow::ID, 2, &nested_classes),
+      ],
+      b"\0A\0B\0",
+      &[],
+    );
+    let md = Md::from_cli_data(&root).unwrap();
+    let header = md.tables().header();
+    let disassembler = Disassembler::new(&md).unwrap();
+
+    let ty = TypeDefOrRef::TypeDef(TypeDefRowId::new(1, header).unwrap());
+    assert!(disassembler.type_def_or_ref(ty).is_err());
+    let ty = TypeDefOrRef::TypeRef(TypeRefRowId::new(1, header).unwrap());
+    assert!(disassembler.type_def_or_ref(ty).is_err());
+  }
+
+  #[test]
+  fn keywords_are_sorted() {
+    assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+  }
+
+  #[test]
+  fn quote_names() {
+    assert_eq!(quote("String"), "String");
+    assert_eq!(quote("List`1"), "List`1");
+    assert_eq!(quote(".ctor"), ".ctor");
+    assert_eq!(quote("value"), "'value'");
+    assert_eq!(quote("add"), "'add'");
+    assert_eq!(quote("<Module>"), "'<Module>'");
+    assert_eq!(quote("1st"), "'1st'");
+    assert_eq!(quote("it's"), "'it\\'s'");
+  }
+
+  #[test]
+  fn array_shapes() {
+    let cases = [
+      (
+        ArrayShape {
+          rank: 2,
+          sizes: vec![],
+          lower_bounds: vec![],
+        },
+        ",",
+      ),
+      (
+        ArrayShape {
+          rank: 1,
+          sizes: vec![5],
+          lower_bounds: vec![],
+        },
+        "5",
+      ),
+      (
+        ArrayShape {
+          rank: 2,
+          sizes: vec![4],
+          lower_bounds: vec![1, 0],
+        },
+        "1...4,0...",
+      ),
+    ];
+
+    for (array, expected) in cases {
+      assert_eq!(shape(&array), expected);
+    }
+  }
+
+  #[test]
+  fn flags_as_keywords() {
+    assert_eq!(
+      method_flags(
+        MethodAttributes::PUBLIC
+          | MethodAttributes::HIDE_BY_SIG
+          | MethodAttributes::NEW_SLOT
+          | MethodAttributes::VIRTUAL
+          | MethodAttributes::FINAL
+      ),
+      "public hidebysig newslot virtual final "
+    );
+    assert_eq!(
+      impl_flags(MethodImplAttributes::RUNTIME | MethodImplAttributes::NO_INLINING),
+      "runtime managed noinlining"
+    );
+    assert_eq!(
+      calling_convention(SignatureHeader {
+        kind: SignatureKind::VarArg,
+        attributes: SignatureAttributes::HAS_THIS,
+      }),
+      "instance vararg "
+    );
+  }
+}
@@ -0,0 +1,344 @@
+//! CIL instruction decoding (III.1.2, III.1.7).
+
+use super::opcodes::{OpCode, OperandType, TWO_BYTE_PREFIX};
+use crate::ecma335::{body::MethodBody, token::Token};
+use alloc::{format, vec::Vec};
+use anyhow::{anyhow, bail, Context, Result};
+use scroll::{Pread, LE};
+
+/// A decoded inline operand.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+  /// No operand.
+  None,
+  /// The absolute offset of a branch target.
+  Branch(u32),
+  /// The absolute offsets of the targets of a `switch`.
+  Switch(Vec<u32>),
+  /// The immediate of `ldc.i4.s`.
+  Int8(i8),
+  /// The alignment of `unaligned.` or the checks skipped by `no.`.
+  UInt8(u8),
+  /// The immediate of `ldc.i4`.
+  Int32(i32),
+  /// The immediate of `ldc.i8`.
+  Int64(i64),
+  /// The immediate of `ldc.r4`.
+  Float32(f32),
+  /// The immediate of `ldc.r8`.
+  Float64(f64),
+  /// The index of a local variable.
+  Local(u16),
+  /// The index of an argument, `this` is argument 0 in instance methods.
+  Arg(u16),
+  /// A metadata token, or a `#US` token for `ldstr`.
+  Token(u32),
+}
+
+/// A decoded instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+  /// The offset of the instruction from the start of the code.
+  pub offset: u32,
+  /// The size in bytes of the instruction, including the operand.
+  pub size: u32,
+  /// The opcode.
+  pub opcode: OpCode,
+  /// The inline operand.
+  pub operand: Operand,
+}
+
+impl Instruction {
+  /// Gets the offset of the instruction following this one.
+  pub fn next_offset(&self) -> u32 {
+    self.offset + self.size
+  }
+
+  /// Gets the index of the local variable loaded, stored or addressed, including the short forms
+  /// like `ldloc.0`.
+  pub fn local(&self) -> Option<u16> {
+    match (self.opcode, &self.operand) {
+      (OpCode::Ldloc0 | OpCode::Stloc0, _) => Some(0),
+      (OpCode::Ldloc1 | OpCode::Stloc1, _) => Some(1),
+      (OpCode::Ldloc2 | OpCode::Stloc2, _) => Some(2),
+      (OpCode::Ldloc3 | OpCode::Stloc3, _) => Some(3),
+      (_, Operand::Local(index)) => Some(*index),
+      _ => None,
+    }
+  }
+
+  /// Gets the metadata or `#US` token operand.
+  pub fn token(&self) -> Option<Token> {
+    match self.operand {
+      Operand::Token(token) => Some(token.into()),
+      _ => None,
+    }
+  }
+
+  /// Gets the index of the argument loaded, stored or addressed, including the short forms like
+  /// `ldarg.0`.
+  pub fn arg(&self) -> Option<u16> {
+    match (self.opcode, &self.operand) {
+      (OpCode::Ldarg0, _) => Some(0),
+      (OpCode::Ldarg1, _) => Some(1),
+      (OpCode::Ldarg2, _) => Some(2),
+      (OpCode::Ldarg3, _) => Some(3),
+      (_, Operand::Arg(index)) => Some(*index),
+      _ => None,
+    }
+  }
+}
+
+/// Determines if a variable opcode refers to a local, otherwise it refers to an argument.
+pub(crate) fn is_local(opcode: OpCode) -> bool {
+  matches!(
+    opcode,
+    OpCode::LdlocS
+      | OpCode::LdlocaS
+      | OpCode::StlocS
+      | OpCode::Ldloc
+      | OpCode::Ldloca
+      | OpCode::Stloc
+  )
+}
+
+/// An iterator decoding the instructions of a method body in order.
+///
+/// Decoding stops after the first malformed instruction.
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+  code: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Instructions<'a> {
+  /// Creates an iterator over the instructions in `code`.
+  pub fn new(code: &'a [u8]) -> Self {
+    Self { code, offset: 0 }
+  }
+
+  /// Decodes the instruction at the current offset.
+  fn read(&mut self) -> Result<Instruction> {
+    let start = self.offset;
+    let offset = &mut self.offset;
+    let code = self.code;
+
+    let value = match code.gread::<u8>(offset)? {
+      TWO_BYTE_PREFIX => 0xfe00 | code.gread::<u8>(offset)? as u16,
+      value => value as u16,
+    };
+
+    let opcode = OpCode::from_value(value).ok_or_else(|| anyhow!("Unknown opcode {:#x}", value))?;
+
+    let operand = match opcode.operand_type() {
+      OperandType::InlineNone => Operand::None,
+      OperandType::ShortInlineBrTarget => {
+        let delta = code.gread::<i8>(offset)?;
+        Operand::Branch(branch_target(*offset, delta.into())?)
+      }
+      OperandType::InlineBrTarget => {
+        let delta = code.gread_with::<i32>(offset, LE)?;
+        Operand::Branch(branch_target(*offset, delta)?)
+      }
+      OperandType::ShortInlineI => match opcode {
+        OpCode::LdcI4S => Operand::Int8(code.gread(offset)?),
+        _ => Operand::UInt8(code.gread(offset)?),
+      },
+      OperandType::InlineI => Operand::Int32(code.gread_with(offset, LE)?),
+      OperandType::InlineI8 => Operand::Int64(code.gread_with(offset, LE)?),
+      OperandType::ShortInlineR => Operand::Float32(code.gread_with(offset, LE)?),
+      OperandType::InlineR => Operand::Float64(code.gread_with(offset, LE)?),
+      OperandType::ShortInlineVar | OperandType::InlineVar => {
+        let index = match opcode.operand_type() {
+          OperandType::ShortInlineVar => code.gread::<u8>(offset)?.into(),
+          _ => code.gread_with::<u16>(offset, LE)?,
+        };
+
+        match is_local(opcode) {
+          true => Operand::Local(index),
+          false => Operand::Arg(index),
+        }
+      }
+      OperandType::InlineMethod
+      | OperandType::InlineField
+      | OperandType::InlineType
+      | OperandType::InlineTok
+      | OperandType::InlineString
+      | OperandType::InlineSig => Operand::Token(code.gread_with(offset, LE)?),
+      OperandType::InlineSwitch => {
+        let count = code.gread_with::<u32>(offset, LE)? as usize;
+
+        // Targets are relative to the end of the whole instruction.
+        let end = count
+          .checked_mul(4)
+          .and_then(|size| offset.checked_add(size))
+          .filter(|end| *end <= code.len())
+          .ok_or_else(|| anyhow!("Malformed switch, {} targets", count))?;
+
+        let targets = (0..count)
+          .map(|_| branch_target(end, code.gread_with(offset, LE)?))
+          .collect::<Result<Vec<_>>>()?;
+
+        Operand::Switch(targets)
+      }
+    };
+
+    Ok(Instruction {
+      offset: start as u32,
+      size: (*offset - start) as u32,
+      opcode,
+      operand,
+    })
+  }
+}
+
+/// Resolves a branch offset relative to the end of the instruction.
+fn branch_target(next: usize, delta: i32) -> Result<u32> {
+  match u32::try_from(next as i64 + delta as i64) {
+    Ok(target) => Ok(target),
+    Err(_) => bail!("Branch target {:+} from {:#x} is out of range", delta, next),
+  }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+  type Item = Result<Instruction>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.offset >= self.code.len() {
+      return None;
+    }
+
+    let start = self.offset;
+    let instruction = self
+      .read()
+      .with_context(|| format!("Instruction at IL_{:04x}", start));
+
+    if instruction.is_err() {
+      self.offset = self.code.len();
+    }
+
+    Some(instruction)
+  }
+}
+
+impl<'a> MethodBody<'a> {
+  /// Gets an iterator decoding the instructions of the body.
+  pub fn instructions(&self) -> Instructions<'a> {
+    Instructions::new(self.code)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Instruction, Instructions, Operand};
+  use crate::il::opcodes::OpCode;
+  use alloc::{vec, vec::Vec};
+
+  fn decode(code: &[u8]) -> Vec<Instruction> {
+    Instructions::new(code)
+      .collect::<anyhow::Result<Vec<_>>>()
+      .unwrap()
+  }
+
+  #[test]
+  fn decode_operands() {
+    #[rustfmt::skip]
+    let code = [
+      // IL_0000: ldarg.1
+      0x03,
+      // IL_0001: ldc.i4.s -2
+      0x1f, 0xfe,
+      // IL_0003: stloc.s 4
+      0x13, 0x04,
+      // IL_0005: ldarg 0x102
+      0xfe, 0x09, 0x02, 0x01,
+      // IL_0009: ldc.r8 1.5
+      0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x3f,
+      // IL_0012: call 0x0a000001
+      0x28, 0x01, 0x00, 0x00, 0x0a,
+      // IL_0017: brtrue.s IL_0012
+      0x2d, 0xf9,
+      // IL_0019: unaligned. 1
+      0xfe, 0x12, 0x01,
+      // IL_001c: ret
+      0x2a,
+    ];
+
+    let instructions = decode(&code);
+    let decoded = instructions
+      .iter()
+      .map(|instruction| {
+        (
+          instruction.offset,
+          instruction.opcode,
+          instruction.operand.clone(),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      decoded,
+      [
+        (0x00, OpCode::Ldarg1, Operand::None),
+        (0x01, OpCode::LdcI4S, Operand::Int8(-2)),
+        (0x03, OpCode::StlocS, Operand::Local(4)),
+        (0x05, OpCode::Ldarg, Operand::Arg(0x102)),
+        (0x09, OpCode::LdcR8, Operand::Float64(1.5)),
+        (0x12, OpCode::Call, Operand::Token(0x0a000001)),
+        (0x17, OpCode::BrtrueS, Operand::Branch(0x12)),
+        (0x19, OpCode::Unaligned, Operand::UInt8(1)),
+        (0x1c, OpCode::Ret, Operand::None),
+      ]
+    );
+
+    assert_eq!(instructions[0].arg(), Some(1));
+    assert_eq!(instructions[2].local(), Some(4));
+    assert_eq!(instructions[3].arg(), Some(0x102));
+    assert_eq!(instructions[8].next_offset(), code.len() as u32);
+  }
+
+  #[test]
+  fn decode_switch() {
+    #[rustfmt::skip]
+    let code = [
+      // IL_0000: switch (IL_0011, IL_000f)
+      0x45, 0x02, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+      // IL_000d: br.s IL_0011
+      0x2b, 0x02,
+      // IL_000f: nop
+      0x00,
+      // IL_0010: nop
+      0x00,
+      // IL_0011: ret
+      0x2a,
+    ];
+
+    let instructions = decode(&code);
+
+    assert_eq!(instructions[0].size, 13);
+    assert_eq!(instructions[0].operand, Operand::Switch(vec![0x11, 0x0f]));
+    assert_eq!(instructions[1].operand, Operand::Branch(0x11));
+  }
+
+  #[test]
+  fn decode_malformed() {
+    let cases: &[&[u8]] = &[
+      // Unknown opcode.
+      &[0x24],
+      &[0xfe, 0x08],
+      // Truncated operand.
+      &[0x20, 0x00, 0x00],
+      // Branch before the start of the code.
+      &[0x2b, 0xf0],
+      // Switch count larger than the code.
+      &[0x45, 0xff, 0xff, 0xff, 0xff],
+    ];
+
+    for code in cases {
+      let mut instructions = Instructions::new(code);
+
+      assert!(instructions.next().unwrap().is_err());
+      assert!(instructions.next().is_none());
+    }
+  }
+}
@@ -0,0 +1,540 @@
+//! The CIL opcode table (III.1.2.1, III.2, III.3, III.4).
+//!
+//! Every opcode is either a single byte or a `0xFE` prefix byte followed by a second byte.  The
+//! table records each opcode's mnemonic, the type of its inline operand, the number and kind of
+//! stack items it pops and pushes, and how it affects control flow.
+
+/// The type of an instruction's inline operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperandType {
+  /// No operand.
+  InlineNone,
+  /// A signed 8 bit branch offset.
+  ShortInlineBrTarget,
+  /// A signed 32 bit branch offset.
+  InlineBrTarget,
+  /// An 8 bit integer.
+  ShortInlineI,
+  /// A 32 bit integer.
+  InlineI,
+  /// A 64 bit integer.
+  InlineI8,
+  /// A 32 bit float.
+  ShortInlineR,
+  /// A 64 bit float.
+  InlineR,
+  /// An 8 bit local or argument index.
+  ShortInlineVar,
+  /// A 16 bit local or argument index.
+  InlineVar,
+  /// A `MethodDef`, `MemberRef` or `MethodSpec` token.
+  InlineMethod,
+  /// A `Field` or `MemberRef` token.
+  InlineField,
+  /// A `TypeDef`, `TypeRef` or `TypeSpec` token.
+  InlineType,
+  /// A type, method or field token.
+  InlineTok,
+  /// A `#US` string token.
+  InlineString,
+  /// A `StandAloneSig` token.
+  InlineSig,
+  /// A 32 bit count followed by that many signed 32 bit branch offsets.
+  InlineSwitch,
+}
+
+impl OperandType {
+  /// Gets the size in bytes of the operand, `None` for switch tables whose size depends on the
+  /// number of targets.
+  pub fn size(&self) -> Option<usize> {
+    match self {
+      Self::InlineNone => Some(0),
+      Self::ShortInlineBrTarget | Self::ShortInlineI | Self::ShortInlineVar => Some(1),
+      Self::InlineVar => Some(2),
+      Self::InlineBrTarget
+      | Self::InlineI
+      | Self::ShortInlineR
+      | Self::InlineMethod
+      | Self::InlineField
+      | Self::InlineType
+      | Self::InlineTok
+      | Self::InlineString
+      | Self::InlineSig => Some(4),
+      Self::InlineI8 | Self::InlineR => Some(8),
+      Self::InlineSwitch => None,
+    }
+  }
+}
+
+/// How an instruction affects control flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlowControl {
+  /// Execution continues with the next instruction.
+  Next,
+  /// A debugger breakpoint, execution continues with the next instruction.
+  Break,
+  /// A method call, execution continues with the next instruction once the callee returns.
+  Call,
+  /// Control leaves the method or the current handler, `ret`, `endfinally` and `endfilter`.
+  Return,
+  /// An unconditional branch.
+  Branch,
+  /// A conditional branch, execution may continue with the next instruction.
+  CondBranch,
+  /// An exception is thrown.
+  Throw,
+  /// A prefix modifying the following instruction.
+  Meta,
+}
+
+/// The stack items popped by an instruction, named after `System.Reflection.Emit.StackBehaviour`.
+///
+/// `I` is an integer or native integer, `I8` a 64 bit integer, `R4`/`R8` a float, `Ref` an object
+/// reference and `1` any value.  Items are listed from the deepest to the top of the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StackPop {
+  Pop0,
+  Pop1,
+  Pop1Pop1,
+  PopI,
+  PopIPop1,
+  PopIPopI,
+  PopIPopI8,
+  PopIPopR4,
+  PopIPopR8,
+  PopIPopIPopI,
+  PopRef,
+  PopRefPop1,
+  PopRefPopI,
+  PopRefPopIPopI,
+  PopRefPopIPopI8,
+  PopRefPopIPopR4,
+  PopRefPopIPopR8,
+  PopRefPopIPopRef,
+  PopRefPopIPop1,
+  /// The number of items depends on the method signature, calls and `ret`.
+  VarPop,
+}
+
+impl StackPop {
+  /// Gets the number of items popped, `None` if it depends on the method signature.
+  pub fn count(&self) -> Option<usize> {
+    match self {
+      Self::Pop0 => Some(0),
+      Self::Pop1 | Self::PopI | Self::PopRef => Some(1),
+      Self::Pop1Pop1
+      | Self::PopIPop1
+      | Self::PopIPopI
+      | Self::PopIPopI8
+      | Self::PopIPopR4
+      | Self::PopIPopR8
+      | Self::PopRefPop1
+      | Self::PopRefPopI => Some(2),
+      Self::PopIPopIPopI
+      | Self::PopRefPopIPopI
+      | Self::PopRefPopIPopI8
+      | Self::PopRefPopIPopR4
+      | Self::PopRefPopIPopR8
+      | Self::PopRefPopIPopRef
+      | Self::PopRefPopIPop1 => Some(3),
+      Self::VarPop => None,
+    }
+  }
+}
+
+/// The stack items pushed by an instruction, named after `System.Reflection.Emit.StackBehaviour`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StackPush {
+  Push0,
+  Push1,
+  Push1Push1,
+  PushI,
+  PushI8,
+  PushR4,
+  PushR8,
+  PushRef,
+  /// The number of items depends on the method signature, calls.
+  VarPush,
+}
+
+impl StackPush {
+  /// Gets the number of items pushed, `None` if it depends on the method signature.
+  pub fn count(&self) -> Option<usize> {
+    match self {
+      Self::Push0 => Some(0),
+      Self::Push1Push1 => Some(2),
+      Self::VarPush => None,
+      _ => Some(1),
+    }
+  }
+}
+
+macro_rules! opcodes {
+  (
+    $(
+      $(#[$attr:meta])*
+      $variant:ident = $value:literal, $name:literal,
+        $operand:ident, $pop:ident, $push:ident, $flow:ident;
+    )*
+  ) => {
+    /// A CIL opcode, the discriminant is the encoded value with two byte opcodes including the
+    /// `0xFE` prefix in the high byte.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[repr(u16)]
+    pub enum OpCode {
+      $(
+        $(#[$attr])*
+        #[doc = concat!("`", $name, "`")]
+        $variant = $value,
+      )*
+    }
+
+    impl OpCode {
+      /// Every opcode, in order of its encoded value.
+      pub const ALL: &'static [OpCode] = &[$(Self::$variant),*];
+
+      /// Gets the opcode with the given encoded value.
+      pub fn from_value(value: u16) -> Option<Self> {
+        match value {
+          $($value => Some(Self::$variant),)*
+          _ => None,
+        }
+      }
+
+      /// Gets the opcode with the given mnemonic, e.g. `ldarg.0` or `constrained.`.
+      pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+          $($name => Some(Self::$variant),)*
+          _ => None,
+        }
+      }
+
+      /// Gets the mnemonic, e.g. `ldarg.0` or `constrained.`.
+      pub fn name(&self) -> &'static str {
+        match self {
+          $(Self::$variant => $name,)*
+        }
+      }
+
+      /// Gets the type of the inline operand.
+      pub fn operand_type(&self) -> OperandType {
+        match self {
+          $(Self::$variant => OperandType::$operand,)*
+        }
+      }
+
+      /// Gets the stack items popped.
+      pub fn stack_pop(&self) -> StackPop {
+        match self {
+          $(Self::$variant => StackPop::$pop,)*
+        }
+      }
+
+      /// Gets the stack items pushed.
+      pub fn stack_push(&self) -> StackPush {
+        match self {
+          $(Self::$variant => StackPush::$push,)*
+        }
+      }
+
+      /// Gets how the opcode affects control flow.
+      pub fn flow_control(&self) -> FlowControl {
+        match self {
+          $(Self::$variant => FlowControl::$flow,)*
+        }
+      }
+    }
+  };
+}
+
+/// The first byte of two byte opcodes.
+pub const TWO_BYTE_PREFIX: u8 = 0xfe;
+
+impl OpCode {
+  /// Gets the encoded value.
+  pub fn value(&self) -> u16 {
+    *self as u16
+  }
+
+  /// Gets the size in bytes of the encoded opcode.
+  pub fn size(&self) -> usize {
+    match self.value() >> 8 {
+      0 => 1,
+      _ => 2,
+    }
+  }
+
+  /// Determines if the opcode is a prefix, e.g. `constrained.` or `tail.`.
+  pub fn is_prefix(&self) -> bool {
+    self.flow_control() == FlowControl::Meta
+  }
+}
+
+opcodes! {
+  Nop = 0x00, "nop", InlineNone, Pop0, Push0, Next;
+  Break = 0x01, "break", InlineNone, Pop0, Push0, Break;
+  Ldarg0 = 0x02, "ldarg.0", InlineNone, Pop0, Push1, Next;
+  Ldarg1 = 0x03, "ldarg.1", InlineNone, Pop0, Push1, Next;
+  Ldarg2 = 0x04, "ldarg.2", InlineNone, Pop0, Push1, Next;
+  Ldarg3 = 0x05, "ldarg.3", InlineNone, Pop0, Push1, Next;
+  Ldloc0 = 0x06, "ldloc.0", InlineNone, Pop0, Push1, Next;
+  Ldloc1 = 0x07, "ldloc.1", InlineNone, Pop0, Push1, Next;
+  Ldloc2 = 0x08, "ldloc.2", InlineNone, Pop0, Push1, Next;
+  Ldloc3 = 0x09, "ldloc.3", InlineNone, Pop0, Push1, Next;
+  Stloc0 = 0x0a, "stloc.0", InlineNone, Pop1, Push0, Next;
+  Stloc1 = 0x0b, "stloc.1", InlineNone, Pop1, Push0, Next;
+  Stloc2 = 0x0c, "stloc.2", InlineNone, Pop1, Push0, Next;
+  Stloc3 = 0x0d, "stloc.3", InlineNone, Pop1, Push0, Next;
+  LdargS = 0x0e, "ldarg.s", ShortInlineVar, Pop0, Push1, Next;
+  LdargaS = 0x0f, "ldarga.s", ShortInlineVar, Pop0, PushI, Next;
+  StargS = 0x10, "starg.s", ShortInlineVar, Pop1, Push0, Next;
+  LdlocS = 0x11, "ldloc.s", ShortInlineVar, Pop0, Push1, Next;
+  LdlocaS = 0x12, "ldloca.s", ShortInlineVar, Pop0, PushI, Next;
+  StlocS = 0x13, "stloc.s", ShortInlineVar, Pop1, Push0, Next;
+  Ldnull = 0x14, "ldnull", InlineNone, Pop0, PushRef, Next;
+  LdcI4M1 = 0x15, "ldc.i4.m1", InlineNone, Pop0, PushI, Next;
+  LdcI40 = 0x16, "ldc.i4.0", InlineNone, Pop0, PushI, Next;
+  LdcI41 = 0x17, "ldc.i4.1", InlineNone, Pop0, PushI, Next;
+  LdcI42 = 0x18, "ldc.i4.2", InlineNone, Pop0, PushI, Next;
+  LdcI43 = 0x19, "ldc.i4.3", InlineNone, Pop0, PushI, Next;
+  LdcI44 = 0x1a, "ldc.i4.4", InlineNone, Pop0, PushI, Next;
+  LdcI45 = 0x1b, "ldc.i4.5", InlineNone, Pop0, PushI, Next;
+  LdcI46 = 0x1c, "ldc.i4.6", InlineNone, Pop0, PushI, Next;
+  LdcI47 = 0x1d, "ldc.i4.7", InlineNone, Pop0, PushI, Next;
+  LdcI48 = 0x1e, "ldc.i4.8", InlineNone, Pop0, PushI, Next;
+  LdcI4S = 0x1f, "ldc.i4.s", ShortInlineI, Pop0, PushI, Next;
+  LdcI4 = 0x20, "ldc.i4", InlineI, Pop0, PushI, Next;
+  LdcI8 = 0x21, "ldc.i8", InlineI8, Pop0, PushI8, Next;
+  LdcR4 = 0x22, "ldc.r4", ShortInlineR, Pop0, PushR4, Next;
+  LdcR8 = 0x23, "ldc.r8", InlineR, Pop0, PushR8, Next;
+  Dup = 0x25, "dup", InlineNone, Pop1, Push1Push1, Next;
+  Pop = 0x26, "pop", InlineNone, Pop1, Push0, Next;
+  Jmp = 0x27, "jmp", InlineMethod, Pop0, Push0, Call;
+  Call = 0x28, "call", InlineMethod, VarPop, VarPush, Call;
+  Calli = 0x29, "calli", InlineSig, VarPop, VarPush, Call;
+  Ret = 0x2a, "ret", InlineNone, VarPop, Push0, Return;
+  BrS = 0x2b, "br.s", ShortInlineBrTarget, Pop0, Push0, Branch;
+  BrfalseS = 0x2c, "brfalse.s", ShortInlineBrTarget, PopI, Push0, CondBranch;
+  BrtrueS = 0x2d, "brtrue.s", ShortInlineBrTarget, PopI, Push0, CondBranch;
+  BeqS = 0x2e, "beq.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgeS = 0x2f, "bge.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgtS = 0x30, "bgt.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BleS = 0x31, "ble.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BltS = 0x32, "blt.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BneUnS = 0x33, "bne.un.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgeUnS = 0x34, "bge.un.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgtUnS = 0x35, "bgt.un.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BleUnS = 0x36, "ble.un.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BltUnS = 0x37, "blt.un.s", ShortInlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Br = 0x38, "br", InlineBrTarget, Pop0, Push0, Branch;
+  Brfalse = 0x39, "brfalse", InlineBrTarget, PopI, Push0, CondBranch;
+  Brtrue = 0x3a, "brtrue", InlineBrTarget, PopI, Push0, CondBranch;
+  Beq = 0x3b, "beq", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Bge = 0x3c, "bge", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Bgt = 0x3d, "bgt", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Ble = 0x3e, "ble", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Blt = 0x3f, "blt", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BneUn = 0x40, "bne.un", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgeUn = 0x41, "bge.un", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BgtUn = 0x42, "bgt.un", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BleUn = 0x43, "ble.un", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  BltUn = 0x44, "blt.un", InlineBrTarget, Pop1Pop1, Push0, CondBranch;
+  Switch = 0x45, "switch", InlineSwitch, PopI, Push0, CondBranch;
+  LdindI1 = 0x46, "ldind.i1", InlineNone, PopI, PushI, Next;
+  LdindU1 = 0x47, "ldind.u1", InlineNone, PopI, PushI, Next;
+  LdindI2 = 0x48, "ldind.i2", InlineNone, PopI, PushI, Next;
+  LdindU2 = 0x49, "ldind.u2", InlineNone, PopI, PushI, Next;
+  LdindI4 = 0x4a, "ldind.i4", InlineNone, PopI, PushI, Next;
+  LdindU4 = 0x4b, "ldind.u4", InlineNone, PopI, PushI, Next;
+  LdindI8 = 0x4c, "ldind.i8", InlineNone, PopI, PushI8, Next;
+  LdindI = 0x4d, "ldind.i", InlineNone, PopI, PushI, Next;
+  LdindR4 = 0x4e, "ldind.r4", InlineNone, PopI, PushR4, Next;
+  LdindR8 = 0x4f, "ldind.r8", InlineNone, PopI, PushR8, Next;
+  LdindRef = 0x50, "ldind.ref", InlineNone, PopI, PushRef, Next;
+  StindRef = 0x51, "stind.ref", InlineNone, PopIPopI, Push0, Next;
+  StindI1 = 0x52, "stind.i1", InlineNone, PopIPopI, Push0, Next;
+  StindI2 = 0x53, "stind.i2", InlineNone, PopIPopI, Push0, Next;
+  StindI4 = 0x54, "stind.i4", InlineNone, PopIPopI, Push0, Next;
+  StindI8 = 0x55, "stind.i8", InlineNone, PopIPopI8, Push0, Next;
+  StindR4 = 0x56, "stind.r4", InlineNone, PopIPopR4, Push0, Next;
+  StindR8 = 0x57, "stind.r8", InlineNone, PopIPopR8, Push0, Next;
+  Add = 0x58, "add", InlineNone, Pop1Pop1, Push1, Next;
+  Sub = 0x59, "sub", InlineNone, Pop1Pop1, Push1, Next;
+  Mul = 0x5a, "mul", InlineNone, Pop1Pop1, Push1, Next;
+  Div = 0x5b, "div", InlineNone, Pop1Pop1, Push1, Next;
+  DivUn = 0x5c, "div.un", InlineNone, Pop1Pop1, Push1, Next;
+  Rem = 0x5d, "rem", InlineNone, Pop1Pop1, Push1, Next;
+  RemUn = 0x5e, "rem.un", InlineNone, Pop1Pop1, Push1, Next;
+  And = 0x5f, "and", InlineNone, Pop1Pop1, Push1, Next;
+  Or = 0x60, "or", InlineNone, Pop1Pop1, Push1, Next;
+  Xor = 0x61, "xor", InlineNone, Pop1Pop1, Push1, Next;
+  Shl = 0x62, "shl", InlineNone, Pop1Pop1, Push1, Next;
+  Shr = 0x63, "shr", InlineNone, Pop1Pop1, Push1, Next;
+  ShrUn = 0x64, "shr.un", InlineNone, Pop1Pop1, Push1, Next;
+  Neg = 0x65, "neg", InlineNone, Pop1, Push1, Next;
+  Not = 0x66, "not", InlineNone, Pop1, Push1, Next;
+  ConvI1 = 0x67, "conv.i1", InlineNone, Pop1, PushI, Next;
+  ConvI2 = 0x68, "conv.i2", InlineNone, Pop1, PushI, Next;
+  ConvI4 = 0x69, "conv.i4", InlineNone, Pop1, PushI, Next;
+  ConvI8 = 0x6a, "conv.i8", InlineNone, Pop1, PushI8, Next;
+  ConvR4 = 0x6b, "conv.r4", InlineNone, Pop1, PushR4, Next;
+  ConvR8 = 0x6c, "conv.r8", InlineNone, Pop1, PushR8, Next;
+  ConvU4 = 0x6d, "conv.u4", InlineNone, Pop1, PushI, Next;
+  ConvU8 = 0x6e, "conv.u8", InlineNone, Pop1, PushI8, Next;
+  Callvirt = 0x6f, "callvirt", InlineMethod, VarPop, VarPush, Call;
+  Cpobj = 0x70, "cpobj", InlineType, PopIPopI, Push0, Next;
+  Ldobj = 0x71, "ldobj", InlineType, PopI, Push1, Next;
+  Ldstr = 0x72, "ldstr", InlineString, Pop0, PushRef, Next;
+  Newobj = 0x73, "newobj", InlineMethod, VarPop, PushRef, Call;
+  Castclass = 0x74, "castclass", InlineType, PopRef, PushRef, Next;
+  Isinst = 0x75, "isinst", InlineType, PopRef, PushI, Next;
+  ConvRUn = 0x76, "conv.r.un", InlineNone, Pop1, PushR8, Next;
+  Unbox = 0x79, "unbox", InlineType, PopRef, PushI, Next;
+  Throw = 0x7a, "throw", InlineNone, PopRef, Push0, Throw;
+  Ldfld = 0x7b, "ldfld", InlineField, PopRef, Push1, Next;
+  Ldflda = 0x7c, "ldflda", InlineField, PopRef, PushI, Next;
+  Stfld = 0x7d, "stfld", InlineField, PopRefPop1, Push0, Next;
+  Ldsfld = 0x7e, "ldsfld", InlineField, Pop0, Push1, Next;
+  Ldsflda = 0x7f, "ldsflda", InlineField, Pop0, PushI, Next;
+  Stsfld = 0x80, "stsfld", InlineField, Pop1, Push0, Next;
+  Stobj = 0x81, "stobj", InlineType, PopIPop1, Push0, Next;
+  ConvOvfI1Un = 0x82, "conv.ovf.i1.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfI2Un = 0x83, "conv.ovf.i2.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfI4Un = 0x84, "conv.ovf.i4.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfI8Un = 0x85, "conv.ovf.i8.un", InlineNone, Pop1, PushI8, Next;
+  ConvOvfU1Un = 0x86, "conv.ovf.u1.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfU2Un = 0x87, "conv.ovf.u2.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfU4Un = 0x88, "conv.ovf.u4.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfU8Un = 0x89, "conv.ovf.u8.un", InlineNone, Pop1, PushI8, Next;
+  ConvOvfIUn = 0x8a, "conv.ovf.i.un", InlineNone, Pop1, PushI, Next;
+  ConvOvfUUn = 0x8b, "conv.ovf.u.un", InlineNone, Pop1, PushI, Next;
+  Box = 0x8c, "box", InlineType, Pop1, PushRef, Next;
+  Newarr = 0x8d, "newarr", InlineType, PopI, PushRef, Next;
+  Ldlen = 0x8e, "ldlen", InlineNone, PopRef, PushI, Next;
+  Ldelema = 0x8f, "ldelema", InlineType, PopRefPopI, PushI, Next;
+  LdelemI1 = 0x90, "ldelem.i1", InlineNone, PopRefPopI, PushI, Next;
+  LdelemU1 = 0x91, "ldelem.u1", InlineNone, PopRefPopI, PushI, Next;
+  LdelemI2 = 0x92, "ldelem.i2", InlineNone, PopRefPopI, PushI, Next;
+  LdelemU2 = 0x93, "ldelem.u2", InlineNone, PopRefPopI, PushI, Next;
+  LdelemI4 = 0x94, "ldelem.i4", InlineNone, PopRefPopI, PushI, Next;
+  LdelemU4 = 0x95, "ldelem.u4", InlineNone, PopRefPopI, PushI, Next;
+  LdelemI8 = 0x96, "ldelem.i8", InlineNone, PopRefPopI, PushI8, Next;
+  LdelemI = 0x97, "ldelem.i", InlineNone, PopRefPopI, PushI, Next;
+  LdelemR4 = 0x98, "ldelem.r4", InlineNone, PopRefPopI, PushR4, Next;
+  LdelemR8 = 0x99, "ldelem.r8", InlineNone, PopRefPopI, PushR8, Next;
+  LdelemRef = 0x9a, "ldelem.ref", InlineNone, PopRefPopI, PushRef, Next;
+  StelemI = 0x9b, "stelem.i", InlineNone, PopRefPopIPopI, Push0, Next;
+  StelemI1 = 0x9c, "stelem.i1", InlineNone, PopRefPopIPopI, Push0, Next;
+  StelemI2 = 0x9d, "stelem.i2", InlineNone, PopRefPopIPopI, Push0, Next;
+  StelemI4 = 0x9e, "stelem.i4", InlineNone, PopRefPopIPopI, Push0, Next;
+  StelemI8 = 0x9f, "stelem.i8", InlineNone, PopRefPopIPopI8, Push0, Next;
+  StelemR4 = 0xa0, "stelem.r4", InlineNone, PopRefPopIPopR4, Push0, Next;
+  StelemR8 = 0xa1, "stelem.r8", InlineNone, PopRefPopIPopR8, Push0, Next;
+  StelemRef = 0xa2, "stelem.ref", InlineNone, PopRefPopIPopRef, Push0, Next;
+  Ldelem = 0xa3, "ldelem", InlineType, PopRefPopI, Push1, Next;
+  Stelem = 0xa4, "stelem", InlineType, PopRefPopIPop1, Push0, Next;
+  UnboxAny = 0xa5, "unbox.any", InlineType, PopRef, Push1, Next;
+  ConvOvfI1 = 0xb3, "conv.ovf.i1", InlineNone, Pop1, PushI, Next;
+  ConvOvfU1 = 0xb4, "conv.ovf.u1", InlineNone, Pop1, PushI, Next;
+  ConvOvfI2 = 0xb5, "conv.ovf.i2", InlineNone, Pop1, PushI, Next;
+  ConvOvfU2 = 0xb6, "conv.ovf.u2", InlineNone, Pop1, PushI, Next;
+  ConvOvfI4 = 0xb7, "conv.ovf.i4", InlineNone, Pop1, PushI, Next;
+  ConvOvfU4 = 0xb8, "conv.ovf.u4", InlineNone, Pop1, PushI, Next;
+  ConvOvfI8 = 0xb9, "conv.ovf.i8", InlineNone, Pop1, PushI8, Next;
+  ConvOvfU8 = 0xba, "conv.ovf.u8", InlineNone, Pop1, PushI8, Next;
+  Refanyval = 0xc2, "refanyval", InlineType, Pop1, PushI, Next;
+  Ckfinite = 0xc3, "ckfinite", InlineNone, Pop1, PushR8, Next;
+  Mkrefany = 0xc6, "mkrefany", InlineType, PopI, Push1, Next;
+  Ldtoken = 0xd0, "ldtoken", InlineTok, Pop0, PushI, Next;
+  ConvU2 = 0xd1, "conv.u2", InlineNone, Pop1, PushI, Next;
+  ConvU1 = 0xd2, "conv.u1", InlineNone, Pop1, PushI, Next;
+  ConvI = 0xd3, "conv.i", InlineNone, Pop1, PushI, Next;
+  ConvOvfI = 0xd4, "conv.ovf.i", InlineNone, Pop1, PushI, Next;
+  ConvOvfU = 0xd5, "conv.ovf.u", InlineNone, Pop1, PushI, Next;
+  AddOvf = 0xd6, "add.ovf", InlineNone, Pop1Pop1, Push1, Next;
+  AddOvfUn = 0xd7, "add.ovf.un", InlineNone, Pop1Pop1, Push1, Next;
+  MulOvf = 0xd8, "mul.ovf", InlineNone, Pop1Pop1, Push1, Next;
+  MulOvfUn = 0xd9, "mul.ovf.un", InlineNone, Pop1Pop1, Push1, Next;
+  SubOvf = 0xda, "sub.ovf", InlineNone, Pop1Pop1, Push1, Next;
+  SubOvfUn = 0xdb, "sub.ovf.un", InlineNone, Pop1Pop1, Push1, Next;
+  Endfinally = 0xdc, "endfinally", InlineNone, Pop0, Push0, Return;
+  Leave = 0xdd, "leave", InlineBrTarget, Pop0, Push0, Branch;
+  LeaveS = 0xde, "leave.s", ShortInlineBrTarget, Pop0, Push0, Branch;
+  StindI = 0xdf, "stind.i", InlineNone, PopIPopI, Push0, Next;
+  ConvU = 0xe0, "conv.u", InlineNone, Pop1, PushI, Next;
+  Arglist = 0xfe00, "arglist", InlineNone, Pop0, PushI, Next;
+  Ceq = 0xfe01, "ceq", InlineNone, Pop1Pop1, PushI, Next;
+  Cgt = 0xfe02, "cgt", InlineNone, Pop1Pop1, PushI, Next;
+  CgtUn = 0xfe03, "cgt.un", InlineNone, Pop1Pop1, PushI, Next;
+  Clt = 0xfe04, "clt", InlineNone, Pop1Pop1, PushI, Next;
+  CltUn = 0xfe05, "clt.un", InlineNone, Pop1Pop1, PushI, Next;
+  Ldftn = 0xfe06, "ldftn", InlineMethod, Pop0, PushI, Next;
+  Ldvirtftn = 0xfe07, "ldvirtftn", InlineMethod, PopRef, PushI, Next;
+  Ldarg = 0xfe09, "ldarg", InlineVar, Pop0, Push1, Next;
+  Ldarga = 0xfe0a, "ldarga", InlineVar, Pop0, PushI, Next;
+  Starg = 0xfe0b, "starg", InlineVar, Pop1, Push0, Next;
+  Ldloc = 0xfe0c, "ldloc", InlineVar, Pop0, Push1, Next;
+  Ldloca = 0xfe0d, "ldloca", InlineVar, Pop0, PushI, Next;
+  Stloc = 0xfe0e, "stloc", InlineVar, Pop1, Push0, Next;
+  Localloc = 0xfe0f, "localloc", InlineNone, PopI, PushI, Next;
+  Endfilter = 0xfe11, "endfilter", InlineNone, PopI, Push0, Return;
+  Unaligned = 0xfe12, "unaligned.", ShortInlineI, Pop0, Push0, Meta;
+  Volatile = 0xfe13, "volatile.", InlineNone, Pop0, Push0, Meta;
+  Tail = 0xfe14, "tail.", InlineNone, Pop0, Push0, Meta;
+  Initobj = 0xfe15, "initobj", InlineType, PopI, Push0, Next;
+  Constrained = 0xfe16, "constrained.", InlineType, Pop0, Push0, Meta;
+  Cpblk = 0xfe17, "cpblk", InlineNone, PopIPopIPopI, Push0, Next;
+  Initblk = 0xfe18, "initblk", InlineNone, PopIPopIPopI, Push0, Next;
+  No = 0xfe19, "no.", ShortInlineI, Pop0, Push0, Meta;
+  Rethrow = 0xfe1a, "rethrow", InlineNone, Pop0, Push0, Throw;
+  Sizeof = 0xfe1c, "sizeof", InlineType, Pop0, PushI, Next;
+  Refanytype = 0xfe1d, "refanytype", InlineNone, Pop1, PushI, Next;
+  Readonly = 0xfe1e, "readonly.", InlineNone, Pop0, Push0, Meta;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{FlowControl, OpCode, OperandType, TWO_BYTE_PREFIX};
+
+  #[test]
+  fn opcode_table() {
+    assert_eq!(OpCode::ALL.len(), 219);
+
+    for opcode in OpCode::ALL {
+      assert_eq!(OpCode::from_value(opcode.value()), Some(*opcode));
+      assert_eq!(OpCode::from_name(opcode.name()), Some(*opcode));
+
+      match opcode.size() {
+        1 => assert_ne!(opcode.value() as u8, TWO_BYTE_PREFIX),
+        _ => assert_eq!((opcode.value() >> 8) as u8, TWO_BYTE_PREFIX),
+      }
+    }
+
+    assert!(OpCode::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(OpCode::from_value(0x24), None);
+    assert_eq!(OpCode::from_value(0xfe08), None);
+  }
+
+  #[test]
+  fn prefixes() {
+    let prefixes = OpCode::ALL
+      .iter()
+      .filter(|opcode| opcode.is_prefix())
+      .map(|opcode| opcode.name())
+      .collect::<alloc::vec::Vec<_>>();
+
+    assert_eq!(
+      prefixes,
+      [
+        "unaligned.",
+        "volatile.",
+        "tail.",
+        "constrained.",
+        "no.",
+        "readonly."
+      ]
+    );
+    assert_eq!(OpCode::Constrained.operand_type(), OperandType::InlineType);
+    assert_eq!(OpCode::Leave.flow_control(), FlowControl::Branch);
+  }
+}
@@ -0,0 +1,1588 @@
+//! Evaluation stack type analysis and verification (III.1.7, III.1.8).
+//!
+//! [Verifier] runs a data flow pass over the [ControlFlowGraph] of a method, tracking the
+//! verification type of each value on the evaluation stack from the entry and handler blocks until
+//! the state at the start of every block stops changing.  Along the way it checks each
+//! instruction's operand types, the stack height against `.maxstack` and at merges, and the rules
+//! on control flow into and out of protected regions.
+//!
+//! Types are tracked at the granularity of [StackType].  Whether a type from another assembly is
+//! an enum can't be known from this module's metadata alone, so value types are treated as
+//! assignable to and from integers.
+
+use super::{
+  cfg::{ControlFlowGraph, EdgeKind, Exit},
+  instructions::{Instruction, Operand},
+  opcodes::{FlowControl, OpCode},
+};
+use crate::ecma335::{
+  body::{ExceptionClause, HandlerKind, MethodBody},
+  signatures::{MethodSig, StandAloneSig, TypeSig},
+  tables::{
+    flags::FieldAttributes, MemberRefParent, MemberRefRowId, MethodDefOrRef, MethodDefRowId,
+    RowIndex, StandAloneSigRowId, TypeDefOrRef, TypeDefRowId, TypeSpecRowId,
+  },
+  token::TokenRow,
+  Md, MAX_NESTING,
+};
+use alloc::{collections::BTreeSet, format, vec, vec::Vec};
+use anyhow::{bail, Context, Result};
+use core::fmt;
+
+/// The verification type of a value on the evaluation stack (III.1.8.1.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StackType {
+  /// `int32`, also `bool`, `char` and the smaller integers.
+  Int32,
+  /// `int64`.
+  Int64,
+  /// `native int`, also unmanaged and function pointers.
+  NativeInt,
+  /// `F`, a floating point number.
+  Float,
+  /// `O`, an object reference or `null`.
+  Object,
+  /// `&`, a managed pointer.
+  ByRef,
+  /// An instance of a value type.
+  ValueType,
+}
+
+impl fmt::Display for StackType {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      StackType::Int32 => "int32",
+      StackType::Int64 => "int64",
+      StackType::NativeInt => "native int",
+      StackType::Float => "F",
+      StackType::Object => "O",
+      StackType::ByRef => "&",
+      StackType::ValueType => "value type",
+    })
+  }
+}
+
+/// Why an instruction is unverifiable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VerificationErrorKind {
+  /// The instruction pops more values than are on the stack.
+  StackUnderflow,
+  /// The stack grows beyond `.maxstack`.
+  StackOverflow { max_stack: u16 },
+  /// Paths merging at a block leave a different number of values on the stack.
+  StackHeightMismatch { expected: usize, found: usize },
+  /// Paths merging at a block leave incompatible types on the stack.
+  StackTypeMismatch {
+    expected: StackType,
+    found: StackType,
+  },
+  /// The stack isn't empty entering a try block, after `ret` or `endfilter`, or before `jmp`.
+  NonEmptyStack,
+  /// An operand has a type the instruction doesn't accept.
+  UnexpectedType { found: StackType },
+  /// A pair of operands can't be combined or compared.
+  IncompatibleOperands { left: StackType, right: StackType },
+  /// A value isn't assignable to the argument, local, field, parameter or return value it's
+  /// stored in.
+  NotAssignable {
+    expected: StackType,
+    found: StackType,
+  },
+  /// A local variable index past the end of the locals.
+  InvalidLocal(u16),
+  /// An argument index past the end of the arguments.
+  InvalidArgument(u16),
+  /// A token operand that doesn't resolve to a row of the expected kind.
+  InvalidToken(u32),
+  /// A branch into a try block other than to its first instruction.
+  BranchIntoTry,
+  /// A branch or fall through into a filter or handler.
+  BranchIntoHandler,
+  /// A branch or fall through out of a try block, filter or handler, which have to be left with
+  /// `leave`, `endfilter` or `endfinally`.
+  BranchOutOfRegion,
+  /// A `leave` out of a filter, `finally` or `fault` handler.
+  LeaveFromHandler,
+  /// A `ret` inside a try block, filter or handler.
+  ReturnFromRegion,
+  /// An `endfinally`, `endfilter` or `rethrow` outside the handler or filter it belongs in.
+  MisplacedInstruction,
+}
+
+impl fmt::Display for VerificationErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::StackUnderflow => write!(f, "Stack underflow"),
+      Self::StackOverflow { max_stack } => write!(f, "Stack exceeds .maxstack {}", max_stack),
+      Self::StackHeightMismatch { expected, found } => {
+        write!(
+          f,
+          "Stack height {} doesn't match {} at merge",
+          found, expected
+        )
+      }
+      Self::StackTypeMismatch { expected, found } => {
+        write!(
+          f,
+          "Stack type {} doesn't match {} at merge",
+          found, expected
+        )
+      }
+      Self::NonEmptyStack => write!(f, "Stack isn't empty"),
+      Self::UnexpectedType { found } => write!(f, "Unexpected operand type {}", found),
+      Self::IncompatibleOperands { left, right } => {
+        write!(f, "Incompatible operand types {} and {}", left, right)
+      }
+      Self::NotAssignable { expected, found } => {
+        write!(f, "{} isn't assignable to {}", found, expected)
+      }
+      Self::InvalidLocal(index) => write!(f, "Invalid local V_{}", index),
+      Self::InvalidArgument(index) => write!(f, "Invalid argument A_{}", index),
+      Self::InvalidToken(token) => write!(f, "Invalid token {:#010x}", token),
+      Self::BranchIntoTry => write!(f, "Branch into the middle of a try block"),
+      Self::BranchIntoHandler => write!(f, "Branch into a filter or handler"),
+      Self::BranchOutOfRegion => write!(f, "Branch out of a protected region without leave"),
+      Self::LeaveFromHandler => write!(f, "Leave out of a filter, finally or fault handler"),
+      Self::ReturnFromRegion => write!(f, "Return from a protected region"),
+      Self::MisplacedInstruction => write!(f, "Instruction outside its handler or filter"),
+    }
+  }
+}
+
+/// An unverifiable instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VerificationError {
+  /// The offset of the instruction.
+  pub offset: u32,
+  /// Why the instruction is unverifiable.
+  pub kind: VerificationErrorKind,
+}
+
+impl fmt::Display for VerificationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "IL_{:04x}: {}", self.offset, self.kind)
+  }
+}
+
+/// The result of verifying a method body.
+#[derive(Debug, Clone)]
+pub struct Verification {
+  /// The offset of each instruction and the stack before it, `None` for unreachable code and
+  /// instructions after an error.
+  stacks: Vec<(u32, Option<Vec<StackType>>)>,
+  max_height: usize,
+  errors: Vec<VerificationError>,
+}
+
+impl Verification {
+  /// Determines if no unverifiable instructions were found.
+  pub fn is_verifiable(&self) -> bool {
+    self.errors.is_empty()
+  }
+
+  /// Gets the unverifiable instructions, ordered by offset.
+  pub fn errors(&self) -> &[VerificationError] {
+    &self.errors
+  }
+
+  /// Gets the largest number of values on the stack at any point.
+  pub fn max_height(&self) -> usize {
+    self.max_height
+  }
+
+  /// Gets the types on the stack before the instruction at the given offset, bottom first.
+  pub fn stack_before(&self, offset: u32) -> Option<&[StackType]> {
+    let i = self
+      .stacks
+      .binary_search_by_key(&offset, |(offset, _)| *offset)
+      .ok()?;
+
+    self.stacks[i].1.as_deref()
+  }
+}
+
+/// Type arguments substituted for `!n` and `!!n` in a member's signature.
+#[derive(Default)]
+struct Generics {
+  type_args: Vec<TypeSig>,
+  method_args: Vec<TypeSig>,
+}
+
+/// A resolved call target.
+struct Callee {
+  sig: MethodSig,
+  generics: Generics,
+  /// The stack type of an instance of the declaring type.
+  owner: StackType,
+}
+
+/// Verifies method bodies against the metadata of their module.
+///
+/// Creating a verifier classifies every type definition, indexes the owners of every method and
+/// collects the type references used as value types, so it should be reused when verifying more
+/// than one method.
+pub struct Verifier<'m, 'a> {
+  md: &'m Md<'a>,
+  /// The stack type of each type definition's instances, `O` for classes and the underlying
+  /// integer type for enums, indexed by zero-based row.
+  type_defs: Vec<StackType>,
+  /// The type owning each method, indexed by zero-based row.
+  method_owners: Vec<Option<TypeDefRowId>>,
+  /// The rows of the type references used as value types in signatures.
+  value_type_refs: BTreeSet<u32>,
+}
+
+impl<'m, 'a> Verifier<'m, 'a> {
+  /// Creates a verifier for the given metadata.
+  pub fn new(md: &'m Md<'a>) -> Result<Self> {
+    let tables = md.tables();
+    let header = tables.header();
+    let mut type_defs = Vec::with_capacity(tables.type_defs().len());
+    let mut method_owners = vec![None; tables.method_defs().len()];
+
+    let mut index = TypeDefRowId::first(header);
+    while let Some(id) = index {
+      let row = tables.type_defs().read(id)?;
+      let base = match row.extends {
+        Some(ty @ (TypeDefOrRef::TypeDef(_) | TypeDefOrRef::TypeRef(_))) => md.type_name(ty)?,
+        _ => Default::default(),
+      };
+
+      type_defs.push(match base.as_str() {
+        "System.ValueType" => StackType::ValueType,
+        "System.Enum" => {
+          // The underlying type is the type of the single instance field, `value__`.
+          let mut underlying = StackType::Int32;
+
+          for id in tables.type_def_fields(id)? {
+            let row = tables.fields().read(id)?;
+            if !row.flags.contains(FieldAttributes::STATIC) {
+              underlying = primitive(&md.field_sig(row.signature)?.ty).unwrap_or(underlying);
+              break;
+            }
+          }
+
+          underlying
+        }
+        _ => StackType::Object,
+      });
+
+      for method in tables.type_def_methods(id)? {
+        method_owners[method.row() as usize - 1] = Some(id);
+      }
+
+      index = id.next();
+    }
+
+    let mut value_type_refs = BTreeSet::new();
+    let mut collect = |ty: &TypeSig| collect_value_type_refs(ty, &mut value_type_refs);
+
+    for row in tables.type_specs() {
+      collect(&md.type_spec_sig(row?.signature)?);
+    }
+    for row in tables.fields() {
+      collect(&md.field_sig(row?.signature)?.ty);
+    }
+    for row in tables.method_defs() {
+      let sig = md.method_sig(row?.signature)?;
+      sig.params.iter().chain([&sig.ret]).for_each(&mut collect);
+    }
+    for row in tables.member_refs() {
+      let row = row?;
+      match md.blobs().get(row.signature)?.first() {
+        Some(0x06) => collect(&md.field_sig(row.signature)?.ty),
+        _ => {
+          let sig = md.method_sig(row.signature)?;
+          sig.params.iter().chain([&sig.ret]).for_each(&mut collect);
+        }
+      }
+    }
+    for row in tables.method_specs() {
+      md.method_spec_sig(row?.instantiation)?
+        .args
+        .iter()
+        .for_each(&mut collect);
+    }
+    for row in tables.stand_alone_sigs() {
+      match md.stand_alone_sig(row?.signature)? {
+        StandAloneSig::Locals(sig) => sig.locals.iter().for_each(&mut collect),
+        StandAloneSig::Method(sig) => sig.params.iter().chain([&sig.ret]).for_each(&mut collect),
+      }
+    }
+
+    Ok(Self {
+      md,
+      type_defs,
+      method_owners,
+      value_type_refs,
+    })
+  }
+
+  /// Verifies the body of a method.
+  pub fn verify(&self, id: MethodDefRowId) -> Result<Verification> {
+    let row = self.md.tables().method_defs().read(id)?;
+    let body = self
+      .md
+      .method_body(&row)?
+      .context("The method has no body")?;
+
+    self.verify_body(id, &body)
+  }
+
+  /// Verifies a body for a method, e.g. rewritten code that hasn't replaced the method's body yet.
+  pub fn verify_body(&self, id: MethodDefRowId, body: &MethodBody) -> Result<Verification> {
+    let md = self.md;
+    let header = md.tables().header();
+    let row = md.tables().method_defs().read(id)?;
+    let sig = md.method_sig(row.signature)?;
+    let generics = Generics::default();
+
+    let mut args = Vec::new();
+    if sig.has_this() {
+      args.push(
+        match self.type_defs[self.method_owner(id)?.row() as usize - 1] {
+          StackType::Object => StackType::Object,
+          _ => StackType::ByRef,
+        },
+      );
+    }
+    for param in &sig.params {
+      args.push(
+        self
+          .stack_type(param, &generics)?
+          .context("Void parameter")?,
+      );
+    }
+
+    let mut locals = Vec::new();
+    if let Some(id) = body.local_var_sig {
+      let row = md.tables().stand_alone_sigs().read(id)?;
+      let sig = match md.stand_alone_sig(row.signature)? {
+        StandAloneSig::Locals(sig) => sig,
+        StandAloneSig::Method(_) => bail!("Expected a local variable signature"),
+      };
+
+      for local in &sig.locals {
+        locals.push(self.stack_type(local, &generics)?.context("Void local")?);
+      }
+    }
+
+    let clauses = body.exception_clauses(header)?;
+    let cfg = ControlFlowGraph::new(body.instructions().collect::<Result<Vec<_>>>()?, &clauses)?;
+
+    let mut analysis = Analysis {
+      verifier: self,
+      args,
+      locals,
+      ret: self.stack_type(&sig.ret, &generics)?,
+      max_stack: body.max_stack,
+      cfg: &cfg,
+      clauses: &clauses,
+      stacks: vec![None; cfg.instructions().len()],
+      max_height: 0,
+      errors: BTreeSet::new(),
+    };
+
+    analysis.run();
+    analysis.check_regions();
+
+    Ok(Verification {
+      stacks: cfg
+        .instructions()
+        .iter()
+        .map(|instruction| instruction.offset)
+        .zip(analysis.stacks)
+        .collect(),
+      max_height: analysis.max_height,
+      errors: analysis.errors.into_iter().collect(),
+    })
+  }
+
+  /// Gets the stack type of a value of the given type, `None` for `void`.
+  fn stack_type(&self, ty: &TypeSig, generics: &Generics) -> Result<Option<StackType>> {
+    self.nested_stack_type(ty, generics, 0)
+  }
+
+  /// Gets the stack type of a value of a type resolved through `depth` type specifications.
+  fn nested_stack_type(
+    &self,
+    ty: &TypeSig,
+    generics: &Generics,
+    depth: usize,
+  ) -> Result<Option<StackType>> {
+    if let Some(ty) = primitive(ty) {
+      return Ok(Some(ty));
+    }
+
+    Ok(Some(match ty {
+      TypeSig::Void => return Ok(None),
+      TypeSig::ValueType(ty) => self.value_type(*ty, depth)?,
+      TypeSig::GenericInst(inst) if inst.value_type => StackType::ValueType,
+      TypeSig::Var(number) | TypeSig::MVar(number) => {
+        let args = match ty {
+          TypeSig::Var(_) => &generics.type_args,
+          _ => &generics.method_args,
+        };
+
+        // Generic parameters of the method being verified could be anything, references are
+        // assumed.
+        match args.get(*number as usize) {
+          Some(arg) => self
+            .nested_stack_type(arg, &Generics::default(), depth)?
+            .context("Void type argument")?,
+          None => StackType::Object,
+        }
+      }
+      TypeSig::Modified(_, ty) | TypeSig::Pinned(ty) => {
+        return self.nested_stack_type(ty, generics, depth);
+      }
+      _ => StackType::Object,
+    }))
+  }
+
+  /// Gets the stack type of an instance of a type used as `valuetype`.
+  fn value_type(&self, ty: TypeDefOrRef, depth: usize) -> Result<StackType> {
+    match ty {
+      TypeDefOrRef::TypeSpec(id) => self.type_spec(id, depth),
+      ty => Ok(match self.type_token(ty)? {
+        StackType::Object => StackType::ValueType,
+        ty => ty,
+      }),
+    }
+  }
+
+  /// Gets the stack type of an instance of a type token operand.
+  fn type_token(&self, ty: TypeDefOrRef) -> Result<StackType> {
+    Ok(match ty {
+      TypeDefOrRef::TypeDef(id) => self.type_defs[id.row() as usize - 1],
+      TypeDefOrRef::TypeRef(id) => match system_type(&self.md.type_ref_name(id)?) {
+        Some(ty) => ty,
+        None if self.value_type_refs.contains(&id.row()) => StackType::ValueType,
+        None => StackType::Object,
+      },
+      TypeDefOrRef::TypeSpec(id) => self.type_spec(id, 0)?,
+    })
+  }
+
+  /// Gets the stack type of an instance of a type specification, failing if it's resolved through
+  /// more than [MAX_NESTING] type specifications, e.g. one referring to itself.
+  fn type_spec(&self, id: TypeSpecRowId, depth: usize) -> Result<StackType> {
+    if depth > MAX_NESTING {
+      bail!(
+        "TypeSpec {} is nested deeper than {} types",
+        id.row(),
+        MAX_NESTING
+      );
+    }
+
+    let row = self.md.tables().type_specs().read(id)?;
+    let sig = self.md.type_spec_sig(row.signature)?;
+
+    self
+      .nested_stack_type(&sig, &Generics::default(), depth + 1)?
+      .context("Void type specification")
+  }
+
+  /// Resolves a type token operand.
+  fn type_operand(&self, token: u32) -> Result<StackType> {
+    let header = self.md.tables().header();
+
+    self.type_token(TypeDefOrRef::from_token(token.into(), header)?)
+  }
+
+  /// Resolves a field token operand to the stack type of its value.
+  fn field_operand(&self, token: u32) -> Result<StackType> {
+    let md = self.md;
+    let (sig, generics) = match md.resolve_token(token.into())? {
+      TokenRow::Field(_, row) => (md.field_sig(row.signature)?, Generics::default()),
+      TokenRow::MemberRef(_, row) => {
+        let (_, generics) = self.member_parent(row.class)?;
+        (md.field_sig(row.signature)?, generics)
+      }
+      _ => bail!("Expected a field token"),
+    };
+
+    self.stack_type(&sig.ty, &generics)?.context("Void field")
+  }
+
+  /// Resolves a method token operand.
+  fn method_operand(&self, token: u32) -> Result<Callee> {
+    match self.md.resolve_token(token.into())? {
+      TokenRow::MethodDef(id, _) => self.method_def(id),
+      TokenRow::MemberRef(id, _) => self.member_ref(id),
+      TokenRow::MethodSpec(_, row) => {
+        let mut callee = match row.method {
+          MethodDefOrRef::MethodDef(id) => self.method_def(id)?,
+          MethodDefOrRef::MemberRef(id) => self.member_ref(id)?,
+        };
+
+        callee.generics.method_args = self.md.method_spec_sig(row.instantiation)?.args;
+        Ok(callee)
+      }
+      _ => bail!("Expected a method token"),
+    }
+  }
+
+  fn method_def(&self, id: MethodDefRowId) -> Result<Callee> {
+    let row = self.md.tables().method_defs().read(id)?;
+
+    Ok(Callee {
+      sig: self.md.method_sig(row.signature)?,
+      generics: Generics::default(),
+      owner: self.type_defs[self.method_owner(id)?.row() as usize - 1],
+    })
+  }
+
+  fn member_ref(&self, id: MemberRefRowId) -> Result<Callee> {
+    let row = self.md.tables().member_refs().read(id)?;
+    let (owner, generics) = self.member_parent(row.class)?;
+
+    Ok(Callee {
+      sig: self.md.method_sig(row.signature)?,
+      generics,
+      owner,
+    })
+  }
+
+  /// Gets the stack type of an instance of a member's declaring type and its type arguments.
+  fn member_parent(&self, parent: MemberRefParent) -> Result<(StackType, Generics)> {
+    Ok(match parent {
+      MemberRefParent::TypeDef(id) => (self.type_defs[id.row() as usize - 1], Generics::default()),
+      MemberRefParent::TypeRef(id) => (
+        self.type_token(TypeDefOrRef::TypeRef(id))?,
+        Generics::default(),
+      ),
+      MemberRefParent::TypeSpec(id) => {
+        let row = self.md.tables().type_specs().read(id)?;
+
+        match self.md.type_spec_sig(row.signature)? {
+          TypeSig::GenericInst(inst) => {
+            let owner = match inst.value_type {
+              true => StackType::ValueType,
+              false => StackType::Object,
+            };
+
+            (
+              owner,
+              Generics {
+                type_args: inst.args,
+                method_args: Vec::new(),
+              },
+            )
+          }
+          sig => (
+            self
+              .stack_type(&sig, &Generics::default())?
+              .context("Void type specification")?,
+            Generics::default(),
+          ),
+        }
+      }
+      MemberRefParent::MethodDef(id) => (
+        self.type_defs[self.method_owner(id)?.row() as usize - 1],
+        Generics::default(),
+      ),
+      MemberRefParent::ModuleRef(_) => (StackType::Object, Generics::default()),
+    })
+  }
+
+  /// Resolves the call site signature of `calli`.
+  fn call_site_operand(&self, token: u32) -> Result<MethodSig> {
+    let id = StandAloneSigRowId::from_token(token.into(), self.md.tables().header())?;
+    let row = self.md.tables().stand_alone_sigs().read(id)?;
+
+    match self.md.stand_alone_sig(row.signature)? {
+      StandAloneSig::Method(sig) => Ok(sig),
+      StandAloneSig::Locals(_) => bail!("Expected a call site signature"),
+    }
+  }
+
+  /// Gets the type owning a method.
+  fn method_owner(&self, id: MethodDefRowId) -> Result<TypeDefRowId> {
+    match self
+      .method_owners
+      .get(id.row() as usize - 1)
+      .copied()
+      .flatten()
+    {
+      Some(owner) => Ok(owner),
+      None => bail!("Method {} has no owner", id.row()),
+    }
+  }
+}
+
+/// The data flow state of a method being verified.
+struct Analysis<'v, 'm, 'a> {
+  verifier: &'v Verifier<'m, 'a>,
+  args: Vec<StackType>,
+  locals: Vec<StackType>,
+  ret: Option<StackType>,
+  max_stack: u16,
+  cfg: &'v ControlFlowGraph,
+  clauses: &'v [ExceptionClause],
+  /// The stack before each instruction.
+  stacks: Vec<Option<Vec<StackType>>>,
+  max_height: usize,
+  errors: BTreeSet<VerificationError>,
+}
+
+impl<'v, 'm, 'a> Analysis<'v, 'm, 'a> {
+  /// Propagates stack states from the entry and handler blocks until they stop changing.
+  fn run(&mut self) {
+    let cfg = self.cfg;
+    let mut states = vec![None; cfg.blocks().len()];
+    let mut pending = BTreeSet::new();
+
+    states[0] = Some(Vec::new());
+    pending.insert(0);
+
+    // Handlers start with the exception on the stack, except `finally` and `fault`.
+    for clause in self.clauses {
+      let entry = match clause.kind {
+        HandlerKind::Catch(_) | HandlerKind::Filter(_) => vec![StackType::Object],
+        HandlerKind::Finally | HandlerKind::Fault => Vec::new(),
+      };
+
+      let mut starts = vec![clause.handler_offset];
+      starts.extend(clause.filter_range().map(|filter| filter.start));
+
+      for start in starts {
+        if let Some(block) = cfg.block_at(start) {
+          states[block] = Some(entry.clone());
+          pending.insert(block);
+        }
+      }
+    }
+
+    while let Some(block) = pending.pop_first() {
+      let mut stack = match &states[block] {
+        Some(stack) => stack.clone(),
+        None => continue,
+      };
+
+      if !self.simulate(block, &mut stack) {
+        continue;
+      }
+
+      let last = &cfg.block_instructions(block).last().unwrap();
+      for edge in &cfg.blocks()[block].successors {
+        let incoming = match edge.kind {
+          EdgeKind::Exception => continue,
+          _ => &stack,
+        };
+
+        match &mut states[edge.target] {
+          state @ None => {
+            *state = Some(incoming.clone());
+            pending.insert(edge.target);
+          }
+          Some(state) => match merge(state, incoming) {
+            Ok(true) => {
+              pending.insert(edge.target);
+            }
+            Ok(false) => {}
+            Err(kind) => {
+              self.errors.insert(VerificationError {
+                offset: last.offset,
+                kind,
+              });
+            }
+          },
+        }
+      }
+    }
+
+    for clause in self.clauses {
+      let block = cfg.block_at(clause.try_offset);
+      if let Some(Some(stack)) = block.map(|block| &states[block]) {
+        if !stack.is_empty() {
+          self.errors.insert(VerificationError {
+            offset: clause.try_offset,
+            kind: VerificationErrorKind::NonEmptyStack,
+          });
+        }
+      }
+    }
+  }
+
+  /// Runs the instructions of a block, returning `false` if an error leaves the stack unknown.
+  fn simulate(&mut self, block: usize, stack: &mut Vec<StackType>) -> bool {
+    let cfg = self.cfg;
+    let range = cfg.blocks()[block].instructions.clone();
+
+    for (i, instruction) in cfg.instructions()[range.clone()].iter().enumerate() {
+      self.stacks[range.start + i] = Some(stack.clone());
+
+      if let Err(kind) = self.step(instruction, stack) {
+        self.errors.insert(VerificationError {
+          offset: instruction.offset,
+          kind,
+        });
+
+        return false;
+      }
+
+      self.max_height = self.max_height.max(stack.len());
+      if stack.len() > self.max_stack as usize {
+        self.errors.insert(VerificationError {
+          offset: instruction.offset,
+          kind: VerificationErrorKind::StackOverflow {
+            max_stack: self.max_stack,
+          },
+        });
+      }
+    }
+
+    true
+  }
+
+  /// Applies the stack transition of an instruction.
+  fn step(
+    &self,
+    instruction: &Instruction,
+    stack: &mut Vec<StackType>,
+  ) -> Result<(), VerificationErrorKind> {
+    use StackType::*;
+
+    let opcode = instruction.opcode;
+    let name = opcode.name();
+    let token = match instruction.operand {
+      Operand::Token(token) => token,
+      _ => 0,
+    };
+
+    match opcode {
+      OpCode::Ldarg0
+      | OpCode::Ldarg1
+      | OpCode::Ldarg2
+      | OpCode::Ldarg3
+      | OpCode::LdargS
+      | OpCode::Ldarg => stack.push(self.arg(instruction)?),
+      OpCode::LdargaS | OpCode::Ldarga => {
+        self.arg(instruction)?;
+        stack.push(ByRef);
+      }
+      OpCode::StargS | OpCode::Starg => {
+        let expected = self.arg(instruction)?;
+        assign(pop(stack)?, expected)?;
+      }
+      OpCode::LdlocaS | OpCode::Ldloca => {
+        self.local(instruction)?;
+        stack.push(ByRef);
+      }
+      _ if name.starts_with("ldloc") => stack.push(self.local(instruction)?),
+      _ if name.starts_with("stloc") => {
+        let expected = self.local(instruction)?;
+        assign(pop(stack)?, expected)?;
+      }
+      OpCode::Ldnull | OpCode::Ldstr => stack.push(Object),
+      OpCode::LdcI8 => stack.push(Int64),
+      OpCode::LdcR4 | OpCode::LdcR8 => stack.push(Float),
+      _ if name.starts_with("ldc.i4") => stack.push(Int32),
+      OpCode::Dup => {
+        let value = pop(stack)?;
+        stack.extend([value, value]);
+      }
+      OpCode::Pop => {
+        pop(stack)?;
+      }
+      OpCode::Jmp => expect_empty(stack)?,
+      OpCode::Call | OpCode::Callvirt | OpCode::Newobj => {
+        let callee = self
+          .verifier
+          .method_operand(token)
+          .map_err(|_| VerificationErrorKind::InvalidToken(token))?;
+        let newobj = opcode == OpCode::Newobj;
+
+        self.pop_params(stack, &callee.sig, &callee.generics)?;
+        if callee.sig.has_this() && !newobj {
+          expect(pop(stack)?, |ty| matches!(ty, Object | ByRef))?;
+        }
+
+        match newobj {
+          true => stack.push(match callee.owner {
+            Object => Object,
+            _ => ValueType,
+          }),
+          false => stack.extend(self.ret_type(&callee.sig, &callee.generics)?),
+        }
+      }
+      OpCode::Calli => {
+        let sig = self
+          .verifier
+          .call_site_operand(token)
+          .map_err(|_| VerificationErrorKind::InvalidToken(token))?;
+
+        expect(pop(stack)?, |ty| ty == NativeInt)?;
+        self.pop_params(stack, &sig, &Generics::default())?;
+        if sig.has_this() {
+          expect(pop(stack)?, |ty| matches!(ty, Object | ByRef))?;
+        }
+
+        stack.extend(self.ret_type(&sig, &Generics::default())?);
+      }
+      OpCode::Ret => {
+        if let Some(expected) = self.ret {
+          assign(pop(stack)?, expected)?;
+        }
+
+        expect_empty(stack)?;
+      }
+      OpCode::Leave | OpCode::LeaveS | OpCode::Endfinally => stack.clear(),
+      OpCode::Endfilter => {
+        expect(pop(stack)?, |ty| ty == Int32)?;
+        expect_empty(stack)?;
+      }
+      OpCode::Switch => expect(pop(stack)?, |ty| {
+        matches!(ty, Int32 | NativeInt | ValueType)
+      })?,
+      OpCode::Brfalse | OpCode::BrfalseS | OpCode::Brtrue | OpCode::BrtrueS => {
+        expect(pop(stack)?, |ty| ty != Float)?
+      }
+      // The remaining conditional branches compare two values like the `c` instructions.
+      OpCode::Ceq | OpCode::Cgt | OpCode::CgtUn | OpCode::Clt | OpCode::CltUn => {
+        self.compare(opcode, stack)?;
+        stack.push(Int32);
+      }
+      _ if opcode.flow_control() == FlowControl::CondBranch => self.compare(opcode, stack)?,
+      OpCode::Add
+      | OpCode::AddOvf
+      | OpCode::AddOvfUn
+      | OpCode::Sub
+      | OpCode::SubOvf
+      | OpCode::SubOvfUn
+      | OpCode::Mul
+      | OpCode::MulOvf
+      | OpCode::MulOvfUn
+      | OpCode::Div
+      | OpCode::Rem
+      | OpCode::DivUn
+      | OpCode::RemUn
+      | OpCode::And
+      | OpCode::Or
+      | OpCode::Xor => {
+        let right = pop(stack)?;
+        let left = pop(stack)?;
+
+        stack.push(
+          binary(opcode, left, right)
+            .ok_or(VerificationErrorKind::IncompatibleOperands { left, right })?,
+        );
+      }
+      OpCode::Shl | OpCode::Shr | OpCode::ShrUn => {
+        let amount = integer(pop(stack)?);
+        let value = integer(pop(stack)?);
+
+        expect(amount, |ty| matches!(ty, Int32 | NativeInt))?;
+        expect(value, |ty| matches!(ty, Int32 | Int64 | NativeInt))?;
+        stack.push(value);
+      }
+      OpCode::Neg => {
+        let value = integer(pop(stack)?);
+        expect(value, |ty| matches!(ty, Int32 | Int64 | NativeInt | Float))?;
+        stack.push(value);
+      }
+      OpCode::Not => {
+        let value = integer(pop(stack)?);
+        expect(value, |ty| matches!(ty, Int32 | Int64 | NativeInt))?;
+        stack.push(value);
+      }
+      OpCode::Ckfinite => {
+        expect(pop(stack)?, |ty| ty == Float)?;
+        stack.push(Float);
+      }
+      _ if name.starts_with("conv.") => {
+        expect(pop(stack)?, |ty| {
+          matches!(ty, Int32 | Int64 | NativeInt | Float | ValueType)
+        })?;
+        stack.push(conversion(name));
+      }
+      _ if name.starts_with("ldind.") => {
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+        stack.push(suffix_type(name));
+      }
+      _ if name.starts_with("stind.") => {
+        assign(pop(stack)?, suffix_type(name))?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+      }
+      OpCode::Ldobj => {
+        let ty = self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+        stack.push(ty);
+      }
+      OpCode::Stobj => {
+        let ty = self.type_operand(token)?;
+        assign(pop(stack)?, ty)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+      }
+      OpCode::Cpobj => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+      }
+      OpCode::Initobj => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+      }
+      OpCode::Castclass | OpCode::Isinst => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(Object);
+      }
+      OpCode::Box => {
+        self.type_operand(token)?;
+        pop(stack)?;
+        stack.push(Object);
+      }
+      OpCode::Unbox => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(ByRef);
+      }
+      OpCode::UnboxAny => {
+        let ty = self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(ty);
+      }
+      OpCode::Throw => expect(pop(stack)?, |ty| ty == Object)?,
+      OpCode::Ldfld | OpCode::Ldflda => {
+        let ty = self.field_operand(token)?;
+        expect(pop(stack)?, |ty| matches!(ty, Object | ByRef | ValueType))?;
+        stack.push(match opcode {
+          OpCode::Ldfld => ty,
+          _ => ByRef,
+        });
+      }
+      OpCode::Stfld => {
+        let ty = self.field_operand(token)?;
+        assign(pop(stack)?, ty)?;
+        expect(pop(stack)?, |ty| matches!(ty, Object | ByRef))?;
+      }
+      OpCode::Ldsfld => stack.push(self.field_operand(token)?),
+      OpCode::Ldsflda => {
+        self.field_operand(token)?;
+        stack.push(ByRef);
+      }
+      OpCode::Stsfld => {
+        let ty = self.field_operand(token)?;
+        assign(pop(stack)?, ty)?;
+      }
+      OpCode::Newarr => {
+        self.type_operand(token)?;
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        stack.push(Object);
+      }
+      OpCode::Ldlen => {
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(NativeInt);
+      }
+      OpCode::Ldelema | OpCode::Ldelem => {
+        let ty = self.type_operand(token)?;
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(match opcode {
+          OpCode::Ldelem => ty,
+          _ => ByRef,
+        });
+      }
+      OpCode::Stelem => {
+        let ty = self.type_operand(token)?;
+        assign(pop(stack)?, ty)?;
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+      }
+      _ if name.starts_with("ldelem.") => {
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(suffix_type(name));
+      }
+      _ if name.starts_with("stelem.") => {
+        assign(pop(stack)?, suffix_type(name))?;
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+      }
+      OpCode::Mkrefany => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == ByRef)?;
+        stack.push(ValueType);
+      }
+      OpCode::Refanyval => {
+        self.type_operand(token)?;
+        expect(pop(stack)?, |ty| ty == ValueType)?;
+        stack.push(ByRef);
+      }
+      OpCode::Refanytype => {
+        expect(pop(stack)?, |ty| ty == ValueType)?;
+        stack.push(ValueType);
+      }
+      OpCode::Ldtoken | OpCode::Arglist => stack.push(ValueType),
+      OpCode::Sizeof => {
+        self.type_operand(token)?;
+        stack.push(Int32);
+      }
+      OpCode::Ldftn => {
+        self
+          .verifier
+          .method_operand(token)
+          .map_err(|_| VerificationErrorKind::InvalidToken(token))?;
+        stack.push(NativeInt);
+      }
+      OpCode::Ldvirtftn => {
+        self
+          .verifier
+          .method_operand(token)
+          .map_err(|_| VerificationErrorKind::InvalidToken(token))?;
+        expect(pop(stack)?, |ty| ty == Object)?;
+        stack.push(NativeInt);
+      }
+      OpCode::Localloc => {
+        expect(integer(pop(stack)?), |ty| matches!(ty, Int32 | NativeInt))?;
+        stack.push(NativeInt);
+      }
+      OpCode::Cpblk | OpCode::Initblk => {
+        for _ in 0..3 {
+          pop(stack)?;
+        }
+      }
+      // nop, break, br, rethrow and the prefixes don't touch the stack.
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  /// Pops the two operands of a comparison or two operand branch.
+  fn compare(
+    &self,
+    opcode: OpCode,
+    stack: &mut Vec<StackType>,
+  ) -> Result<(), VerificationErrorKind> {
+    let right = pop(stack)?;
+    let left = pop(stack)?;
+    let equality = matches!(
+      opcode,
+      OpCode::Beq | OpCode::BeqS | OpCode::BneUn | OpCode::BneUnS | OpCode::Ceq | OpCode::CgtUn
+    );
+
+    match comparable(left, right, equality) {
+      true => Ok(()),
+      false => Err(VerificationErrorKind::IncompatibleOperands { left, right }),
+    }
+  }
+
+  fn arg(&self, instruction: &Instruction) -> Result<StackType, VerificationErrorKind> {
+    let index = instruction.arg().unwrap_or_default();
+
+    self
+      .args
+      .get(index as usize)
+      .copied()
+      .ok_or(VerificationErrorKind::InvalidArgument(index))
+  }
+
+  fn local(&self, instruction: &Instruction) -> Result<StackType, VerificationErrorKind> {
+    let index = instruction.local().unwrap_or_default();
+
+    self
+      .locals
+      .get(index as usize)
+      .copied()
+      .ok_or(VerificationErrorKind::InvalidLocal(index))
+  }
+
+  fn type_operand(&self, token: u32) -> Result<StackType, VerificationErrorKind> {
+    self
+      .verifier
+      .type_operand(token)
+      .map_err(|_| VerificationErrorKind::InvalidToken(token))
+  }
+
+  fn field_operand(&self, token: u32) -> Result<StackType, VerificationErrorKind> {
+    self
+      .verifier
+      .field_operand(token)
+      .map_err(|_| VerificationErrorKind::InvalidToken(token))
+  }
+
+  /// Pops the arguments of a call, last first.
+  fn pop_params(
+    &self,
+    stack: &mut Vec<StackType>,
+    sig: &MethodSig,
+    generics: &Generics,
+  ) -> Result<(), VerificationErrorKind> {
+    for param in sig.params.iter().rev() {
+      let expected = self
+        .verifier
+        .stack_type(param, generics)
+        .ok()
+        .flatten()
+        .ok_or(VerificationErrorKind::InvalidToken(0))?;
+
+      assign(pop(stack)?, expected)?;
+    }
+
+    Ok(())
+  }
+
+  /// Gets the stack type of the value returned by a call, `None` for `void`.
+  fn ret_type(
+    &self,
+    sig: &MethodSig,
+    generics: &Generics,
+  ) -> Result<Option<StackType>, VerificationErrorKind> {
+    self
+      .verifier
+      .stack_type(&sig.ret, generics)
+      .map_err(|_| VerificationErrorKind::InvalidToken(0))
+  }
+
+  /// Checks the rules on control flow into and out of protected regions (III.1.7.5).
+  fn check_regions(&mut self) {
+    let cfg = self.cfg;
+
+    for block in cfg.blocks() {
+      let last = cfg.instructions()[block.instructions.end - 1].offset;
+      let from = block.range.start;
+
+      for edge in &block.successors {
+        let to = cfg.blocks()[edge.target].range.start;
+        if edge.kind == EdgeKind::Exception {
+          continue;
+        }
+
+        for clause in self.clauses {
+          let kind = region_transfer(clause, from, to, edge.kind);
+          if let Some(kind) = kind {
+            self.errors.insert(VerificationError { offset: last, kind });
+          }
+        }
+      }
+
+      let in_region = |clause: &ExceptionClause, handlers: fn(&ExceptionClause) -> bool| {
+        handlers(clause) && clause.handler_range().contains(&from)
+      };
+
+      let misplaced = match block.exit {
+        Some(Exit::Return) => self.clauses.iter().any(|clause| {
+          clause.try_range().contains(&from)
+            || clause.handler_range().contains(&from)
+            || clause
+              .filter_range()
+              .is_some_and(|filter| filter.contains(&from))
+        }),
+        Some(Exit::EndFinally) => !self.clauses.iter().any(|clause| {
+          in_region(clause, |clause| {
+            matches!(clause.kind, HandlerKind::Finally | HandlerKind::Fault)
+          })
+        }),
+        Some(Exit::Rethrow) => !self.clauses.iter().any(|clause| {
+          in_region(clause, |clause| {
+            matches!(clause.kind, HandlerKind::Catch(_) | HandlerKind::Filter(_))
+          })
+        }),
+        Some(Exit::EndFilter) => !self.clauses.iter().any(|clause| {
+          clause
+            .filter_range()
+            .is_some_and(|filter| filter.end == block.range.end)
+        }),
+        _ => false,
+      };
+
+      if misplaced {
+        self.errors.insert(VerificationError {
+          offset: last,
+          kind: match block.exit {
+            Some(Exit::Return) => VerificationErrorKind::ReturnFromRegion,
+            _ => VerificationErrorKind::MisplacedInstruction,
+          },
+        });
+      }
+    }
+  }
+}
+
+/// Checks a transfer of control from `from` to `to` against the regions of a clause.
+fn region_transfer(
+  clause: &ExceptionClause,
+  from: u32,
+  to: u32,
+  kind: EdgeKind,
+) -> Option<VerificationErrorKind> {
+  let leave = kind == EdgeKind::Leave;
+  let try_range = clause.try_range();
+  let handler_range = clause.handler_range();
+  let filter_range = clause.filter_range().unwrap_or_default();
+
+  for range in [&handler_range, &filter_range] {
+    if !range.contains(&from) && range.contains(&to) {
+      return Some(VerificationErrorKind::BranchIntoHandler);
+    }
+  }
+
+  if !try_range.contains(&from) && try_range.contains(&to) && to != try_range.start {
+    return Some(VerificationErrorKind::BranchIntoTry);
+  }
+
+  let exits = |range: &core::ops::Range<u32>| range.contains(&from) && !range.contains(&to);
+  let handler_left_only_by_endfinally =
+    matches!(clause.kind, HandlerKind::Finally | HandlerKind::Fault) && exits(&handler_range);
+
+  if leave && (exits(&filter_range) || handler_left_only_by_endfinally) {
+    return Some(VerificationErrorKind::LeaveFromHandler);
+  }
+
+  if !leave && (exits(&try_range) || exits(&handler_range) || exits(&filter_range)) {
+    return Some(VerificationErrorKind::BranchOutOfRegion);
+  }
+
+  None
+}
+
+/// Merges the stack arriving along an edge into the state at the start of a block, returning
+/// whether the state changed.
+fn merge(state: &mut [StackType], incoming: &[StackType]) -> Result<bool, VerificationErrorKind> {
+  if state.len() != incoming.len() {
+    return Err(VerificationErrorKind::StackHeightMismatch {
+      expected: state.len(),
+      found: incoming.len(),
+    });
+  }
+
+  let mut changed = false;
+  for (existing, incoming) in state.iter_mut().zip(incoming) {
+    let merged = match (*existing, *incoming) {
+      (a, b) if a == b => a,
+      (StackType::Int32, StackType::NativeInt) | (StackType::NativeInt, StackType::Int32) => {
+        StackType::NativeInt
+      }
+      (StackType::ValueType, b) | (b, StackType::ValueType) if is_integer(b) => {
+        StackType::ValueType
+      }
+      (expected, found) => {
+        return Err(VerificationErrorKind::StackTypeMismatch { expected, found })
+      }
+    };
+
+    changed |= merged != *existing;
+    *existing = merged;
+  }
+
+  Ok(changed)
+}
+
+fn pop(stack: &mut Vec<StackType>) -> Result<StackType, VerificationErrorKind> {
+  stack.pop().ok_or(VerificationErrorKind::StackUnderflow)
+}
+
+fn expect(
+  found: StackType,
+  accepts: impl Fn(StackType) -> bool,
+) -> Result<(), VerificationErrorKind> {
+  match accepts(found) {
+    true => Ok(()),
+    false => Err(VerificationErrorKind::UnexpectedType { found }),
+  }
+}
+
+fn expect_empty(stack: &[StackType]) -> Result<(), VerificationErrorKind> {
+  match stack.is_empty() {
+    true => Ok(()),
+    false => Err(VerificationErrorKind::NonEmptyStack),
+  }
+}
+
+/// Checks a value is assignable to a location of the expected type (III.1.8.1.2.3).
+fn assign(found: StackType, expected: StackType) -> Result<(), VerificationErrorKind> {
+  let assignable = found == expected
+    || matches!(
+      (found, expected),
+      (StackType::Int32, StackType::NativeInt) | (StackType::NativeInt, StackType::Int32)
+    )
+    || (found == StackType::ValueType && is_integer(expected))
+    || (expected == StackType::ValueType && is_integer(found));
+
+  match assignable {
+    true => Ok(()),
+    false => Err(VerificationErrorKind::NotAssignable { expected, found }),
+  }
+}
+
+fn is_integer(ty: StackType) -> bool {
+  matches!(
+    ty,
+    StackType::Int32 | StackType::Int64 | StackType::NativeInt
+  )
+}
+
+/// Treats a value type operand of an arithmetic instruction as an enum, `int32` being the usual
+/// underlying type.
+fn integer(ty: StackType) -> StackType {
+  match ty {
+    StackType::ValueType => StackType::Int32,
+    ty => ty,
+  }
+}
+
+/// Gets the result of a binary numeric operation (III.1.5, tables 2 and 5).
+fn binary(opcode: OpCode, left: StackType, right: StackType) -> Option<StackType> {
+  use StackType::*;
+
+  // An enum operand takes the type of the other operand.
+  let (left, right) = match (left, right) {
+    (ValueType, right) if is_integer(right) => (right, right),
+    (left, ValueType) if is_integer(left) => (left, left),
+    (left, right) => (integer(left), integer(right)),
+  };
+
+  let integer_only = matches!(
+    opcode,
+    OpCode::DivUn | OpCode::RemUn | OpCode::And | OpCode::Or | OpCode::Xor
+  ) || opcode.name().contains(".ovf");
+  let is_add = matches!(opcode, OpCode::Add | OpCode::AddOvfUn);
+  let is_sub = matches!(opcode, OpCode::Sub | OpCode::SubOvfUn);
+
+  match (left, right) {
+    (Int32, Int32) => Some(Int32),
+    (Int32 | NativeInt, Int32 | NativeInt) => Some(NativeInt),
+    (Int64, Int64) => Some(Int64),
+    (Float, Float) if !integer_only => Some(Float),
+    (ByRef, Int32 | NativeInt) if is_add || is_sub => Some(ByRef),
+    (Int32 | NativeInt, ByRef) if is_add => Some(ByRef),
+    (ByRef, ByRef) if is_sub => Some(NativeInt),
+    _ => None,
+  }
+}
+
+/// Determines if two operands can be compared (III.1.5, table 4).
+fn comparable(left: StackType, right: StackType, equality: bool) -> bool {
+  use StackType::*;
+
+  let (left, right) = match (left, right) {
+    (ValueType, right) if is_integer(right) => (right, right),
+    (left, ValueType) if is_integer(left) => (left, left),
+    (left, right) => (integer(left), integer(right)),
+  };
+
+  match (left, right) {
+    (Int32 | NativeInt, Int32 | NativeInt) | (Int64, Int64) | (Float, Float) => true,
+    (ByRef, ByRef | NativeInt) | (NativeInt, ByRef) => true,
+    (Object, Object) => equality,
+    _ => false,
+  }
+}
+
+/// Gets the result of a `conv` instruction from its name, e.g. `conv.ovf.u2.un`.
+fn conversion(name: &str) -> StackType {
+  let target = name
+    .trim_start_matches("conv.")
+    .trim_start_matches("ovf.")
+    .trim_end_matches(".un");
+
+  match target {
+    "i8" | "u8" => StackType::Int64,
+    "i" | "u" => StackType::NativeInt,
+    "r4" | "r8" | "r" => StackType::Float,
+    _ => StackType::Int32,
+  }
+}
+
+/// Gets the type of the value loaded or stored by an instruction with a type suffix, e.g.
+/// `ldind.u2` or `stelem.ref`.
+fn suffix_type(name: &str) -> StackType {
+  match name.rsplit('.').next() {
+    Some("i8" | "u8") => StackType::Int64,
+    Some("i") => StackType::NativeInt,
+    Some("r4" | "r8") => StackType::Float,
+    Some("ref") => StackType::Object,
+    _ => StackType::Int32,
+  }
+}
+
+/// Gets the stack type of a primitive type.
+fn primitive(ty: &TypeSig) -> Option<StackType> {
+  Some(match ty {
+    TypeSig::Boolean
+    | TypeSig::Char
+    | TypeSig::I1
+    | TypeSig::U1
+    | TypeSig::I2
+    | TypeSig::U2
+    | TypeSig::I4
+    | TypeSig::U4 => StackType::Int32,
+    TypeSig::I8 | TypeSig::U8 => StackType::Int64,
+    TypeSig::R4 | TypeSig::R8 => StackType::Float,
+    TypeSig::I | TypeSig::U | TypeSig::Ptr(_) | TypeSig::FnPtr(_) => StackType::NativeInt,
+    TypeSig::String | TypeSig::Object | TypeSig::SzArray(_) | TypeSig::Array(_) => {
+      StackType::Object
+    }
+    TypeSig::Class(_) => StackType::Object,
+    TypeSig::GenericInst(inst) if !inst.value_type => StackType::Object,
+    TypeSig::TypedByRef => StackType::ValueType,
+    TypeSig::ByRef(_) => StackType::ByRef,
+    _ => return None,
+  })
+}
+
+/// Gets the stack type of the `System` types with a primitive element type.
+fn system_type(name: &str) -> Option<StackType> {
+  Some(match name {
+    "System.Boolean" | "System.Char" | "System.SByte" | "System.Byte" | "System.Int16"
+    | "System.UInt16" | "System.Int32" | "System.UInt32" => StackType::Int32,
+    "System.Int64" | "System.UInt64" => StackType::Int64,
+    "System.IntPtr" | "System.UIntPtr" => StackType::NativeInt,
+    "System.Single" | "System.Double" => StackType::Float,
+    "System.String" | "System.Object" => StackType::Object,
+    _ => return None,
+  })
+}
+
+/// Collects the rows of the type references used as value types in a signature.
+fn collect_value_type_refs(ty: &TypeSig, rows: &mut BTreeSet<u32>) {
+  match ty {
+    TypeSig::ValueType(TypeDefOrRef::TypeRef(id)) => {
+      rows.insert(id.row());
+    }
+    TypeSig::GenericInst(inst) => {
+      if let (true, TypeDefOrRef::TypeRef(id)) = (inst.value_type, inst.ty) {
+        rows.insert(id.row());
+      }
+
+      for arg in &inst.args {
+        collect_value_type_refs(arg, rows);
+      }
+    }
+    TypeSig::Ptr(ty)
+    | TypeSig::ByRef(ty)
+    | TypeSig::SzArray(ty)
+    | TypeSig::Modified(_, ty)
+    | TypeSig::Pinned(ty) => collect_value_type_refs(ty, rows),
+    TypeSig::Array(array) => collect_value_type_refs(&array.ty, rows),
+    TypeSig::FnPtr(sig) => {
+      for ty in sig.params.iter().chain([&sig.ret]) {
+        collect_value_type_refs(ty, rows);
+      }
+    }
+    _ => {}
+  }
+}
+
+impl<'a> Md<'a> {
+  /// Verifies the body of a single method, use a [Verifier] when verifying more than one.
+  pub fn verify_method(&self, id: MethodDefRowId) -> Result<Verification> {
+    Verifier::new(self)?
+      .verify(id)
+      .with_context(|| format!("Method {}", id.row()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    binary, comparable, conversion, merge, region_transfer, suffix_type, StackType::*,
+    VerificationErrorKind, Verifier,
+  };
+  use crate::{
+    ecma335::{
+      body::{ExceptionClause, HandlerKind},
+      tables::{Row, TypeSpecRow, TypeSpecRowId},
+      Md,
+    },
+    il::{cfg::EdgeKind, opcodes::OpCode},
+  };
+
+  #[test]
+  fn cyclic_type_spec() {
+    // TypeSpec 1 is `valuetype` TypeSpec 1.
+    let type_specs = [0x01u8, 0x00];
+    let blobs = [0x00u8, 0x02, 0x11, 0x06];
+    let root = Md::root_with_tables(&[(TypeSpecRow::ID, 1, &type_specs)], b"\0", &blobs);
+    let md = Md::from_cli_data(&root).unwrap();
+    let verifier = Verifier::new(&md).unwrap();
+
+    let id = TypeSpecRowId::new(1, md.tables().header()).unwrap();
+    assert!(verifier.type_spec(id, 0).is_err());
+  }
+
+  #[test]
+  fn operand_types() {
+    assert_eq!(binary(OpCode::Add, Int32, Int32), Some(Int32));
+    assert_eq!(binary(OpCode::Add, Int32, NativeInt), Some(NativeInt));
+    assert_eq!(binary(OpCode::Add, Int32, Int64), None);
+    assert_eq!(binary(OpCode::Mul, Float, Float), Some(Float));
+    assert_eq!(binary(OpCode::MulOvf, Float, Float), None);
+    assert_eq!(binary(OpCode::Xor, Float, Float), None);
+    assert_eq!(binary(OpCode::Add, ByRef, Int32), Some(ByRef));
+    assert_eq!(binary(OpCode::Sub, ByRef, ByRef), Some(NativeInt));
+    assert_eq!(binary(OpCode::Mul, ByRef, Int32), None);
+    assert_eq!(binary(OpCode::Or, ValueType, Int64), Some(Int64));
+    assert_eq!(binary(OpCode::Add, Object, Int32), None);
+
+    assert!(comparable(Object, Object, true));
+    assert!(!comparable(Object, Object, false));
+    assert!(comparable(NativeInt, ByRef, false));
+    assert!(!comparable(Float, Int32, true));
+
+    assert_eq!(conversion("conv.ovf.u2.un"), Int32);
+    assert_eq!(conversion("conv.u8"), Int64);
+    assert_eq!(conversion("conv.ovf.i"), NativeInt);
+    assert_eq!(conversion("conv.r.un"), Float);
+    assert_eq!(suffix_type("ldind.ref"), Object);
+    assert_eq!(suffix_type("stelem.r4"), Float);
+    assert_eq!(suffix_type("ldelem.u1"), Int32);
+  }
+
+  #[test]
+  fn merge_stacks() {
+    let mut state = [Int32, Object];
+    assert_eq!(merge(&mut state, &[Int32, Object]), Ok(false));
+    assert_eq!(merge(&mut state, &[NativeInt, Object]), Ok(true));
+    assert_eq!(state, [NativeInt, Object]);
+    assert_eq!(
+      merge(&mut state, &[NativeInt]),
+      Err(VerificationErrorKind::StackHeightMismatch {
+        expected: 2,
+        found: 1
+      })
+    );
+    assert_eq!(
+      merge(&mut state, &[NativeInt, Float]),
+      Err(VerificationErrorKind::StackTypeMismatch {
+        expected: Object,
+        found: Float
+      })
+    );
+  }
+
+  #[test]
+  fn region_transfers() {
+    let finally = ExceptionClause {
+      kind: HandlerKind::Finally,
+      try_offset: 0x10,
+      try_length: 0x10,
+      handler_offset: 0x20,
+      handler_length: 0x10,
+    };
+
+    let branch = |from, to, kind| region_transfer(&finally, from, to, kind);
+    assert_eq!(branch(0x00, 0x10, EdgeKind::Branch), None);
+    assert_eq!(
+      branch(0x00, 0x14, EdgeKind::Branch),
+      Some(VerificationErrorKind::BranchIntoTry)
+    );
+    assert_eq!(
+      branch(0x00, 0x24, EdgeKind::Branch),
+      Some(VerificationErrorKind::BranchIntoHandler)
+    );
+    assert_eq!(
+      branch(0x14, 0x40, EdgeKind::Branch),
+      Some(VerificationErrorKind::BranchOutOfRegion)
+    );
+    assert_eq!(branch(0x14, 0x40, EdgeKind::Leave), None);
+    assert_eq!(branch(0x14, 0x18, EdgeKind::Branch), None);
+    assert_eq!(
+      branch(0x24, 0x40, EdgeKind::Leave),
+      Some(VerificationErrorKind::LeaveFromHandler)
+    );
+  }
+}
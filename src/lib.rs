@@ -2,6 +2,7 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 pub mod ecma335;
+pub mod il;
 pub mod pe;
 
 extern crate alloc;
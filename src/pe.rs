@@ -1,10 +1,11 @@
 //! Portable executable parsing.
 
-use crate::ecma335::Md;
-use anyhow::{anyhow, Result};
+use crate::ecma335::{token::Token, Md};
+use alloc::vec::Vec;
+use anyhow::{anyhow, bail, Result};
 use goblin::pe::{
   data_directories::DataDirectory, optional_header::OptionalHeader, options::ParseOptions,
-  utils::find_offset, PE,
+  section_table::SectionTable, utils::find_offset, PE,
 };
 use scroll::Pread;
 
@@ -29,8 +30,78 @@ impl<'a> Md<'a> {
     let cor20 = cor20.pread::<Cor20Header>(0)?;
 
     let metadata = read_data_directory(&pe, &optional_header, &cor20.metadata, buf)?;
+    let image = PeImage {
+      buf,
+      sections: pe.sections,
+      cor20,
+    };
 
-    Ok(Some(Self::from_cli_data(metadata)?))
+    Ok(Some(Self::from_cli_data(metadata)?.with_image(image)))
+  }
+}
+
+/// The parts of a PE image needed to reach the data that metadata refers to by RVA, such as
+/// method bodies and field initial values.
+#[derive(Clone)]
+pub struct PeImage<'a> {
+  buf: &'a [u8],
+  sections: Vec<SectionTable>,
+  cor20: Cor20Header,
+}
+
+impl<'a> PeImage<'a> {
+  /// Gets the whole image.
+  pub fn buf(&self) -> &'a [u8] {
+    self.buf
+  }
+
+  /// Gets the section table.
+  pub fn sections(&self) -> &[SectionTable] {
+    &self.sections
+  }
+
+  /// Gets the CLI header.
+  pub fn cor20_header(&self) -> &Cor20Header {
+    &self.cor20
+  }
+
+  /// Gets the section containing the given RVA.
+  pub fn section(&self, rva: u32) -> Option<&SectionTable> {
+    self.sections.iter().find(|section| {
+      let size = section.virtual_size.max(section.size_of_raw_data);
+
+      rva >= section.virtual_address && rva - section.virtual_address < size
+    })
+  }
+
+  /// Maps an RVA to an offset in the image, `None` if it isn't backed by file data.
+  pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+    let section = self.section(rva)?;
+    let delta = rva - section.virtual_address;
+
+    match delta < section.size_of_raw_data {
+      true => Some(section.pointer_to_raw_data as usize + delta as usize),
+      false => None,
+    }
+  }
+
+  /// Gets the data from the given RVA up to the end of its section.
+  pub fn read_rva(&self, rva: u32) -> Result<&'a [u8]> {
+    let section = match self.section(rva) {
+      Some(section) => section,
+      None => bail!("RVA {:#x} isn't in any section", rva),
+    };
+
+    let start = match self.rva_to_offset(rva) {
+      Some(start) => start,
+      None => bail!("RVA {:#x} isn't backed by file data", rva),
+    };
+
+    let end = section.pointer_to_raw_data as usize + section.size_of_raw_data as usize;
+    match self.buf.get(start..end.min(self.buf.len())) {
+      Some(data) => Ok(data),
+      None => bail!("RVA {:#x} is outside of the image", rva),
+    }
   }
 }
 
@@ -80,6 +151,20 @@ pub struct Cor20Header {
   pub managed_native_header: DataDirectory,
 }
 
+impl Cor20Header {
+  /// Gets the `MethodDef` or `File` token of the entry point, `None` for libraries and images with
+  /// a native entry point.
+  pub fn entry_point(&self) -> Option<Token> {
+    match self
+      .flags
+      .contains(CorMetaFlags::COMIMAGE_FLAGS_NATIVE_ENTRYPOINT)
+    {
+      true => None,
+      false => Some(Token::from(self.entry_point_token)).filter(|token| !token.is_null()),
+    }
+  }
+}
+
 bitflags::bitflags! {
   #[derive(Pread)]
   pub struct CorMetaFlags: u32 {
@@ -0,0 +1,153 @@
+use recil::{
+  ecma335::{
+    tables::{MethodDefRowId, RowIndex},
+    Md,
+  },
+  il::{Assembler, Disassembler, FixupKind, Instructions, OpCode, Operand},
+};
+
+#[test]
+fn reassemble_method_bodies() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let disassembler = Disassembler::new(&md).unwrap();
+  let assembler = Assembler::new(&md).unwrap();
+  let mut index = MethodDefRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    index = id.next();
+
+    let row = md.tables().method_defs().read(id).unwrap();
+    let body = match md.method_body(&row).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    // Keep only the instructions, dropping the directives and try blocks.
+    let text = disassembler.method(id).unwrap().to_string();
+    let text = text
+      .lines()
+      .filter(|line| line.trim_start().starts_with("IL_"))
+      .collect::<Vec<_>>()
+      .join("\n");
+
+    let assembled = assembler.assemble_method(id, &text).unwrap();
+    assert!(assembled.fixups.is_empty(), "{:?}", assembled.fixups);
+    assert_eq!(assembled.code.len(), body.code.len());
+
+    let expected = Instructions::new(body.code);
+    let actual = Instructions::new(&assembled.code);
+
+    for (expected, actual) in expected.zip(actual) {
+      let (expected, actual) = (expected.unwrap(), actual.unwrap());
+      assert_eq!(expected.opcode, actual.opcode);
+
+      match (&expected.operand, &actual.operand) {
+        // Rows with identical names, like duplicate type specs, resolve to the first of them.
+        (Operand::Token(a), Operand::Token(b)) if a != b && expected.opcode != OpCode::Ldstr => {
+          assert_eq!(
+            disassembler.token(*a).unwrap(),
+            disassembler.token(*b).unwrap()
+          )
+        }
+        // NaN doesn't equal itself.
+        (Operand::Float64(a), Operand::Float64(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+        (Operand::Float32(a), Operand::Float32(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+        (expected, actual) => assert_eq!(expected, actual),
+      }
+    }
+  }
+}
+
+#[test]
+fn assemble_fixups() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let assembler = Assembler::new(&md).unwrap();
+
+  let assembled = assembler
+    .assemble(
+      r#"
+        ldarg.0
+        brtrue.s   not_null         // forward branch
+        ldstr      "not in the heap"
+        newobj     instance void [System.Runtime]System.ArgumentNullException::.ctor(string)
+        throw
+      not_null:
+        ldarg.0
+        call       void Missing::Method(object)
+        ret
+      "#,
+    )
+    .unwrap();
+
+  let ctor = assembler
+    .assemble("newobj instance void [System.Runtime]System.ArgumentNullException::.ctor(string)")
+    .unwrap();
+  let token = u32::from_le_bytes(ctor.code[1..5].try_into().unwrap());
+  assert_eq!(token >> 24, 0x0a);
+
+  #[rustfmt::skip]
+  let mut expected = vec![
+    // IL_0000: ldarg.0
+    0x02,
+    // IL_0001: brtrue.s IL_000e
+    0x2d, 0x0b,
+    // IL_0003: ldstr <fixup>
+    0x72, 0x00, 0x00, 0x00, 0x00,
+    // IL_0008: newobj
+    0x73,
+  ];
+  expected.extend(token.to_le_bytes());
+  #[rustfmt::skip]
+  expected.extend([
+    // IL_000d: throw
+    0x7a,
+    // IL_000e: ldarg.0
+    0x02,
+    // IL_000f: call <fixup>
+    0x28, 0x00, 0x00, 0x00, 0x00,
+    // IL_0014: ret
+    0x2a,
+  ]);
+
+  assert_eq!(assembled.code, expected);
+  assert_eq!(assembled.fixups.len(), 2);
+  assert_eq!(assembled.fixups[0].offset, 4);
+  assert_eq!(
+    assembled.fixups[0].kind,
+    FixupKind::UserString("not in the heap".encode_utf16().collect())
+  );
+  assert_eq!(assembled.fixups[1].offset, 0x10);
+  assert_eq!(
+    assembled.fixups[1].kind,
+    FixupKind::Method("void Missing::Method(object)".into())
+  );
+}
+
+#[test]
+fn assemble_malformed() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let assembler = Assembler::new(&md).unwrap();
+
+  let cases = [
+    "frobnicate",
+    "ret 1",
+    "ldc.i4",
+    "ldc.i4.s 256",
+    "ldc.i4.s 200",
+    "ldc.i4.s -129",
+    "br.s missing",
+    "a: nop\na: nop",
+    "ldstr \"unterminated",
+    "ldloc.s V_256",
+  ];
+
+  for case in cases {
+    assert!(assembler.assemble(case).is_err(), "{}", case);
+  }
+
+  let far = format!("br.s end\n{}\nend: ret", "nop\n".repeat(128));
+  assert!(assembler.assemble(&far).is_err());
+}
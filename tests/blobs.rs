@@ -0,0 +1,51 @@
+mod common;
+
+use common::first_row;
+use recil::ecma335::Md;
+
+#[test]
+fn blobs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let mut blobs = md.blobs().iter();
+  let (index, blob) = blobs.next().unwrap().unwrap();
+
+  assert_eq!(index.offset(), 0);
+  assert!(blob.is_empty());
+
+  for blob in blobs {
+    let (index, blob) = blob.unwrap();
+
+    assert_eq!(md.blobs().get(index).unwrap(), blob);
+  }
+}
+
+#[test]
+fn assembly_public_key() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let assembly = first_row(md.tables().assemblies());
+  let public_key = md.blobs().get(assembly.public_key).unwrap();
+
+  // PublicKeyBlob: SigAlgId (CALG_RSA_SIGN), HashAlgId (CALG_SHA1), then a 1024-bit RSA key.
+  assert_eq!(public_key.len(), 160);
+  assert_eq!(
+    &public_key[..8],
+    &[0x00, 0x24, 0x00, 0x00, 0x04, 0x80, 0x00, 0x00]
+  );
+}
+
+#[test]
+fn signatures() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for method in md.tables().method_defs() {
+    let method = method.unwrap();
+    let signature = md.blobs().get(method.signature).unwrap();
+
+    assert!(!signature.is_empty());
+  }
+}
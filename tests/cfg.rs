@@ -0,0 +1,57 @@
+use recil::ecma335::Md;
+
+#[test]
+fn control_flow_graphs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let header = md.tables().header();
+  let mut loops = 0;
+  let mut handlers = 0;
+
+  for row in md.tables().method_defs() {
+    let row = row.unwrap();
+    let body = match md.method_body(&row).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    let cfg = body.control_flow_graph(header).unwrap();
+    let blocks = cfg.blocks();
+
+    // The blocks partition the code.
+    assert_eq!(blocks[0].range.start, 0);
+    assert_eq!(blocks.last().unwrap().range.end, body.code.len() as u32);
+    assert!(blocks
+      .windows(2)
+      .all(|pair| pair[0].range.end == pair[1].range.start));
+
+    for (i, block) in blocks.iter().enumerate() {
+      let instructions = cfg.block_instructions(i);
+      assert_eq!(instructions.first().unwrap().offset, block.range.start);
+      assert_eq!(instructions.last().unwrap().next_offset(), block.range.end);
+
+      for edge in &block.successors {
+        assert!(blocks[edge.target].predecessors.contains(&i));
+      }
+
+      // Compilers only emit code reachable from the entry or a handler.
+      if cfg.is_reachable(i) {
+        assert!(cfg.dominates(0, i));
+      }
+    }
+
+    loops += cfg.loop_headers().len();
+    handlers += body.exception_clauses(header).unwrap().len();
+
+    for clause in body.exception_clauses(header).unwrap() {
+      let handler = cfg.block_at(clause.handler_offset).unwrap();
+      let protected = cfg.block_at(clause.try_offset).unwrap();
+
+      // Try blocks can only be entered at their first instruction.
+      assert!(cfg.dominates(protected, handler));
+    }
+  }
+
+  assert!(loops > 0);
+  assert!(handlers > 0);
+}
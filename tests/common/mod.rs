@@ -1,4 +1,6 @@
-use guid_create::GUID;
+// Not every test binary uses every helper.
+#![allow(dead_code)]
+
 use recil::ecma335::{
   guids::GuidIndex,
   strings::StringIndex,
@@ -6,16 +8,34 @@ use recil::ecma335::{
   Md,
 };
 
+/// Gets the expected rows of the given table from `inputs/Newtonsoft.Json.toml`.
+pub fn expected_rows(table: &str) -> Vec<toml::Value> {
+  let expected = include_str!("../inputs/Newtonsoft.Json.toml");
+  let expected = expected.parse::<toml::Table>().unwrap();
+
+  expected[table].as_array().unwrap().clone()
+}
+
+/// Gets the `(kind, row_id)` of an expected coded index, as written by
+/// `System.Reflection.Metadata`.
+pub fn expected_handle(value: &toml::Value) -> (&str, u32) {
+  (
+    value["kind"].as_str().unwrap(),
+    value["row_id"].as_integer().unwrap() as u32,
+  )
+}
+
 /// Gets the first row from the given [TableRowReader].
 pub fn first_row<'a, R: Row<'a>>(table: TableRowReader<'a, '_, R>) -> R {
   table.into_iter().next().unwrap().unwrap()
 }
 
-/// Asserts that the given [actual] guid is equal to the given [expected] guid.
-pub fn assert_guid_eq(md: &Md<'_>, actual: GuidIndex, expected: &str) {
+/// Asserts that the given [actual] guid is equal to the given [expected] guid, where `None` is a
+/// null guid.
+pub fn assert_guid_eq(md: &Md<'_>, actual: GuidIndex, expected: Option<&str>) {
   let actual = md.guids().get(actual).expect("Failed to read guid");
 
-  assert_eq!(GUID::build_from_slice(&actual).to_string(), expected);
+  assert_eq!(actual.map(|guid| guid.to_string()).as_deref(), expected);
 }
 
 /// Asserts that the given [actual] str is equal to the given [expected] str.
@@ -0,0 +1,73 @@
+use recil::ecma335::{
+  constants::ConstantValue,
+  tables::{FieldRowId, HasConstant, RowIndex},
+  Md,
+};
+
+/// Finds the id of the first field with the given name.
+fn field_id(md: &Md, name: &str) -> FieldRowId {
+  let mut index = FieldRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    let field = md.tables().fields().read(id).unwrap();
+    if md.strings().get(field.name).unwrap() == name {
+      return id;
+    }
+
+    index = id.next();
+  }
+
+  panic!("No field named `{}`", name);
+}
+
+#[test]
+fn constants() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for row in md.tables().constants() {
+    let row = row.unwrap();
+    let value = md.constant_value(&row).unwrap();
+
+    assert_eq!(md.constant(row.parent).unwrap(), Some(value));
+  }
+}
+
+#[test]
+fn enum_literal() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `Formatting.Indented = 1`
+  let id = field_id(&md, "Indented");
+
+  assert_eq!(
+    md.constant(HasConstant::Field(id)).unwrap(),
+    Some(ConstantValue::I4(1))
+  );
+}
+
+#[test]
+fn string_literal() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let id = field_id(&md, "DefaultDateFormatString");
+  let value = md.constant(HasConstant::Field(id)).unwrap().unwrap();
+
+  assert_eq!(
+    value.to_string(),
+    "\"yyyy'-'MM'-'dd'T'HH':'mm':'ss.FFFFFFFK\""
+  );
+}
+
+#[test]
+fn missing_constant() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `JsonConvert.True` is `static readonly`, not `const`.
+  let id = field_id(&md, "True");
+
+  assert_eq!(md.constant(HasConstant::Field(id)).unwrap(), None);
+}
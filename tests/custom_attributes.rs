@@ -0,0 +1,135 @@
+use recil::ecma335::{
+  attributes::{AttributeArg, AttributeType},
+  signatures::TypeSig,
+  tables::HasCustomAttribute,
+  Md,
+};
+
+/// Resolves the enums from `System.Runtime` used by attributes in the test assembly.
+fn resolve_enum(name: &str) -> Option<TypeSig> {
+  match name {
+    "System.AttributeTargets" | "System.Diagnostics.DebuggableAttribute+DebuggingModes" => {
+      Some(TypeSig::I4)
+    }
+    _ => None,
+  }
+}
+
+#[test]
+fn custom_attributes() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for row in md.tables().custom_attributes() {
+    md.custom_attribute_with(&row.unwrap(), &resolve_enum)
+      .unwrap();
+  }
+}
+
+#[test]
+fn unresolved_enum() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let failed = md
+    .tables()
+    .custom_attributes()
+    .into_iter()
+    .map(|row| md.custom_attribute(&row.unwrap()))
+    .filter(|value| value.is_err())
+    .count();
+
+  assert!(failed > 0);
+}
+
+#[test]
+fn target_framework_attribute() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `[assembly: TargetFramework(".NETCoreApp,Version=v6.0", FrameworkDisplayName = "")]`
+  let value = md
+    .tables()
+    .custom_attributes()
+    .into_iter()
+    .map(|row| row.unwrap())
+    .filter(|row| matches!(row.parent, HasCustomAttribute::Assembly(_)))
+    .map(|row| md.custom_attribute_with(&row, &resolve_enum).unwrap())
+    .find(|value| value.named("FrameworkDisplayName").is_some())
+    .unwrap();
+
+  assert_eq!(
+    value.fixed_args,
+    [AttributeArg::String(Some(
+      ".NETCoreApp,Version=v6.0".into()
+    ))]
+  );
+  assert_eq!(
+    value.named("FrameworkDisplayName"),
+    Some(&AttributeArg::String(Some("".into())))
+  );
+}
+
+#[test]
+fn attribute_usage_attribute() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let value = md
+    .tables()
+    .custom_attributes()
+    .into_iter()
+    .map(|row| {
+      md.custom_attribute_with(&row.unwrap(), &resolve_enum)
+        .unwrap()
+    })
+    .find(|value| value.named("AllowMultiple").is_some())
+    .unwrap();
+
+  let [AttributeArg::Enum(name, targets)] = &value.fixed_args[..] else {
+    panic!("Expected AttributeTargets, found {:?}", value.fixed_args);
+  };
+
+  assert_eq!(name, "System.AttributeTargets");
+  assert!(matches!(**targets, AttributeArg::I4(_)));
+  assert_eq!(value.named_args[0].ty, AttributeType::Boolean);
+}
+
+#[test]
+fn nullable_attribute() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `[Nullable(new byte[] { ... })]` is emitted into the assembly itself.
+  let arrays = md
+    .tables()
+    .custom_attributes()
+    .into_iter()
+    .map(|row| md.custom_attribute(&row.unwrap()))
+    .filter_map(|value| value.ok())
+    .filter(|value| {
+      matches!(
+        &value.fixed_args[..],
+        [AttributeArg::Array(AttributeType::U1, Some(_))]
+      )
+    })
+    .count();
+
+  assert!(arrays > 0);
+}
+
+#[test]
+fn local_enum_type() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  assert_eq!(
+    md.local_enum_type("Newtonsoft.Json.Formatting").unwrap(),
+    Some(TypeSig::I4)
+  );
+  assert_eq!(
+    md.local_enum_type("Newtonsoft.Json.JsonConvert").unwrap(),
+    None
+  );
+  assert_eq!(md.local_enum_type("System.AttributeTargets").unwrap(), None);
+}
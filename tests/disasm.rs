@@ -0,0 +1,100 @@
+use recil::{
+  ecma335::{
+    tables::{MethodDefRowId, RowIndex},
+    Md,
+  },
+  il::{Disassembler, MethodDisassembly},
+};
+
+/// Disassembles the first method with the given qualified name.
+fn disassemble(disassembler: &Disassembler, md: &Md, name: &str) -> MethodDisassembly {
+  let mut index = MethodDefRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    let method = disassembler.method(id).unwrap();
+    if method.qualified_name() == name {
+      return method;
+    }
+
+    index = id.next();
+  }
+
+  panic!("No method named `{}`", name);
+}
+
+#[test]
+fn disassemble_methods() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let disassembler = Disassembler::new(&md).unwrap();
+  let mut index = MethodDefRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    let method = disassembler.method(id).unwrap().to_string();
+
+    assert!(method.starts_with(".method "));
+    assert_eq!(
+      method.matches('{').count(),
+      method.matches('}').count(),
+      "{}",
+      method
+    );
+
+    index = id.next();
+  }
+}
+
+#[test]
+fn disassemble_tiny_method() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let disassembler = Disassembler::new(&md).unwrap();
+  let method = disassemble(
+    &disassembler,
+    &md,
+    "Newtonsoft.Json.JsonConvert::SerializeObject",
+  );
+
+  assert_eq!(
+    method.to_string(),
+    [
+      ".method public hidebysig static string SerializeObject(object 'value') cil managed",
+      "{",
+      "  .maxstack  8",
+      "  IL_0000:  ldarg.0",
+      "  IL_0001:  ldnull",
+      "  IL_0002:  ldnull",
+      "  IL_0003:  call       string Newtonsoft.Json.JsonConvert::SerializeObject(object, \
+       class [System.Runtime]System.Type, class Newtonsoft.Json.JsonSerializerSettings)",
+      "  IL_0008:  ret",
+      "} // end of method JsonConvert::SerializeObject",
+    ]
+    .join("\n")
+  );
+}
+
+#[test]
+fn disassemble_try_blocks() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let disassembler = Disassembler::new(&md).unwrap();
+  let mut index = MethodDefRowId::first(md.tables().header());
+  let mut finallies = 0;
+  let mut catches = 0;
+
+  while let Some(id) = index {
+    let method = disassembler.method(id).unwrap().to_string();
+    let blocks = method.lines().filter(|line| line.trim() == ".try").count();
+
+    assert_eq!(blocks, method.matches("}  // end .try\n").count());
+    finallies += method.matches("}  // end .try\n  finally\n  {\n").count();
+    catches += method
+      .matches("catch [System.Runtime]System.Exception\n")
+      .count();
+
+    index = id.next();
+  }
+
+  assert!(finallies > 0);
+  assert!(catches > 0);
+}
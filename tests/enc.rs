@@ -0,0 +1,224 @@
+use recil::ecma335::{
+  enc::EncMd,
+  tables::{MethodDefRow, MethodDefRowId, ModuleRow, ModuleRowId, Row, TypeDefRowId},
+  token::Token,
+  user_strings::UserStringIndex,
+  Md,
+};
+
+const GUID: [u8; 16] = [
+  0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+];
+
+/// Writes a metadata root with the given streams.
+fn metadata(streams: &[(&str, Vec<u8>)]) -> Vec<u8> {
+  let version = b"v4.0.30319\0\0";
+  let headers_size = streams
+    .iter()
+    .map(|(name, _)| 8 + (name.len() + 4) / 4 * 4)
+    .sum::<usize>();
+  let mut offset = 16 + version.len() + 4 + headers_size;
+
+  let mut buf = Vec::new();
+  buf.extend(0x424A5342u32.to_le_bytes());
+  buf.extend(1u16.to_le_bytes());
+  buf.extend(1u16.to_le_bytes());
+  buf.extend(0u32.to_le_bytes());
+  buf.extend((version.len() as u32).to_le_bytes());
+  buf.extend(version);
+  buf.extend(0u16.to_le_bytes());
+  buf.extend((streams.len() as u16).to_le_bytes());
+
+  for (name, data) in streams {
+    buf.extend((offset as u32).to_le_bytes());
+    buf.extend((data.len() as u32).to_le_bytes());
+    buf.extend(name.as_bytes());
+    buf.resize(buf.len() + 4 - name.len() % 4, 0);
+    offset += data.len();
+  }
+
+  for (_, data) in streams {
+    buf.extend(data);
+  }
+
+  buf
+}
+
+/// Writes a minimal delta renaming the module and a method of the second type and adding a method
+/// to the type, where the added method is given the row `added`.
+fn delta(base: &Md, added: u32) -> Vec<u8> {
+  let strings = base.strings().size() as u32;
+  let guids = base.guids().len() as u32;
+  let blobs = base.blobs().size() as u32;
+  let method_defs = base.tables().method_defs().len() as u32;
+  let params = base.tables().params().len() as u32;
+  let ty = TypeDefRowId::new(2, base.tables().header()).unwrap();
+  let method = base.tables().type_def_methods(ty).unwrap().next().unwrap();
+  let row = base.tables().method_defs().read(method).unwrap();
+
+  let mut tables = Vec::new();
+  tables.extend(0u32.to_le_bytes());
+  tables.extend([2, 0, 0xa7, 1]);
+  tables.extend((1u64 << 0x00 | 1 << 0x06 | 1 << 0x1e | 1 << 0x1f).to_le_bytes());
+  tables.extend(0u64.to_le_bytes());
+  for rows in [1u32, 2, 4, 3] {
+    tables.extend(rows.to_le_bytes());
+  }
+
+  // Module: generation, name, mvid, enc_id, enc_base_id
+  tables.extend(1u16.to_le_bytes());
+  for column in [strings, 1, guids + 1, 0] {
+    tables.extend(column.to_le_bytes());
+  }
+
+  // MethodDef: rva, impl_flags, flags, name, signature, param_list
+  for (name, signature) in [
+    (strings + 10, row.signature.offset() as u32),
+    (strings + 18, blobs),
+  ] {
+    tables.extend(row.rva.to_le_bytes());
+    tables.extend(row.impl_flags.bits().to_le_bytes());
+    tables.extend(row.flags.bits().to_le_bytes());
+    for column in [name, signature, params + 1] {
+      tables.extend(column.to_le_bytes());
+    }
+  }
+
+  // ENCLog: token, func_code
+  let added = (MethodDefRow::ID as u32) << 24 | added;
+  for (token, func_code) in [
+    (0x0000_0001, 0u32),
+    (method.token().value(), 0),
+    (ty.token().value(), 1),
+    (added, 0),
+  ] {
+    tables.extend(token.to_le_bytes());
+    tables.extend(func_code.to_le_bytes());
+  }
+
+  // ENCMap: token
+  assert!(method.row() < method_defs);
+  for token in [0x0000_0001, method.token().value(), added] {
+    tables.extend(token.to_le_bytes());
+  }
+
+  metadata(&[
+    ("#-", tables),
+    ("#Strings", b"Delta.dll\0Renamed\0Added\0\0\0".to_vec()),
+    ("#US", b"\x05h\0i\0\0\0\0".to_vec()),
+    ("#GUID", GUID.to_vec()),
+    ("#Blob", b"\x03\x01\x02\x03".to_vec()),
+    ("#JTD", Vec::new()),
+  ])
+}
+
+#[test]
+fn apply_deltas() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let base = Md::parse_from_pe(pe).unwrap().unwrap();
+  let old = Md::parse_from_pe(pe).unwrap().unwrap();
+  let method_defs = base.tables().method_defs().len() as u32;
+  let delta = delta(&base, method_defs + 1);
+
+  let enc = EncMd::new(base, [delta.as_slice()]).unwrap();
+  assert_eq!(enc.generations(), 2);
+
+  let module = enc
+    .read::<ModuleRow>(ModuleRowId::new(1, enc.header()).unwrap())
+    .unwrap();
+  assert_eq!(module.generation, 1);
+  assert_eq!(enc.string(module.name).unwrap(), "Delta.dll");
+  assert_eq!(enc.guid(module.enc_id).unwrap().unwrap().as_bytes(), &GUID);
+  assert_eq!(
+    enc.guid(module.mvid).unwrap(),
+    old.guids().get(module.mvid).unwrap()
+  );
+
+  let ty = TypeDefRowId::new(2, enc.header()).unwrap();
+  let methods = enc.type_def_methods(ty).unwrap();
+  let renamed = methods[0];
+  let added = *methods.last().unwrap();
+  assert_eq!(
+    methods.len(),
+    old
+      .tables()
+      .type_def_methods(TypeDefRowId::new(2, old.tables().header()).unwrap())
+      .unwrap()
+      .len()
+      + 1
+  );
+  assert_eq!(added.row(), method_defs + 1);
+
+  let name = |id: MethodDefRowId| {
+    enc
+      .string(enc.read::<MethodDefRow>(id).unwrap().name)
+      .unwrap()
+  };
+  assert_eq!(name(renamed), "Renamed");
+  assert_eq!(name(added), "Added");
+  assert_ne!(
+    old
+      .strings()
+      .get(old.tables().method_defs().read(renamed).unwrap().name)
+      .unwrap(),
+    "Renamed"
+  );
+
+  assert_eq!(
+    enc.locate(renamed.token()).unwrap(),
+    (1, Token::from(0x0600_0001))
+  );
+  assert_eq!(
+    enc.locate(added.token()).unwrap(),
+    (1, Token::from(0x0600_0002))
+  );
+  assert_eq!(
+    enc.locate(Token::from(0x0600_0001)).unwrap().0,
+    (renamed.row() == 1) as usize
+  );
+  assert!(enc
+    .locate(Token::from(0x0600_0000 | (method_defs + 2)))
+    .is_err());
+
+  assert_eq!(enc.header().rows[MethodDefRow::ID], method_defs + 1);
+  assert_eq!(enc.rows::<MethodDefRow>().count(), method_defs as usize + 1);
+  assert!(enc.rows::<MethodDefRow>().all(|row| row.is_ok()));
+
+  let signature = enc.read::<MethodDefRow>(added).unwrap().signature;
+  assert_eq!(signature.offset(), old.blobs().size());
+  assert_eq!(enc.blob(signature).unwrap(), [1, 2, 3]);
+
+  let hi = old.user_strings().size() as u32;
+  let hi = UserStringIndex::from_token(0x7000_0000 | hi).unwrap();
+  assert_eq!(enc.user_string(hi).unwrap().decode().unwrap(), "hi");
+  assert_eq!(
+    enc
+      .user_string(UserStringIndex::from_token(0x7000_0001).unwrap())
+      .unwrap()
+      .decode()
+      .unwrap(),
+    old
+      .user_strings()
+      .get(UserStringIndex::from_token(0x7000_0001).unwrap())
+      .unwrap()
+      .decode()
+      .unwrap()
+  );
+}
+
+#[test]
+fn reject_malformed_deltas() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let base = Md::parse_from_pe(pe).unwrap().unwrap();
+  let method_defs = base.tables().method_defs().len() as u32;
+  let skips_rows = delta(&base, method_defs + 2);
+  let valid = delta(&base, method_defs + 1);
+
+  let mut enc = EncMd::new(base, []).unwrap();
+  assert!(enc.apply(&skips_rows).is_err());
+  assert_eq!(enc.generations(), 1);
+  assert_eq!(enc.header().rows[MethodDefRow::ID], method_defs);
+
+  enc.apply(&valid).unwrap();
+  assert_eq!(enc.generations(), 2);
+}
@@ -0,0 +1,63 @@
+use recil::{
+  ecma335::Md,
+  il::{OpCode, Operand},
+};
+use std::collections::HashSet;
+
+#[test]
+fn decode_method_bodies() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for row in md.tables().method_defs() {
+    let body = match md.method_body(&row.unwrap()).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    let instructions = body
+      .instructions()
+      .collect::<anyhow::Result<Vec<_>>>()
+      .unwrap();
+    let offsets = instructions
+      .iter()
+      .map(|instruction| instruction.offset)
+      .collect::<HashSet<_>>();
+
+    assert_eq!(
+      instructions.last().unwrap().next_offset() as usize,
+      body.code.len()
+    );
+
+    for instruction in &instructions {
+      match &instruction.operand {
+        Operand::Branch(target) => assert!(offsets.contains(target)),
+        Operand::Switch(targets) => assert!(targets.iter().all(|target| offsets.contains(target))),
+        _ => {}
+      }
+    }
+  }
+}
+
+#[test]
+fn decode_tiny_method() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let instructions = md
+    .tables()
+    .method_defs()
+    .into_iter()
+    .map(|row| row.unwrap())
+    .find(|row| md.strings().get(row.name).unwrap() == "SerializeObject")
+    .and_then(|row| md.method_body(&row).unwrap())
+    .unwrap()
+    .instructions()
+    .collect::<anyhow::Result<Vec<_>>>()
+    .unwrap();
+
+  assert_eq!(instructions.last().unwrap().opcode, OpCode::Ret);
+  assert!(instructions
+    .iter()
+    .any(|instruction| instruction.opcode == OpCode::Call));
+}
@@ -0,0 +1,16 @@
+use recil::ecma335::{
+  tables::{FieldRowId, HasFieldMarshal, RowIndex},
+  Md,
+};
+
+#[test]
+fn default_marshalling() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // Nothing in the assembly uses `[MarshalAs]`.
+  assert!(md.tables().field_marshals().is_empty());
+
+  let id = FieldRowId::first(md.tables().header()).unwrap();
+  assert_eq!(md.field_marshal(HasFieldMarshal::Field(id)).unwrap(), None);
+}
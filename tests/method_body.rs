@@ -0,0 +1,124 @@
+use recil::ecma335::{
+  body::{HandlerKind, SectionKind, TryBlock},
+  tables::{MethodDefRow, MethodDefRowId, RowIndex, TypeDefOrRef},
+  Md,
+};
+
+/// Finds the first method with the given name.
+fn method(md: &Md, name: &str) -> MethodDefRow {
+  let mut index = MethodDefRowId::first(md.tables().header());
+
+  while let Some(id) = index {
+    let method = md.tables().method_defs().read(id).unwrap();
+    if md.strings().get(method.name).unwrap() == name {
+      return method;
+    }
+
+    index = id.next();
+  }
+
+  panic!("No method named `{}`", name);
+}
+
+#[test]
+fn method_bodies() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for row in md.tables().method_defs() {
+    let row = row.unwrap();
+    let body = match md.method_body(&row).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    assert!(!body.code.is_empty());
+
+    if let Some(sig) = body.local_var_sig {
+      md.tables().stand_alone_sigs().read(sig).unwrap();
+    }
+
+    for section in body.sections {
+      assert!(section.kind.contains(SectionKind::EH_TABLE));
+    }
+  }
+}
+
+#[test]
+fn abstract_method() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `JsonConverter.CanConvert` is abstract.
+  assert_eq!(md.method_body(&method(&md, "CanConvert")).unwrap(), None);
+}
+
+#[test]
+fn tiny_method() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let body = md
+    .method_body(&method(&md, "SerializeObject"))
+    .unwrap()
+    .unwrap();
+
+  assert!(body.is_tiny());
+  assert_eq!(body.max_stack, 8);
+  assert_eq!(body.local_var_sig, None);
+  assert!(body.sections.is_empty());
+  // ret
+  assert_eq!(body.code.last(), Some(&0x2a));
+}
+
+#[test]
+fn no_image() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let image = md.image().unwrap();
+  let metadata = image.cor20_header().metadata;
+  let cli_data = &image.read_rva(metadata.virtual_address).unwrap()[..metadata.size as usize];
+
+  let md = Md::from_cli_data(cli_data).unwrap();
+  let row = method(&md, "SerializeObject");
+
+  assert!(md.method_body(&row).is_err());
+}
+
+#[test]
+fn exception_clauses() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let header = md.tables().header();
+
+  fn count(blocks: &[TryBlock]) -> usize {
+    blocks
+      .iter()
+      .map(|block| block.clauses.len() + count(&block.children))
+      .sum()
+  }
+
+  let mut total = 0;
+  for row in md.tables().method_defs() {
+    let body = match md.method_body(&row.unwrap()).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    let clauses = body.exception_clauses(header).unwrap();
+    for clause in &clauses {
+      assert!(clause.try_range().end as usize <= body.code.len());
+      assert!(clause.handler_range().end as usize <= body.code.len());
+
+      if let HandlerKind::Catch(ty) = clause.kind {
+        if !matches!(ty, TypeDefOrRef::TypeSpec(_)) {
+          md.type_name(ty).unwrap();
+        }
+      }
+    }
+
+    assert_eq!(count(&body.try_blocks(header).unwrap()), clauses.len());
+    total += clauses.len();
+  }
+
+  assert!(total > 0);
+}
@@ -14,7 +14,25 @@ fn modules() {
 
   assert_eq!(m.generation, 0);
   assert_string_eq(&md, m.name, "Newtonsoft.Json.dll");
-  assert_guid_eq(&md, m.mvid, "4A5B913F-2570-4581-8A19-DC2CAD19C900");
-  assert_guid_eq(&md, m.enc_id, "234A5B91-3F25-7045-818A-19DC2CAD1900");
-  assert_guid_eq(&md, m.enc_base_id, "234A5B91-3F25-7045-818A-19DC2CAD1900");
+  assert_guid_eq(&md, m.mvid, Some("915B4A23-253F-4570-818A-19DC2CAD19C9"));
+  assert_guid_eq(&md, m.enc_id, None);
+  assert_guid_eq(&md, m.enc_base_id, None);
+}
+
+#[test]
+fn guids() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let guids = md.guids().iter().collect::<Vec<_>>();
+
+  assert_eq!(guids.len(), 1);
+  assert_eq!(guids[0].0.index(), 1);
+  assert_eq!(
+    format!("{:#}", guids[0].1),
+    "{915B4A23-253F-4570-818A-19DC2CAD19C9}"
+  );
+
+  let m = first_row(md.tables().modules());
+  assert_eq!(md.guids().get(m.mvid).unwrap(), Some(guids[0].1));
 }
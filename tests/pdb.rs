@@ -0,0 +1,244 @@
+use recil::ecma335::{
+  pdb::{
+    CustomDebugInfo, LambdaClosure, LocalSlot, MetadataReferenceFlags, HASH_SHA1, HASH_SHA256,
+    LANGUAGE_CSHARP,
+  },
+  tables::{
+    HasCustomDebugInformation, LocalScopeRowId, MethodDebugInformationRowId, MethodDefRowId,
+  },
+  Md,
+};
+
+#[test]
+fn pdb_stream() {
+  let pdb = include_bytes!("./generate/bin/Debug/net7.0/tools.pdb");
+  let md = Md::from_cli_data(pdb).unwrap();
+  let stream = md.pdb().unwrap();
+
+  assert_eq!(stream.entry_point.value(), 0x0600_0018);
+  assert_eq!(stream.type_system_rows[0x02], 12);
+  assert_eq!(stream.type_system_rows[0x06], 36);
+
+  // The type system tables are in the module, only indexes into them are sized by the PDB.
+  let header = md.tables().header();
+  assert_eq!(header.rows[0x06], 0);
+  assert_eq!(header.external_rows[0x06], 36);
+  assert_eq!(header.index_rows(0x06), 36);
+  assert_eq!(md.tables().method_defs().len(), 0);
+  assert_eq!(md.tables().method_defs().into_iter().count(), 0);
+  assert!(MethodDefRowId::new(36, header).is_ok());
+  assert!(MethodDefRowId::new(37, header).is_err());
+  assert_eq!(md.tables().method_debug_information().len(), 36);
+}
+
+#[test]
+fn documents() {
+  let pdb = include_bytes!("./generate/bin/Debug/net7.0/tools.pdb");
+  let md = Md::from_cli_data(pdb).unwrap();
+  let documents = md
+    .tables()
+    .documents()
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+
+  assert_eq!(documents.len(), 12);
+  assert_eq!(
+    md.document_name(documents[1].name).unwrap(),
+    "/Users/marvin/dev/recil/tests/generate/MetadataWriter.cs"
+  );
+  assert_eq!(
+    md.document_name(documents[5].name).unwrap(),
+    "/Users/marvin/dev/recil/tests/generate/Program.cs"
+  );
+
+  for document in &documents {
+    assert!(md.document_name(document.name).is_ok());
+    assert_eq!(
+      md.guids().get(document.language).unwrap(),
+      Some(LANGUAGE_CSHARP)
+    );
+  }
+
+  assert_eq!(
+    md.guids().get(documents[0].hash_algorithm).unwrap(),
+    Some(HASH_SHA256)
+  );
+  assert_eq!(
+    md.guids().get(documents[9].hash_algorithm).unwrap(),
+    Some(HASH_SHA1)
+  );
+  assert_eq!(md.blobs().get(documents[0].hash).unwrap().len(), 32);
+}
+
+#[test]
+fn sequence_points() {
+  let pdb = include_bytes!("./generate/bin/Debug/net7.0/tools.pdb");
+  let md = Md::from_cli_data(pdb).unwrap();
+  let header = md.tables().header();
+
+  for id in 1..=md.tables().method_debug_information().len() as u32 {
+    let id = MethodDebugInformationRowId::new(id, header).unwrap();
+    assert!(md.sequence_points(id).is_ok());
+  }
+
+  // `WriteMetadataFile` in MetadataWriter.cs
+  let id = MethodDebugInformationRowId::new(16, header).unwrap();
+  let points = md.sequence_points(id).unwrap();
+  assert_eq!(points.local_signature.unwrap().row(), 5);
+  assert!(points.points.iter().all(|point| point.document.row() == 2));
+  assert!(points
+    .points
+    .windows(2)
+    .all(|w| w[0].il_offset < w[1].il_offset));
+
+  let first = points.points[0];
+  assert_eq!(
+    (first.il_offset, first.start_line, first.start_column),
+    (0, 43, 3)
+  );
+  assert_eq!((first.end_line, first.end_column), (43, 4));
+
+  let point = points.find(5).unwrap();
+  assert_eq!(
+    (point.il_offset, point.start_line, point.start_column),
+    (1, 44, 5)
+  );
+  assert_eq!((point.end_line, point.end_column), (44, 55));
+  assert!(points.find(20).is_none());
+  assert_eq!(points.find(22).unwrap().start_line, 46);
+
+  // `R` in Program.cs
+  let id = MethodDebugInformationRowId::new(25, header).unwrap();
+  let points = md.sequence_points(id).unwrap();
+  assert!(points.local_signature.is_none());
+  assert_eq!(points.points.len(), 1);
+  assert_eq!(points.find(0).unwrap().document.row(), 6);
+  assert_eq!(points.find(12).unwrap().end_column, 53);
+
+  // Methods without debug information have an empty blob
+  let id = MethodDebugInformationRowId::new(1, header).unwrap();
+  assert!(md.sequence_points(id).unwrap().points.is_empty());
+}
+
+#[test]
+fn local_scopes() {
+  let pdb = include_bytes!("./generate/bin/Debug/net7.0/tools.pdb");
+  let md = Md::from_cli_data(pdb).unwrap();
+  let header = md.tables().header();
+  let scopes = md.tables().local_scopes();
+
+  let names = |scope: LocalScopeRowId| {
+    md.tables()
+      .local_scope_variables(scope)
+      .unwrap()
+      .map(|id| {
+        let variable = md.tables().local_variables().read(id).unwrap();
+        (variable.index, md.strings().get(variable.name).unwrap())
+      })
+      .collect::<Vec<_>>()
+  };
+
+  // `WriteMetadataFile` has a nested scope for the `foreach` variable
+  let outer = LocalScopeRowId::new(11, header).unwrap();
+  let inner = LocalScopeRowId::new(12, header).unwrap();
+  assert_eq!(scopes.read(outer).unwrap().method.row(), 16);
+  assert_eq!(scopes.read(inner).unwrap().method.row(), 16);
+  assert_eq!(scopes.read(inner).unwrap().start_offset, 22);
+  assert_eq!(names(outer), [(0, "array")]);
+  assert_eq!(names(inner), [(3, "item")]);
+
+  let last = LocalScopeRowId::new(scopes.len() as u32, header).unwrap();
+  assert_eq!(names(last), []);
+  assert_eq!(md.tables().local_scope_constants(last).unwrap().count(), 0);
+
+  let state_machine = md.tables().state_machine_methods().into_iter().next();
+  let state_machine = state_machine.unwrap().unwrap();
+  assert_eq!(
+    state_machine.kickoff_method,
+    MethodDefRowId::new(22, header).unwrap()
+  );
+  assert_eq!(state_machine.move_next_method.row(), 35);
+
+  let import_scopes = md.tables().import_scopes();
+  assert_eq!(import_scopes.len(), 5);
+  assert!(import_scopes
+    .into_iter()
+    .next()
+    .unwrap()
+    .unwrap()
+    .parent
+    .is_none());
+}
+
+#[test]
+fn custom_debug_info() {
+  let pdb = include_bytes!("./generate/bin/Debug/net7.0/tools.pdb");
+  let md = Md::from_cli_data(pdb).unwrap();
+  let infos = md
+    .tables()
+    .custom_debug_information()
+    .into_iter()
+    .map(|row| {
+      let row = row.unwrap();
+      (row.parent, md.custom_debug_info(&row).unwrap())
+    })
+    .collect::<Vec<_>>();
+
+  let of = |parent: &dyn Fn(&HasCustomDebugInformation) -> bool| {
+    infos
+      .iter()
+      .filter(|(p, _)| parent(p))
+      .map(|(_, info)| info)
+      .collect::<Vec<_>>()
+  };
+
+  let module = of(&|parent| matches!(parent, HasCustomDebugInformation::Module(_)));
+  let CustomDebugInfo::CompilationOptions(options) = module[0] else {
+    panic!("{:?}", module[0])
+  };
+  assert!(options.contains(&("language", "C#")));
+  assert!(options.contains(&("output-kind", "ConsoleApplication")));
+
+  let CustomDebugInfo::CompilationMetadataReferences(references) = module[1] else {
+    panic!("{:?}", module[1])
+  };
+  assert_eq!(references.len(), 165);
+  assert_eq!(references[0].file_name, "dnlib.dll");
+  assert_eq!(references[0].file_size, 0x12_4000);
+  assert!(references[0].aliases.is_empty());
+  assert!(references
+    .iter()
+    .all(|reference| reference.flags == MetadataReferenceFlags::ASSEMBLY));
+
+  // Sources generated by the interop source generators are embedded.
+  let document =
+    of(&|parent| matches!(parent, HasCustomDebugInformation::Document(d) if d.row() == 10));
+  let CustomDebugInfo::EmbeddedSource(source) = document[0] else {
+    panic!("{:?}", document[0])
+  };
+  assert_eq!(&source[..], "\u{feff}// <auto-generated/>\n".as_bytes());
+
+  // `WriteMetadataFile`, the `foreach` synthesizes two locals between `array` and `item`.
+  let method =
+    of(&|parent| matches!(parent, HasCustomDebugInformation::MethodDef(m) if m.row() == 16));
+  let CustomDebugInfo::EncLocalSlotMap(slots) = method[0] else {
+    panic!("{:?}", method[0])
+  };
+  let kinds = slots.iter().map(|slot| match slot {
+    LocalSlot::Local { kind, .. } => Some(*kind),
+    LocalSlot::Temp => None,
+  });
+  assert!(kinds.eq([Some(0), Some(6), Some(8), Some(0)]));
+
+  // The top-level statements, their `R` local function captures nothing.
+  let method =
+    of(&|parent| matches!(parent, HasCustomDebugInformation::MethodDef(m) if m.row() == 22));
+  let CustomDebugInfo::EncLambdaAndClosureMap(map) = method[0] else {
+    panic!("{:?}", method[0])
+  };
+  assert_eq!(map.method_ordinal, Some(0));
+  assert!(map.closures.is_empty());
+  assert_eq!(map.lambdas[0].closure, LambdaClosure::Static);
+  assert!(matches!(method[1], CustomDebugInfo::Unknown(..)));
+}
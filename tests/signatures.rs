@@ -0,0 +1,266 @@
+mod common;
+
+use recil::ecma335::{
+  signatures::{SignatureKind, StandAloneSig, TypeSig},
+  tables::TypeDefOrRef,
+  Md,
+};
+
+#[test]
+fn method_def_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for method in md.tables().method_defs() {
+    let method = method.unwrap();
+    let sig = md.method_sig(method.signature).unwrap();
+
+    assert_eq!(sig.sentinel, None);
+  }
+}
+
+#[test]
+fn member_ref_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for member in md.tables().member_refs() {
+    let member = member.unwrap();
+
+    // Member references to fields use a `FieldSig`.
+    if md.blobs().get(member.signature).unwrap()[0] & 0x0f == SignatureKind::Field as u8 {
+      continue;
+    }
+
+    md.method_sig(member.signature).unwrap();
+  }
+}
+
+#[test]
+fn property_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for property in md.tables().properties() {
+    let property = property.unwrap();
+    let sig = md.property_sig(property.signature).unwrap();
+
+    assert_eq!(sig.header.kind, SignatureKind::Property);
+  }
+}
+
+#[test]
+fn serialize_object_sig() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `public static string SerializeObject(object? value)`
+  let sig = md
+    .tables()
+    .method_defs()
+    .into_iter()
+    .map(|method| method.unwrap())
+    .filter(|method| md.strings().get(method.name).unwrap() == "SerializeObject")
+    .map(|method| md.method_sig(method.signature).unwrap())
+    .find(|sig| sig.params.len() == 1)
+    .unwrap();
+
+  assert!(!sig.has_this());
+  assert_eq!(sig.header.kind, SignatureKind::Default);
+  assert_eq!(sig.generic_param_count, 0);
+  assert_eq!(sig.ret, TypeSig::String);
+  assert_eq!(sig.params, [TypeSig::Object]);
+}
+
+#[test]
+fn generic_method_sig() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `public static T? DeserializeObject<T>(string value)`
+  let sig = md
+    .tables()
+    .method_defs()
+    .into_iter()
+    .map(|method| method.unwrap())
+    .filter(|method| md.strings().get(method.name).unwrap() == "DeserializeObject")
+    .map(|method| md.method_sig(method.signature).unwrap())
+    .find(|sig| sig.generic_param_count == 1 && sig.params.len() == 1)
+    .unwrap();
+
+  assert_eq!(sig.ret, TypeSig::MVar(0));
+  assert_eq!(sig.params, [TypeSig::String]);
+}
+
+#[test]
+fn by_ref_param_sig() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // Every `out`/`ref` parameter in the assembly must decode as a managed pointer, class and value
+  // type targets must resolve in the tables.
+  let mut by_refs = 0;
+
+  for method in md.tables().method_defs() {
+    let sig = md.method_sig(method.unwrap().signature).unwrap();
+
+    for param in sig.params.iter().filter(|param| param.is_by_ref()) {
+      by_refs += 1;
+
+      let TypeSig::ByRef(ty) = param.strip_modifiers() else {
+        unreachable!()
+      };
+
+      match **ty {
+        TypeSig::Class(TypeDefOrRef::TypeRef(id))
+        | TypeSig::ValueType(TypeDefOrRef::TypeRef(id)) => {
+          md.tables().type_refs().read(id).unwrap();
+        }
+        TypeSig::Class(TypeDefOrRef::TypeDef(id))
+        | TypeSig::ValueType(TypeDefOrRef::TypeDef(id)) => {
+          md.tables().type_defs().read(id).unwrap();
+        }
+        _ => {}
+      }
+    }
+  }
+
+  assert!(by_refs > 0);
+}
+
+#[test]
+fn field_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let mut trues = Vec::new();
+
+  for field in md.tables().fields() {
+    let field = field.unwrap();
+    let sig = md.field_sig(field.signature).unwrap();
+
+    assert_eq!(sig.header.kind, SignatureKind::Field);
+
+    if md.strings().get(field.name).unwrap() == "True" {
+      trues.push(sig.ty);
+    }
+  }
+
+  // `public static readonly string True = "true";` on `JsonConvert`.
+  assert!(trues.contains(&TypeSig::String));
+}
+
+#[test]
+fn type_spec_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let mut generic_insts = 0;
+
+  for spec in md.tables().type_specs() {
+    let sig = md.type_spec_sig(spec.unwrap().signature).unwrap();
+
+    if let TypeSig::GenericInst(inst) = sig {
+      generic_insts += 1;
+      assert!(!inst.args.is_empty());
+    }
+  }
+
+  assert!(generic_insts > 0);
+}
+
+#[test]
+fn method_spec_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  for spec in md.tables().method_specs() {
+    let sig = md.method_spec_sig(spec.unwrap().instantiation).unwrap();
+
+    assert_eq!(sig.header.kind, SignatureKind::GenericInst);
+    assert!(!sig.args.is_empty());
+  }
+}
+
+#[test]
+fn stand_alone_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let mut locals = 0;
+
+  for row in md.tables().stand_alone_sigs() {
+    let row = row.unwrap();
+
+    match md.stand_alone_sig(row.signature).unwrap() {
+      StandAloneSig::Locals(sig) => {
+        locals += 1;
+
+        assert_eq!(md.local_var_sig(row.signature).unwrap(), sig);
+        assert!(sig.locals.iter().all(|local| local != &TypeSig::Void));
+      }
+      StandAloneSig::Method(sig) => assert!(sig.header.kind.is_method()),
+    }
+  }
+
+  assert!(locals > 0);
+}
+
+#[test]
+fn round_trip_sigs() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let blob = |index| md.blobs().get(index).unwrap();
+
+  for method in md.tables().method_defs() {
+    let index = method.unwrap().signature;
+    assert_eq!(md.method_sig(index).unwrap().encode().unwrap(), blob(index));
+  }
+
+  for member in md.tables().member_refs() {
+    let index = member.unwrap().signature;
+    let encoded = match blob(index)[0] & 0x0f == SignatureKind::Field as u8 {
+      true => md.field_sig(index).unwrap().encode().unwrap(),
+      false => md.method_sig(index).unwrap().encode().unwrap(),
+    };
+
+    assert_eq!(encoded, blob(index));
+  }
+
+  for field in md.tables().fields() {
+    let index = field.unwrap().signature;
+    assert_eq!(md.field_sig(index).unwrap().encode().unwrap(), blob(index));
+  }
+
+  for property in md.tables().properties() {
+    let index = property.unwrap().signature;
+    assert_eq!(
+      md.property_sig(index).unwrap().encode().unwrap(),
+      blob(index)
+    );
+  }
+
+  for row in md.tables().stand_alone_sigs() {
+    let index = row.unwrap().signature;
+    assert_eq!(
+      md.stand_alone_sig(index).unwrap().encode().unwrap(),
+      blob(index)
+    );
+  }
+
+  for spec in md.tables().type_specs() {
+    let index = spec.unwrap().signature;
+    assert_eq!(
+      md.type_spec_sig(index).unwrap().encode().unwrap(),
+      blob(index)
+    );
+  }
+
+  for spec in md.tables().method_specs() {
+    let index = spec.unwrap().instantiation;
+    assert_eq!(
+      md.method_spec_sig(index).unwrap().encode().unwrap(),
+      blob(index)
+    );
+  }
+}
@@ -0,0 +1,86 @@
+use recil::ecma335::{
+  tables::{MemberRefParent, RowIndex, TypeDefOrRef, TypeDefRowId},
+  token::{Token, TokenRow},
+  Md,
+};
+
+#[test]
+fn resolve_operand_tokens() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let mut strings = 0;
+  let mut members = 0;
+
+  for row in md.tables().method_defs() {
+    let body = match md.method_body(&row.unwrap()).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    for instruction in body.instructions() {
+      let token = match instruction.unwrap().token() {
+        Some(token) => token,
+        None => continue,
+      };
+
+      match md.resolve_token(token).unwrap() {
+        TokenRow::UserString(index, string) => {
+          assert_eq!(Token::from(index), token);
+          assert_eq!(md.user_strings().get(index).unwrap(), string);
+          strings += 1;
+        }
+        TokenRow::MemberRef(id, row) => {
+          assert_eq!(id.token(), token);
+
+          let parent = Token::from(row.class);
+          let resolved = md.resolve_token(parent).unwrap();
+          match row.class {
+            MemberRefParent::TypeRef(_) => assert!(matches!(resolved, TokenRow::TypeRef(..))),
+            MemberRefParent::TypeSpec(_) => assert!(matches!(resolved, TokenRow::TypeSpec(..))),
+            _ => {}
+          }
+
+          members += 1;
+        }
+        TokenRow::TypeDef(id, _) => assert_eq!(id.token(), token),
+        TokenRow::TypeRef(id, _) => assert_eq!(id.token(), token),
+        TokenRow::TypeSpec(id, _) => assert_eq!(id.token(), token),
+        TokenRow::Field(id, _) => assert_eq!(id.token(), token),
+        TokenRow::MethodDef(id, _) => assert_eq!(id.token(), token),
+        TokenRow::MethodSpec(id, _) => assert_eq!(id.token(), token),
+        TokenRow::StandAloneSig(id, _) => assert_eq!(id.token(), token),
+        row => panic!("Unexpected operand {:?}", row),
+      }
+    }
+  }
+
+  assert!(strings > 100);
+  assert!(members > 100);
+}
+
+#[test]
+fn resolve_coded_index_tokens() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let header = md.tables().header();
+
+  for row in md.tables().type_defs() {
+    if let Some(extends) = row.unwrap().extends {
+      assert_eq!(
+        TypeDefOrRef::from_token(extends.into(), header).unwrap(),
+        extends
+      );
+    }
+  }
+
+  // A library has no entry point.
+  assert_eq!(md.image().unwrap().cor20_header().entry_point(), None);
+  assert!(md.resolve_token(Token::from(0x0200_0000)).is_err());
+  assert!(md.resolve_token(Token::from(0x5500_0001)).is_err());
+
+  let first = TypeDefRowId::first(header).unwrap();
+  assert!(matches!(
+    md.resolve_token(first.into()).unwrap(),
+    TokenRow::TypeDef(id, _) if id == first
+  ));
+}
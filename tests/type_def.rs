@@ -1,6 +1,10 @@
 mod common;
 
-use recil::ecma335::Md;
+use common::{assert_string_eq, expected_handle, expected_rows, first_row};
+use recil::ecma335::{
+  tables::{EventMapRowId, PropertyMapRowId, RowIndex, TypeDefOrRef, TypeDefRowId},
+  Md,
+};
 
 #[test]
 fn type_defs() {
@@ -11,3 +15,115 @@ fn type_defs() {
     t.unwrap();
   }
 }
+
+#[test]
+fn type_def_extends() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let module = first_row(md.tables().type_defs());
+  assert_string_eq(&md, module.name, "<Module>");
+  assert_eq!(module.extends, None);
+
+  let expected = expected_rows("type_def");
+  assert_eq!(md.tables().type_defs().len(), expected.len());
+
+  for (actual, expected) in md.tables().type_defs().into_iter().zip(expected) {
+    let actual = actual.unwrap();
+    let extends = match actual.extends {
+      None => ("TypeDefinition", 0),
+      Some(TypeDefOrRef::TypeDef(id)) => ("TypeDefinition", id.row()),
+      Some(TypeDefOrRef::TypeRef(id)) => ("TypeReference", id.row()),
+      Some(TypeDefOrRef::TypeSpec(id)) => ("TypeSpecification", id.row()),
+    };
+
+    assert_eq!(extends, expected_handle(&expected["extends"]));
+    assert_eq!(
+      actual.name.offset(),
+      expected["name"].as_integer().unwrap() as usize
+    );
+  }
+}
+
+#[test]
+fn type_def_base_is_object() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  // `Newtonsoft.Json.JsonConvert` is a static class deriving directly from `System.Object`.
+  let convert = md
+    .tables()
+    .type_defs()
+    .into_iter()
+    .map(|t| t.unwrap())
+    .find(|t| md.strings().get(t.name).unwrap() == "JsonConvert")
+    .unwrap();
+
+  let base = match convert.extends {
+    Some(TypeDefOrRef::TypeRef(id)) => md.tables().type_refs().read(id).unwrap(),
+    extends => panic!("Unexpected base {:?}", extends),
+  };
+
+  assert_string_eq(&md, base.namespace, "System");
+  assert_string_eq(&md, base.name, "Object");
+}
+
+#[test]
+fn type_def_member_lists() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let tables = md.tables();
+  let (mut fields, mut methods, mut params) = (Vec::new(), Vec::new(), 0);
+
+  let mut index = TypeDefRowId::first(tables.header());
+  while let Some(id) = index {
+    fields.extend(tables.type_def_fields(id).unwrap().map(|id| id.row()));
+
+    for method in tables.type_def_methods(id).unwrap() {
+      let sig = md
+        .method_sig(tables.method_defs().read(method).unwrap().signature)
+        .unwrap();
+
+      // Every parameter row belongs to the method's signature, or is its return value.
+      for param in tables.method_def_params(method).unwrap() {
+        let param = tables.params().read(param).unwrap();
+        assert!(param.sequence as usize <= sig.params.len());
+        params += 1;
+      }
+
+      methods.push(method.row());
+    }
+
+    index = id.next();
+  }
+
+  // The lists partition the member tables in order.
+  assert!(fields.iter().copied().eq(1..=tables.fields().len() as u32));
+  assert!(methods
+    .iter()
+    .copied()
+    .eq(1..=tables.method_defs().len() as u32));
+  assert_eq!(params, tables.params().len());
+
+  let events = tables
+    .event_maps()
+    .into_iter()
+    .enumerate()
+    .map(|(i, _)| {
+      let id = EventMapRowId::new(i as u32 + 1, tables.header()).unwrap();
+      tables.event_map_events(id).unwrap().len()
+    })
+    .sum::<usize>();
+  assert_eq!(events, tables.events().len());
+
+  let properties = tables
+    .property_maps()
+    .into_iter()
+    .enumerate()
+    .map(|(i, _)| {
+      let id = PropertyMapRowId::new(i as u32 + 1, tables.header()).unwrap();
+      tables.property_map_properties(id).unwrap().len()
+    })
+    .sum::<usize>();
+  assert_eq!(properties, tables.properties().len());
+}
@@ -1,6 +1,7 @@
 mod common;
 
-use recil::ecma335::Md;
+use common::{expected_handle, expected_rows};
+use recil::ecma335::{tables::ResolutionScope, Md};
 
 #[test]
 fn type_refs() {
@@ -11,3 +12,33 @@ fn type_refs() {
     t.unwrap();
   }
 }
+
+#[test]
+fn type_ref_resolution_scope() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let expected = expected_rows("type_ref");
+  assert_eq!(md.tables().type_refs().len(), expected.len());
+
+  for (actual, expected) in md.tables().type_refs().into_iter().zip(expected) {
+    let actual = actual.unwrap();
+    let scope = match actual.resolution_scope {
+      None => ("ModuleDefinition", 0),
+      Some(ResolutionScope::Module(id)) => ("ModuleDefinition", id.row()),
+      Some(ResolutionScope::ModuleRef(id)) => ("ModuleReference", id.row()),
+      Some(ResolutionScope::AssemblyRef(id)) => ("AssemblyReference", id.row()),
+      Some(ResolutionScope::TypeRef(id)) => ("TypeReference", id.row()),
+    };
+
+    assert_eq!(scope, expected_handle(&expected["resolution_scope"]));
+    assert_eq!(
+      actual.name.offset(),
+      expected["name"].as_integer().unwrap() as usize
+    );
+
+    if let Some(ResolutionScope::TypeRef(id)) = actual.resolution_scope {
+      md.tables().type_refs().read(id).unwrap();
+    }
+  }
+}
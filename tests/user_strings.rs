@@ -0,0 +1,59 @@
+mod common;
+
+use recil::ecma335::{user_strings::UserStringIndex, Md};
+
+#[test]
+fn user_strings() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let strings = md
+    .user_strings()
+    .iter()
+    .take(8)
+    .map(|string| {
+      let (index, string) = string.unwrap();
+      (index.offset(), string.decode().unwrap())
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    strings,
+    [
+      (0x00, "".to_string()),
+      (0x01, "key".to_string()),
+      (0x09, "D".to_string()),
+      (0x0d, "R".to_string()),
+      (0x11, "0.0".to_string()),
+      (0x19, ".0".to_string()),
+      (
+        0x1f,
+        "Delimiter must be a single or double quote.".to_string()
+      ),
+      (0x77, "delimiter".to_string()),
+    ]
+  );
+
+  for string in md.user_strings() {
+    let (index, string) = string.unwrap();
+
+    assert_eq!(md.user_strings().get(index).unwrap(), string);
+  }
+}
+
+#[test]
+fn user_string_token() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+
+  let index = UserStringIndex::from_token(0x7000001f).unwrap();
+  let string = md.user_strings().get(index).unwrap();
+
+  assert_eq!(index.token(), 0x7000001f);
+  assert!(!string.has_special_chars());
+  assert_eq!(
+    string.to_string(),
+    "Delimiter must be a single or double quote."
+  );
+  assert!(UserStringIndex::from_token(0x0600001f).is_err());
+}
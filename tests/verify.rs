@@ -0,0 +1,220 @@
+use recil::{
+  ecma335::{
+    body::{MethodBody, MethodHeaderFlags},
+    signatures::TypeSig,
+    tables::{MethodDefRowId, RowIndex},
+    Md,
+  },
+  il::{StackType, Verification, VerificationError, VerificationErrorKind, Verifier},
+};
+
+#[test]
+fn verify_methods() {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let verifier = Verifier::new(&md).unwrap();
+  let mut verified = 0;
+  let mut errors = Vec::new();
+
+  let mut index = MethodDefRowId::first(md.tables().header());
+  while let Some(id) = index {
+    index = id.next();
+
+    let row = md.tables().method_defs().read(id).unwrap();
+    let body = match md.method_body(&row).unwrap() {
+      Some(body) => body,
+      None => continue,
+    };
+
+    let verification = verifier.verify_body(id, &body).unwrap();
+
+    // The compiler computes `.maxstack` from the same stack transitions.
+    assert!(verification.max_height() <= body.max_stack as usize);
+    assert_eq!(verification.stack_before(0), Some(&[][..]));
+
+    for error in verification.errors() {
+      errors.push(format!(
+        "{}: {}",
+        md.strings().get(row.name).unwrap(),
+        error
+      ));
+    }
+
+    verified += 1;
+  }
+
+  // The C# compiler only emits verifiable code outside of `unsafe` blocks.
+  assert!(verified > 1000);
+  assert!(errors.is_empty(), "{}", errors.join("\n"));
+}
+
+/// Verifies code as the body of a `static void ()` method.
+fn verify(code: &[u8], max_stack: u16) -> Verification {
+  let pe = include_bytes!("./inputs/Newtonsoft.Json.dll");
+  let md = Md::parse_from_pe(pe).unwrap().unwrap();
+  let verifier = Verifier::new(&md).unwrap();
+
+  let mut index = MethodDefRowId::first(md.tables().header());
+  let id = loop {
+    let id = index.unwrap();
+    let row = md.tables().method_defs().read(id).unwrap();
+    let sig = md.method_sig(row.signature).unwrap();
+
+    if !sig.has_this() && sig.params.is_empty() && sig.ret == TypeSig::Void {
+      break id;
+    }
+
+    index = id.next();
+  };
+
+  let body = MethodBody {
+    flags: MethodHeaderFlags::TINY_FORMAT,
+    max_stack,
+    local_var_sig: None,
+    code,
+    sections: Vec::new(),
+  };
+
+  verifier.verify_body(id, &body).unwrap()
+}
+
+fn errors(verification: &Verification) -> Vec<(u32, VerificationErrorKind)> {
+  verification
+    .errors()
+    .iter()
+    .map(|VerificationError { offset, kind }| (*offset, *kind))
+    .collect()
+}
+
+#[test]
+fn verify_stack_types() {
+  #[rustfmt::skip]
+  let code = [
+    // IL_0000: ldc.i4.1
+    0x17,
+    // IL_0001: conv.i8
+    0x6a,
+    // IL_0002: ldc.i8 2
+    0x21, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // IL_000b: add
+    0x58,
+    // IL_000c: conv.r8
+    0x6c,
+    // IL_000d: pop
+    0x26,
+    // IL_000e: ret
+    0x2a,
+  ];
+
+  let verification = verify(&code, 2);
+  assert!(verification.is_verifiable());
+  assert_eq!(verification.max_height(), 2);
+  assert_eq!(
+    verification.stack_before(0x01),
+    Some(&[StackType::Int32][..])
+  );
+  assert_eq!(
+    verification.stack_before(0x0b),
+    Some(&[StackType::Int64, StackType::Int64][..])
+  );
+  assert_eq!(
+    verification.stack_before(0x0d),
+    Some(&[StackType::Float][..])
+  );
+}
+
+#[test]
+fn verify_unverifiable() {
+  #[rustfmt::skip]
+  let merge = [
+    // IL_0000: ldc.i4.0
+    0x16,
+    // IL_0001: brtrue.s IL_0004
+    0x2d, 0x01,
+    // IL_0003: ldnull
+    0x14,
+    // IL_0004: ret
+    0x2a,
+  ];
+
+  assert_eq!(
+    errors(&verify(&merge, 8)),
+    [(
+      0x03,
+      VerificationErrorKind::StackHeightMismatch {
+        expected: 0,
+        found: 1
+      }
+    )]
+  );
+
+  #[rustfmt::skip]
+  let underflow = [
+    // IL_0000: pop
+    0x26,
+    // IL_0001: ret
+    0x2a,
+  ];
+
+  assert_eq!(
+    errors(&verify(&underflow, 8)),
+    [(0x00, VerificationErrorKind::StackUnderflow)]
+  );
+
+  #[rustfmt::skip]
+  let overflow = [
+    // IL_0000: ldc.i4.0
+    0x16,
+    // IL_0001: ldc.i4.0
+    0x16,
+    // IL_0002: pop
+    0x26,
+    // IL_0003: pop
+    0x26,
+    // IL_0004: ret
+    0x2a,
+  ];
+
+  assert_eq!(
+    errors(&verify(&overflow, 1)),
+    [(0x01, VerificationErrorKind::StackOverflow { max_stack: 1 })]
+  );
+
+  #[rustfmt::skip]
+  let operands = [
+    // IL_0000: ldnull
+    0x14,
+    // IL_0001: ldc.i4.1
+    0x17,
+    // IL_0002: add
+    0x58,
+    // IL_0003: pop
+    0x26,
+    // IL_0004: ret
+    0x2a,
+  ];
+
+  assert_eq!(
+    errors(&verify(&operands, 8)),
+    [(
+      0x02,
+      VerificationErrorKind::IncompatibleOperands {
+        left: StackType::Object,
+        right: StackType::Int32
+      }
+    )]
+  );
+
+  #[rustfmt::skip]
+  let ret = [
+    // IL_0000: ldc.i4.0
+    0x16,
+    // IL_0001: ret
+    0x2a,
+  ];
+
+  assert_eq!(
+    errors(&verify(&ret, 8)),
+    [(0x01, VerificationErrorKind::NonEmptyStack)]
+  );
+}